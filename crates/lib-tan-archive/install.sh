@@ -0,0 +1,5 @@
+# PROFILE="debug"
+PROFILE="release"
+
+cargo b --$PROFILE
+cp ../../target/$PROFILE/libtanarchive.so $TAN_ROOT/@std/archive/.
@@ -0,0 +1,776 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use tan::{
+    context::Context,
+    error::Error,
+    expr::{annotate_type, Expr},
+    util::{
+        args::{unpack_arg, unpack_array_arg, unpack_stringable_arg},
+        expect_lock_read, expect_lock_write,
+        module_util::require_module,
+    },
+};
+use tanutil::{args::options_from_args, buffer::buffer_expr};
+use zip::{result::ZipError, write::SimpleFileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+// #todo Support more formats, e.g. tar.zst, tar.xz, 7z.
+// #todo Support reading archives from a Buffer.
+// #todo Preserve file permissions and modification times.
+// #todo Consider returning Paths instead of Strings.
+
+// #insight The format is inferred from the archive extension, use the
+// `:format` option to override it.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ArchiveFormat {
+    Tar,
+    TarGz,
+    Zip,
+}
+
+fn archive_format_from_name(name: &str) -> Option<ArchiveFormat> {
+    match name {
+        "tar" => Some(ArchiveFormat::Tar),
+        "tar-gz" | "tgz" => Some(ArchiveFormat::TarGz),
+        "zip" => Some(ArchiveFormat::Zip),
+        _ => None,
+    }
+}
+
+fn archive_format_from_path(path: &str) -> Option<ArchiveFormat> {
+    let path = path.to_lowercase();
+
+    if path.ends_with(".tar.gz") || path.ends_with(".tgz") {
+        Some(ArchiveFormat::TarGz)
+    } else if path.ends_with(".tar") {
+        Some(ArchiveFormat::Tar)
+    } else if path.ends_with(".zip") {
+        Some(ArchiveFormat::Zip)
+    } else {
+        None
+    }
+}
+
+fn archive_format_from_options(
+    path: &str,
+    options: &HashMap<String, Expr>,
+) -> Result<ArchiveFormat, Error> {
+    if let Some(format) = options.get("format") {
+        let Some(name) = format.as_stringable() else {
+            return Err(Error::invalid_arguments(
+                "`format` option should be a String",
+                format.range(),
+            ));
+        };
+
+        return archive_format_from_name(name).ok_or_else(|| {
+            Error::invalid_arguments(&format!("unsupported archive format `{name}`"), None)
+        });
+    }
+
+    archive_format_from_path(path).ok_or_else(|| {
+        Error::invalid_arguments(
+            &format!("cannot infer the archive format of `{path}`, use the `format` option"),
+            None,
+        )
+    })
+}
+
+fn error_from_zip_error(error: ZipError) -> Error {
+    match error {
+        ZipError::Io(io_error) => io_error.into(),
+        _ => Error::general(&format!("invalid zip archive: {error}")),
+    }
+}
+
+// #insight Entry names always use `/` and are relative.
+/// Computes the archive entry name of a path.
+fn entry_name(path: &Path) -> String {
+    path.components()
+        .filter_map(|component| match component {
+            std::path::Component::Normal(name) => Some(name.to_string_lossy()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn open_tar_archive(path: &str, format: ArchiveFormat) -> io::Result<tar::Archive<Box<dyn Read>>> {
+    let file = File::open(path)?;
+
+    let reader: Box<dyn Read> = if format == ArchiveFormat::TarGz {
+        Box::new(GzDecoder::new(file))
+    } else {
+        Box::new(file)
+    };
+
+    Ok(tar::Archive::new(reader))
+}
+
+fn open_zip_archive(path: &str) -> Result<ZipArchive<File>, Error> {
+    let file = File::open(path)?;
+    ZipArchive::new(file).map_err(error_from_zip_error)
+}
+
+fn entry_expr(path: String, size: u64, is_directory: bool) -> Expr {
+    let mut map = HashMap::new();
+    map.insert("path".to_string(), Expr::String(path));
+    map.insert("size".to_string(), Expr::Int(size as i64));
+    map.insert("is-directory".to_string(), Expr::Bool(is_directory));
+    annotate_type(Expr::map(map), "Archive-Entry")
+}
+
+/// Copies the contents of an archive entry to a writer, returns the number of
+/// bytes copied, or None if the entry is not found.
+fn copy_entry(
+    path: &str,
+    format: ArchiveFormat,
+    name: &str,
+    writer: &mut impl Write,
+) -> Result<Option<u64>, Error> {
+    if format == ArchiveFormat::Zip {
+        let mut archive = open_zip_archive(path)?;
+        let mut entry = match archive.by_name(name) {
+            Ok(entry) => entry,
+            Err(ZipError::FileNotFound) => return Ok(None),
+            Err(error) => return Err(error_from_zip_error(error)),
+        };
+        return Ok(Some(io::copy(&mut entry, writer)?));
+    }
+
+    let mut archive = open_tar_archive(path, format)?;
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if entry_name(&entry.path()?) == name {
+            return Ok(Some(io::copy(&mut entry, writer)?));
+        }
+    }
+
+    Ok(None)
+}
+
+enum ArchiveBuilder {
+    Tar(tar::Builder<File>),
+    TarGz(tar::Builder<GzEncoder<File>>),
+    Zip(Box<ZipWriter<File>>, SimpleFileOptions),
+}
+
+struct ArchiveWriter {
+    builder: ArchiveBuilder,
+    /// The canonical path of the archive, it is skipped when appending
+    /// directories, e.g. when the archive is written into a source directory.
+    path: PathBuf,
+}
+
+fn tar_header(size: u64, mode: u32) -> tar::Header {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(size);
+    header.set_mode(mode);
+    let mtime = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    header.set_mtime(mtime);
+    header.set_cksum();
+    header
+}
+
+fn tar_append_data<W: Write>(
+    builder: &mut tar::Builder<W>,
+    name: &str,
+    size: u64,
+    data: impl Read,
+) -> io::Result<()> {
+    let mut header = tar_header(size, 0o644);
+    builder.append_data(&mut header, name, data)
+}
+
+fn tar_append_directory<W: Write>(builder: &mut tar::Builder<W>, name: &str) -> io::Result<()> {
+    let mut header = tar_header(0, 0o755);
+    header.set_entry_type(tar::EntryType::Directory);
+    header.set_cksum();
+    builder.append_data(&mut header, format!("{name}/"), io::empty())
+}
+
+impl ArchiveWriter {
+    fn create(path: &str, format: ArchiveFormat, level: Option<u32>) -> io::Result<Self> {
+        let file = File::create(path)?;
+        let canonical_path = Path::new(path).canonicalize()?;
+
+        let builder = match format {
+            ArchiveFormat::Tar => ArchiveBuilder::Tar(tar::Builder::new(file)),
+            ArchiveFormat::TarGz => {
+                let level = level.map(Compression::new).unwrap_or_default();
+                ArchiveBuilder::TarGz(tar::Builder::new(GzEncoder::new(file, level)))
+            }
+            ArchiveFormat::Zip => {
+                let options = SimpleFileOptions::default()
+                    .compression_method(CompressionMethod::Deflated)
+                    .compression_level(level.map(|level| level as i64));
+                ArchiveBuilder::Zip(Box::new(ZipWriter::new(file)), options)
+            }
+        };
+
+        Ok(Self {
+            builder,
+            path: canonical_path,
+        })
+    }
+
+    fn append_data(&mut self, name: &str, size: u64, mut data: impl Read) -> Result<(), Error> {
+        match &mut self.builder {
+            ArchiveBuilder::Tar(builder) => tar_append_data(builder, name, size, data)?,
+            ArchiveBuilder::TarGz(builder) => tar_append_data(builder, name, size, data)?,
+            ArchiveBuilder::Zip(writer, options) => {
+                writer
+                    .start_file(name, *options)
+                    .map_err(error_from_zip_error)?;
+                io::copy(&mut data, writer)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn append_directory(&mut self, name: &str) -> Result<(), Error> {
+        match &mut self.builder {
+            ArchiveBuilder::Tar(builder) => tar_append_directory(builder, name)?,
+            ArchiveBuilder::TarGz(builder) => tar_append_directory(builder, name)?,
+            ArchiveBuilder::Zip(writer, options) => writer
+                .add_directory(name, *options)
+                .map_err(error_from_zip_error)?,
+        }
+
+        Ok(())
+    }
+
+    fn is_archive_path(&self, path: &Path) -> bool {
+        path.file_name() == self.path.file_name()
+            && path.canonicalize().is_ok_and(|path| path == self.path)
+    }
+
+    /// Appends a file or (recursively) a directory from the file-system,
+    /// under the given entry name.
+    fn append_path(&mut self, path: &Path, name: &str) -> Result<(), Error> {
+        for entry in walkdir::WalkDir::new(path).sort_by_file_name() {
+            let entry = entry.map_err(io::Error::from)?;
+            let relative_name = entry_name(entry.path().strip_prefix(path).unwrap_or(path));
+
+            let name = if relative_name.is_empty() {
+                name.to_string()
+            } else if name.is_empty() {
+                relative_name
+            } else {
+                format!("{name}/{relative_name}")
+            };
+
+            // #insight An empty name denotes the archive root, it has no entry.
+            if name.is_empty() {
+                continue;
+            }
+
+            if entry.file_type().is_dir() {
+                self.append_directory(&name)?;
+            } else if self.is_archive_path(entry.path()) {
+                continue;
+            } else {
+                let file = File::open(entry.path())?;
+                let size = file.metadata()?.len();
+                self.append_data(&name, size, file)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn finish(self) -> Result<(), Error> {
+        match self.builder {
+            ArchiveBuilder::Tar(builder) => {
+                builder.into_inner()?;
+            }
+            ArchiveBuilder::TarGz(builder) => {
+                builder.into_inner()?.finish()?;
+            }
+            ArchiveBuilder::Zip(writer, _) => {
+                writer.finish().map_err(error_from_zip_error)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn level_from_options(options: &HashMap<String, Expr>) -> Result<Option<u32>, Error> {
+    let Some(level) = options.get("level") else {
+        return Ok(None);
+    };
+
+    match level.as_int() {
+        Some(level) if (0..=9).contains(&level) => Ok(Some(level as u32)),
+        _ => Err(Error::invalid_arguments(
+            "`level` option should be an Int between 0 and 9",
+            level.range(),
+        )),
+    }
+}
+
+/// Creates an archive from files and directories, directories are added
+/// recursively.
+/// ```tan
+/// (archive/create "dist.tar.gz" ["dist"] {:base-dir "dist"})
+/// (archive/create "fixtures.zip" ["a.json" "b.json"] {:level 9})
+/// ```
+pub fn archive_create(args: &[Expr]) -> Result<Expr, Error> {
+    let path = unpack_stringable_arg(args, 0, "path")?;
+    let sources = unpack_array_arg(args, 1, "sources")?;
+    let options = options_from_args(args, 2);
+
+    let format = archive_format_from_options(path, &options)?;
+    let level = level_from_options(&options)?;
+    let base_dir = options.get("base-dir").and_then(|x| x.as_stringable());
+
+    let mut writer = ArchiveWriter::create(path, format, level)?;
+
+    for source in sources.iter() {
+        let Some(source_path) = source.as_stringable() else {
+            return Err(Error::invalid_arguments(
+                &format!("source `{source}` should be a String or Path"),
+                source.range(),
+            ));
+        };
+
+        let source_path = Path::new(source_path);
+
+        let name = match base_dir {
+            Some(base_dir) => entry_name(source_path.strip_prefix(base_dir).unwrap_or(source_path)),
+            None => entry_name(source_path),
+        };
+
+        if let Err(mut error) = writer.append_path(source_path, &name) {
+            error.push_note(
+                &format!("while archiving `{}`", source_path.display()),
+                source.range(),
+            );
+            return Err(error);
+        }
+    }
+
+    writer.finish()?;
+
+    // #todo What is a good return value?
+    Ok(Expr::None)
+}
+
+/// Extracts all entries of an archive into a directory.
+/// ```tan
+/// (archive/extract "fixtures.tar.gz" "tmp/fixtures")
+/// ```
+pub fn archive_extract(args: &[Expr]) -> Result<Expr, Error> {
+    let path = unpack_stringable_arg(args, 0, "path")?;
+    let target_dir = unpack_stringable_arg(args, 1, "target-dir")?;
+    let options = options_from_args(args, 2);
+
+    let format = archive_format_from_options(path, &options)?;
+
+    // #insight Both implementations reject entries that escape the target directory.
+    if format == ArchiveFormat::Zip {
+        let mut archive = open_zip_archive(path)?;
+        archive.extract(target_dir).map_err(error_from_zip_error)?;
+    } else {
+        let mut archive = open_tar_archive(path, format)?;
+        archive.unpack(target_dir)?;
+    }
+
+    // #todo What is a good return value?
+    Ok(Expr::None)
+}
+
+/// Lists the entries of an archive.
+/// ```tan
+/// (for (entry (archive/list "dist.zip"))
+///     (writeln (entry :path) " " (entry :size))
+/// )
+/// ```
+pub fn archive_list(args: &[Expr]) -> Result<Expr, Error> {
+    let path = unpack_stringable_arg(args, 0, "path")?;
+    let options = options_from_args(args, 1);
+
+    let format = archive_format_from_options(path, &options)?;
+
+    let mut entries = Vec::new();
+
+    if format == ArchiveFormat::Zip {
+        let mut archive = open_zip_archive(path)?;
+        for i in 0..archive.len() {
+            let entry = archive.by_index(i).map_err(error_from_zip_error)?;
+            entries.push(entry_expr(
+                entry.name().trim_end_matches('/').to_string(),
+                entry.size(),
+                entry.is_dir(),
+            ));
+        }
+    } else {
+        let mut archive = open_tar_archive(path, format)?;
+        for entry in archive.entries()? {
+            let entry = entry?;
+            entries.push(entry_expr(
+                entry_name(&entry.path()?),
+                entry.header().size()?,
+                entry.header().entry_type().is_dir(),
+            ));
+        }
+    }
+
+    Ok(Expr::array(entries))
+}
+
+/// Reads an archive entry into a Buffer, returns None if the entry is not found.
+/// ```tan
+/// (let data (archive/read-entry "fixtures.zip" "users.json"))
+/// ```
+pub fn archive_read_entry(args: &[Expr]) -> Result<Expr, Error> {
+    let path = unpack_stringable_arg(args, 0, "path")?;
+    let name = unpack_stringable_arg(args, 1, "name")?;
+    let options = options_from_args(args, 2);
+
+    let format = archive_format_from_options(path, &options)?;
+
+    let mut bytes: Vec<u8> = Vec::new();
+
+    match copy_entry(path, format, name, &mut bytes)? {
+        Some(_) => Ok(buffer_expr(bytes)),
+        None => Ok(Expr::None),
+    }
+}
+
+/// Streams an archive entry into a File, returns the number of bytes written.
+/// ```tan
+/// (let file (fs/create "users.json"))
+/// (archive/extract-entry "fixtures.zip" "users.json" file)
+/// ```
+pub fn archive_extract_entry(args: &[Expr]) -> Result<Expr, Error> {
+    let path = unpack_stringable_arg(args, 0, "path")?;
+    let name = unpack_stringable_arg(args, 1, "name")?;
+    let file = unpack_arg(args, 2, "file")?;
+    let options = options_from_args(args, 3);
+
+    let format = archive_format_from_options(path, &options)?;
+
+    let Expr::ForeignMut(file) = file.unpack() else {
+        return Err(Error::invalid_arguments("invalid File", file.range()));
+    };
+
+    let file = expect_lock_write(file);
+
+    let Some(mut file) = file.downcast_ref::<File>() else {
+        return Err(Error::invalid_arguments("invalid File", args[2].range()));
+    };
+
+    match copy_entry(path, format, name, &mut file)? {
+        Some(count) => Ok(Expr::Int(count as i64)),
+        None => Err(Error::invalid_arguments(
+            &format!("entry `{name}` not found in `{path}`"),
+            args[1].range(),
+        )),
+    }
+}
+
+// #insight Use the Writer to stream entries into an archive.
+/// Creates an archive Writer.
+/// ```tan
+/// (let writer (archive/Writer "out.zip" {:level 9}))
+/// (archive/add writer "index.html" html-buffer)
+/// (archive/add writer "style.css" (fs/open "style.css" {:read true}))
+/// (archive/add writer "images" "public/images")
+/// (archive/finish writer)
+/// ```
+pub fn archive_writer_new(args: &[Expr]) -> Result<Expr, Error> {
+    let path = unpack_stringable_arg(args, 0, "path")?;
+    let options = options_from_args(args, 1);
+
+    let format = archive_format_from_options(path, &options)?;
+    let level = level_from_options(&options)?;
+
+    let writer = ArchiveWriter::create(path, format, level)?;
+
+    // #insight The writer is consumed by `finish`.
+    let expr = Expr::ForeignMut(Arc::new(RwLock::new(Some(writer))));
+
+    Ok(annotate_type(expr, "Archive-Writer"))
+}
+
+/// Adds an entry from a Buffer, a File, or a file-system path.
+pub fn archive_writer_add(args: &[Expr]) -> Result<Expr, Error> {
+    let writer = unpack_arg(args, 0, "writer")?;
+    let name = unpack_stringable_arg(args, 1, "name")?;
+    let data = unpack_arg(args, 2, "data")?;
+
+    let Expr::ForeignMut(writer) = writer.unpack() else {
+        return Err(Error::invalid_arguments(
+            "invalid Archive-Writer",
+            writer.range(),
+        ));
+    };
+
+    let mut writer = expect_lock_write(writer);
+
+    let Some(Some(writer)) = writer.downcast_mut::<Option<ArchiveWriter>>() else {
+        return Err(Error::invalid_arguments(
+            "invalid or finished Archive-Writer",
+            args[0].range(),
+        ));
+    };
+
+    match data.unpack() {
+        Expr::Buffer(length, bytes) => {
+            let bytes = expect_lock_read(bytes);
+            writer.append_data(name, *length as u64, &bytes[..*length])?;
+        }
+        Expr::ForeignMut(file) => {
+            let mut file = expect_lock_write(file);
+            let Some(file) = file.downcast_mut::<File>() else {
+                return Err(Error::invalid_arguments("invalid File", data.range()));
+            };
+            // #insight The whole file is added, regardless of the current
+            // position, the entry size must match the bytes written.
+            file.seek(SeekFrom::Start(0))?;
+            let size = file.metadata()?.len();
+            writer.append_data(name, size, file)?;
+        }
+        _ => {
+            let Some(path) = data.as_stringable() else {
+                return Err(Error::invalid_arguments(
+                    "`data` argument should be a Buffer, a File, or a Path",
+                    data.range(),
+                ));
+            };
+            writer.append_path(Path::new(path), name)?;
+        }
+    }
+
+    Ok(Expr::None)
+}
+
+/// Finishes an archive Writer, no entries can be added afterwards.
+pub fn archive_writer_finish(args: &[Expr]) -> Result<Expr, Error> {
+    let writer = unpack_arg(args, 0, "writer")?;
+
+    let Expr::ForeignMut(writer) = writer.unpack() else {
+        return Err(Error::invalid_arguments(
+            "invalid Archive-Writer",
+            writer.range(),
+        ));
+    };
+
+    let mut writer = expect_lock_write(writer);
+
+    let Some(writer) = writer.downcast_mut::<Option<ArchiveWriter>>() else {
+        return Err(Error::invalid_arguments(
+            "invalid Archive-Writer",
+            args[0].range(),
+        ));
+    };
+
+    let Some(writer) = writer.take() else {
+        return Err(Error::invalid_arguments(
+            "Archive-Writer is already finished",
+            args[0].range(),
+        ));
+    };
+
+    writer.finish()?;
+
+    Ok(Expr::None)
+}
+
+pub fn import_lib_archive(context: &mut Context) {
+    let module = require_module("archive", context);
+
+    module.insert_invocable("create", Expr::foreign_func(&archive_create));
+    module.insert_invocable("extract", Expr::foreign_func(&archive_extract));
+    module.insert_invocable("list", Expr::foreign_func(&archive_list));
+
+    // #todo Consider `read` and `extract` overloads instead.
+    module.insert_invocable("read-entry", Expr::foreign_func(&archive_read_entry));
+    module.insert_invocable("extract-entry", Expr::foreign_func(&archive_extract_entry));
+
+    // #todo Consider `Archive-Writer`.
+    module.insert_invocable("Writer", Expr::foreign_func(&archive_writer_new));
+    module.insert_invocable("add", Expr::foreign_func(&archive_writer_add));
+    module.insert_invocable("finish", Expr::foreign_func(&archive_writer_finish));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        fs::{self, File},
+        io::Read,
+        path::{Path, PathBuf},
+        sync::{Arc, RwLock},
+    };
+
+    use tan::expr::Expr;
+
+    use crate::archive::{
+        archive_create, archive_extract, archive_format_from_path, archive_list,
+        archive_writer_add, archive_writer_finish, archive_writer_new, entry_name, ArchiveFormat,
+    };
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("tan-archive-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn path_arg(path: &Path) -> Expr {
+        Expr::string(path.to_string_lossy())
+    }
+
+    #[test]
+    fn archive_format_from_path_usage() {
+        assert_eq!(
+            archive_format_from_path("dist.tar.gz"),
+            Some(ArchiveFormat::TarGz)
+        );
+        assert_eq!(
+            archive_format_from_path("dist.TGZ"),
+            Some(ArchiveFormat::TarGz)
+        );
+        assert_eq!(
+            archive_format_from_path("dist.tar"),
+            Some(ArchiveFormat::Tar)
+        );
+        assert_eq!(
+            archive_format_from_path("dist.zip"),
+            Some(ArchiveFormat::Zip)
+        );
+        assert_eq!(archive_format_from_path("dist.rar"), None);
+    }
+
+    #[test]
+    fn entry_name_usage() {
+        assert_eq!(
+            entry_name(Path::new("./dist/index.html")),
+            "dist/index.html"
+        );
+        assert_eq!(entry_name(Path::new("/tmp/dist/")), "tmp/dist");
+        assert_eq!(entry_name(Path::new(".")), "");
+    }
+
+    #[test]
+    fn create_list_extract_round_trip() {
+        for extension in ["tar", "tar.gz", "zip"] {
+            let dir = temp_dir(extension);
+            let source = dir.join("site");
+            fs::create_dir_all(source.join("css")).unwrap();
+            fs::write(source.join("index.html"), "<h1>Hello</h1>").unwrap();
+            fs::write(source.join("css/style.css"), "h1 { color: red }").unwrap();
+
+            let archive = dir.join(format!("site.{extension}"));
+            archive_create(&[
+                path_arg(&archive),
+                Expr::array(vec![path_arg(&source)]),
+                Expr::map(HashMap::from([("base-dir".to_string(), path_arg(&dir))])),
+            ])
+            .unwrap();
+
+            let entries = archive_list(&[path_arg(&archive)]).unwrap();
+            let entries = entries.as_array().unwrap();
+            let mut names: Vec<String> = entries
+                .iter()
+                .map(|entry| {
+                    let entry = entry.as_map().unwrap();
+                    entry["path"].as_string().unwrap().to_string()
+                })
+                .collect();
+            names.sort();
+            assert_eq!(
+                names,
+                ["site", "site/css", "site/css/style.css", "site/index.html"]
+            );
+
+            let target = dir.join("out");
+            archive_extract(&[path_arg(&archive), path_arg(&target)]).unwrap();
+            assert_eq!(
+                fs::read_to_string(target.join("site/index.html")).unwrap(),
+                "<h1>Hello</h1>"
+            );
+            assert_eq!(
+                fs::read_to_string(target.join("site/css/style.css")).unwrap(),
+                "h1 { color: red }"
+            );
+
+            fs::remove_dir_all(&dir).unwrap();
+        }
+    }
+
+    #[test]
+    fn writer_add_file_usage() {
+        for extension in ["tar", "zip"] {
+            let dir = temp_dir(&format!("writer-{extension}"));
+            let data_path = dir.join("data.txt");
+            fs::write(&data_path, "0123456789").unwrap();
+
+            // The File is already read from, the whole file is still added.
+            let mut file = File::open(&data_path).unwrap();
+            let mut prefix = [0; 4];
+            file.read_exact(&mut prefix).unwrap();
+            let file = Expr::ForeignMut(Arc::new(RwLock::new(file)));
+
+            let archive = dir.join(format!("data.{extension}"));
+            let writer = archive_writer_new(&[path_arg(&archive)]).unwrap();
+            archive_writer_add(&[writer.clone(), Expr::string("data.txt"), file]).unwrap();
+            archive_writer_finish(&[writer]).unwrap();
+
+            let target = dir.join("out");
+            archive_extract(&[path_arg(&archive), path_arg(&target)]).unwrap();
+            assert_eq!(
+                fs::read_to_string(target.join("data.txt")).unwrap(),
+                "0123456789"
+            );
+
+            fs::remove_dir_all(&dir).unwrap();
+        }
+    }
+
+    #[test]
+    fn create_excludes_the_archive_itself() {
+        for extension in ["tar", "zip"] {
+            let dir = temp_dir(&format!("self-{extension}"));
+            fs::write(dir.join("index.html"), "<h1>Hello</h1>").unwrap();
+
+            // The archive is written into the source directory.
+            let archive = dir.join(format!("site.{extension}"));
+            archive_create(&[
+                path_arg(&archive),
+                Expr::array(vec![path_arg(&dir)]),
+                Expr::map(HashMap::from([("base-dir".to_string(), path_arg(&dir))])),
+            ])
+            .unwrap();
+
+            let entries = archive_list(&[path_arg(&archive)]).unwrap();
+            let entries = entries.as_array().unwrap();
+            let names: Vec<String> = entries
+                .iter()
+                .map(|entry| {
+                    let entry = entry.as_map().unwrap();
+                    entry["path"].as_string().unwrap().to_string()
+                })
+                .collect();
+            assert_eq!(names, ["index.html"]);
+
+            fs::remove_dir_all(&dir).unwrap();
+        }
+    }
+}
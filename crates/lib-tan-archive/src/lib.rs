@@ -0,0 +1,10 @@
+use archive::import_lib_archive;
+use tan::context::Context;
+
+pub mod archive;
+
+// #todo Find a good name for this: considere import_*, link_*
+#[no_mangle]
+pub fn install_foreign_dyn_lib(context: &mut Context) {
+    import_lib_archive(context);
+}
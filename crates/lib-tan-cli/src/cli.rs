@@ -0,0 +1,786 @@
+use std::{collections::HashMap, io::Write};
+
+use tan::{
+    context::Context,
+    error::Error,
+    expr::{format_value, Expr},
+    util::{
+        args::{unpack_arg, unpack_array_arg, unpack_map_arg},
+        module_util::require_module,
+    },
+};
+
+// #insight
+// The spec is a Map, flags, options and commands are Maps keyed by name,
+// positionals are an Array, to preserve their order:
+//
+// (let spec {
+//     :name "deploy"
+//     :description "Deploys the site"
+//     :flags {:verbose {:short "v" :help "Verbose output"}}
+//     :options {:port {:short "p" :type :int :default 8000 :help "The port"}}
+//     :positionals [{:name "target" :required true :help "The target host"}]
+//     :commands {:rollback {:description "Rolls back the last deploy"}}
+// })
+
+// #insight
+// Like most argument parsers, the first argument is the program (script) and
+// is skipped, so `(process/args)` can be passed as-is.
+
+// #todo Support environment variable fallbacks for options.
+// #todo Support :choices for options.
+// #todo Consider `cli/Parser` with a cached spec.
+
+/// The exit code for usage errors, as used by bash builtins and clap (BSD
+/// `sysexits.h` uses 64 instead).
+const USAGE_ERROR_EXIT_CODE: i32 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ValueType {
+    String,
+    Int,
+    Float,
+}
+
+impl ValueType {
+    fn placeholder(self) -> &'static str {
+        match self {
+            ValueType::String => "<VALUE>",
+            ValueType::Int => "<INT>",
+            ValueType::Float => "<FLOAT>",
+        }
+    }
+
+    fn parse(self, value: &str) -> Option<Expr> {
+        match self {
+            ValueType::String => Some(Expr::string(value)),
+            ValueType::Int => value.parse().ok().map(Expr::Int),
+            ValueType::Float => value.parse().ok().map(Expr::Float),
+        }
+    }
+}
+
+struct FlagSpec {
+    name: String,
+    short: Option<char>,
+    help: String,
+}
+
+struct OptionSpec {
+    name: String,
+    short: Option<char>,
+    help: String,
+    value_type: ValueType,
+    default: Option<Expr>,
+    required: bool,
+    multiple: bool,
+}
+
+struct PositionalSpec {
+    name: String,
+    help: String,
+    value_type: ValueType,
+    default: Option<Expr>,
+    required: bool,
+    variadic: bool,
+}
+
+#[derive(Default)]
+struct Spec {
+    name: String,
+    description: String,
+    flags: Vec<FlagSpec>,
+    options: Vec<OptionSpec>,
+    positionals: Vec<PositionalSpec>,
+    commands: Vec<Spec>,
+}
+
+enum Outcome {
+    Values(HashMap<String, Expr>),
+    Help(String),
+}
+
+/// A usage error, reported with the usage line of the (sub)command.
+#[derive(Debug)]
+struct UsageError {
+    message: String,
+    usage: String,
+}
+
+fn spec_error(message: &str, expr: &Expr) -> Error {
+    Error::invalid_arguments(&format!("invalid cli spec: {message}"), expr.range())
+}
+
+fn get_string(map: &HashMap<String, Expr>, key: &str) -> Result<Option<String>, Error> {
+    match map.get(key) {
+        Some(value) => match value.as_stringable() {
+            Some(s) => Ok(Some(s.to_string())),
+            None => Err(spec_error(&format!("`{key}` should be a String"), value)),
+        },
+        None => Ok(None),
+    }
+}
+
+fn get_bool(map: &HashMap<String, Expr>, key: &str) -> bool {
+    map.get(key).and_then(|b| b.as_bool()).unwrap_or_default()
+}
+
+fn get_short(map: &HashMap<String, Expr>) -> Result<Option<char>, Error> {
+    let Some(short) = get_string(map, "short")? else {
+        return Ok(None);
+    };
+
+    let mut chars = short.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c != '-' => Ok(Some(c)),
+        _ => Err(spec_error(
+            &format!("`short` should be a single character, found `{short}`"),
+            &map["short"],
+        )),
+    }
+}
+
+fn get_value_type(map: &HashMap<String, Expr>) -> Result<ValueType, Error> {
+    let Some(value_type) = get_string(map, "type")? else {
+        return Ok(ValueType::String);
+    };
+
+    match value_type.as_str() {
+        "string" | "String" => Ok(ValueType::String),
+        "int" | "Int" => Ok(ValueType::Int),
+        "float" | "Float" => Ok(ValueType::Float),
+        _ => Err(spec_error(
+            &format!("unsupported type `{value_type}`"),
+            &map["type"],
+        )),
+    }
+}
+
+/// Iterates the entries of a Map, sorted by name, for a stable help output.
+fn sorted_entries(expr: &Expr, key: &str) -> Result<Vec<(String, Expr)>, Error> {
+    let Some(map) = expr.as_map() else {
+        return Err(spec_error(&format!("`{key}` should be a Map"), expr));
+    };
+
+    let mut entries: Vec<(String, Expr)> = map
+        .iter()
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect();
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+    Ok(entries)
+}
+
+impl Spec {
+    fn from_expr(name: &str, expr: &Expr) -> Result<Self, Error> {
+        let Some(map) = expr.as_map() else {
+            return Err(spec_error("the spec should be a Map", expr));
+        };
+
+        let mut spec = Spec {
+            name: get_string(&map, "name")?.unwrap_or_else(|| name.to_string()),
+            description: get_string(&map, "description")?.unwrap_or_default(),
+            ..Default::default()
+        };
+
+        if let Some(flags) = map.get("flags") {
+            for (name, flag) in sorted_entries(flags, "flags")? {
+                // #insight A String is a shorthand for the help text.
+                if let Some(help) = flag.as_stringable() {
+                    spec.flags.push(FlagSpec {
+                        name,
+                        short: None,
+                        help: help.to_string(),
+                    });
+                    continue;
+                }
+                let Some(flag) = flag.as_map() else {
+                    return Err(spec_error(
+                        &format!("flag `{name}` should be a Map or a String"),
+                        &flag,
+                    ));
+                };
+                spec.flags.push(FlagSpec {
+                    short: get_short(&flag)?,
+                    help: get_string(&flag, "help")?.unwrap_or_default(),
+                    name,
+                });
+            }
+        }
+
+        if let Some(options) = map.get("options") {
+            for (name, option) in sorted_entries(options, "options")? {
+                let Some(option) = option.as_map() else {
+                    return Err(spec_error(
+                        &format!("option `{name}` should be a Map"),
+                        &option,
+                    ));
+                };
+                spec.options.push(OptionSpec {
+                    short: get_short(&option)?,
+                    help: get_string(&option, "help")?.unwrap_or_default(),
+                    value_type: get_value_type(&option)?,
+                    default: option.get("default").cloned(),
+                    required: get_bool(&option, "required"),
+                    multiple: get_bool(&option, "multiple"),
+                    name,
+                });
+            }
+        }
+
+        if let Some(positionals) = map.get("positionals") {
+            let Some(positionals) = positionals.as_array() else {
+                return Err(spec_error("`positionals` should be an Array", positionals));
+            };
+            for positional in positionals.iter() {
+                let Some(positional) = positional.as_map() else {
+                    return Err(spec_error("positionals should be Maps", positional));
+                };
+                let Some(name) = get_string(&positional, "name")? else {
+                    return Err(spec_error("positionals require a `name`", expr));
+                };
+                spec.positionals.push(PositionalSpec {
+                    name,
+                    help: get_string(&positional, "help")?.unwrap_or_default(),
+                    value_type: get_value_type(&positional)?,
+                    default: positional.get("default").cloned(),
+                    required: get_bool(&positional, "required"),
+                    variadic: get_bool(&positional, "variadic"),
+                });
+            }
+            let variadic_count = spec.positionals.iter().filter(|p| p.variadic).count();
+            if variadic_count > 1
+                || (variadic_count == 1 && !spec.positionals.last().is_some_and(|p| p.variadic))
+            {
+                return Err(spec_error("only the last positional can be variadic", expr));
+            }
+        }
+
+        // #insight `-h` and `--help` are reserved, they are handled before the user arguments.
+        let names = spec.flags.iter().map(|f| (&f.name, f.short));
+        let names = names.chain(spec.options.iter().map(|o| (&o.name, o.short)));
+        for (name, short) in names {
+            if name == "help" || short == Some('h') {
+                return Err(spec_error(
+                    &format!("`{name}` conflicts with the reserved `-h, --help` flag"),
+                    expr,
+                ));
+            }
+        }
+
+        if let Some(commands) = map.get("commands") {
+            for (name, command) in sorted_entries(commands, "commands")? {
+                spec.commands.push(Spec::from_expr(&name, &command)?);
+            }
+        }
+
+        Ok(spec)
+    }
+
+    fn find_flag(&self, f: impl Fn(&FlagSpec) -> bool) -> Option<&FlagSpec> {
+        self.flags.iter().find(|flag| f(flag))
+    }
+
+    fn find_option(&self, f: impl Fn(&OptionSpec) -> bool) -> Option<&OptionSpec> {
+        self.options.iter().find(|option| f(option))
+    }
+
+    fn usage(&self, path: &str) -> String {
+        let mut usage = format!("Usage: {path} [OPTIONS]");
+
+        for positional in &self.positionals {
+            let name = if positional.variadic {
+                format!("{}...", positional.name)
+            } else {
+                positional.name.clone()
+            };
+            if positional.required {
+                usage.push_str(&format!(" <{name}>"));
+            } else {
+                usage.push_str(&format!(" [{name}]"));
+            }
+        }
+
+        if !self.commands.is_empty() {
+            usage.push_str(" [COMMAND]");
+        }
+
+        usage
+    }
+
+    /// Generates the `--help` text.
+    fn help(&self, path: &str) -> String {
+        let mut sections = Vec::new();
+
+        if !self.description.is_empty() {
+            sections.push(self.description.clone());
+        }
+
+        sections.push(self.usage(path));
+
+        if !self.positionals.is_empty() {
+            let rows = self
+                .positionals
+                .iter()
+                .map(|p| {
+                    let mut help = p.help.clone();
+                    if let Some(default) = &p.default {
+                        help.push_str(&format!(" [default: {}]", format_value(default)));
+                    }
+                    (format!("<{}>", p.name), help)
+                })
+                .collect();
+            sections.push(format_rows("Arguments:", rows));
+        }
+
+        let mut rows: Vec<(String, String, String)> = Vec::new();
+        for flag in &self.flags {
+            rows.push((
+                flag.name.clone(),
+                flag_label(flag.short, &flag.name),
+                flag.help.clone(),
+            ));
+        }
+        for option in &self.options {
+            let label = format!(
+                "{} {}",
+                flag_label(option.short, &option.name),
+                option.value_type.placeholder()
+            );
+            let mut help = option.help.clone();
+            if let Some(default) = &option.default {
+                help.push_str(&format!(" [default: {}]", format_value(default)));
+            }
+            if option.required {
+                help.push_str(" [required]");
+            }
+            rows.push((option.name.clone(), label, help));
+        }
+        rows.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut rows: Vec<(String, String)> = rows.into_iter().map(|(_, l, h)| (l, h)).collect();
+        rows.push((flag_label(Some('h'), "help"), "Print help".to_string()));
+        sections.push(format_rows("Options:", rows));
+
+        if !self.commands.is_empty() {
+            let rows = self
+                .commands
+                .iter()
+                .map(|c| (c.name.clone(), c.description.clone()))
+                .collect();
+            sections.push(format_rows("Commands:", rows));
+        }
+
+        let mut help = sections.join("\n\n");
+        help.push('\n');
+        help
+    }
+}
+
+fn flag_label(short: Option<char>, name: &str) -> String {
+    match short {
+        Some(short) => format!("-{short}, --{name}"),
+        None => format!("    --{name}"),
+    }
+}
+
+fn format_rows(title: &str, rows: Vec<(String, String)>) -> String {
+    let width = rows.iter().map(|(label, _)| label.len()).max().unwrap_or(0);
+
+    let mut text = title.to_string();
+    for (label, help) in rows {
+        text.push_str(&format!("\n  {label:width$}  {}", help.trim()));
+    }
+
+    // #insight Trim the padding of rows without help.
+    text.lines()
+        .map(|line| line.trim_end())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn is_negative_number(arg: &str) -> bool {
+    arg.starts_with('-') && arg[1..].parse::<f64>().is_ok()
+}
+
+fn parse_value(value_type: ValueType, value: &str, label: &str) -> Result<Expr, String> {
+    value_type.parse(value).ok_or_else(|| {
+        format!(
+            "invalid value `{value}` for `{label}`, expected {}",
+            value_type.placeholder()
+        )
+    })
+}
+
+/// Parses the arguments (without the program) against the spec.
+fn parse_args(spec: &Spec, args: &[String], path: &str) -> Result<Outcome, UsageError> {
+    let error = |message: String| UsageError {
+        message,
+        usage: spec.usage(path),
+    };
+
+    let mut values: HashMap<String, Expr> = HashMap::new();
+    let mut option_values: HashMap<String, Vec<Expr>> = HashMap::new();
+    let mut positional_args: Vec<&String> = Vec::new();
+
+    for flag in &spec.flags {
+        values.insert(flag.name.clone(), Expr::Bool(false));
+    }
+
+    let mut i = 0;
+    let mut only_positionals = false;
+
+    while i < args.len() {
+        let arg = &args[i];
+        i += 1;
+
+        if only_positionals {
+            positional_args.push(arg);
+            continue;
+        }
+
+        if arg == "--" {
+            only_positionals = true;
+            continue;
+        }
+
+        if arg == "--help" || arg == "-h" {
+            return Ok(Outcome::Help(spec.help(path)));
+        }
+
+        if let Some(long) = arg.strip_prefix("--") {
+            let (name, inline_value) = match long.split_once('=') {
+                Some((name, value)) => (name, Some(value)),
+                None => (long, None),
+            };
+
+            if let Some(flag) = spec.find_flag(|f| f.name == name) {
+                if inline_value.is_some() {
+                    return Err(error(format!("flag `--{name}` does not take a value")));
+                }
+                values.insert(flag.name.clone(), Expr::Bool(true));
+                continue;
+            }
+
+            let Some(option) = spec.find_option(|o| o.name == name) else {
+                return Err(error(format!("unexpected argument `--{name}`")));
+            };
+
+            let value = match inline_value {
+                Some(value) => value,
+                None if i < args.len() => {
+                    i += 1;
+                    &args[i - 1]
+                }
+                None => return Err(error(format!("`--{name}` requires a value"))),
+            };
+
+            let value =
+                parse_value(option.value_type, value, &format!("--{name}")).map_err(error)?;
+            option_values
+                .entry(option.name.clone())
+                .or_default()
+                .push(value);
+            continue;
+        }
+
+        if arg.len() > 1 && arg.starts_with('-') && !is_negative_number(arg) {
+            // #insight Short flags can be combined, e.g. `-vq`, `-p8000`.
+            let shorts = &arg[1..];
+            for (j, short) in shorts.char_indices() {
+                if let Some(flag) = spec.find_flag(|f| f.short == Some(short)) {
+                    values.insert(flag.name.clone(), Expr::Bool(true));
+                    continue;
+                }
+
+                let Some(option) = spec.find_option(|o| o.short == Some(short)) else {
+                    return Err(error(format!("unexpected argument `-{short}`")));
+                };
+
+                let rest = &shorts[j + short.len_utf8()..];
+                let value = if !rest.is_empty() {
+                    rest
+                } else if i < args.len() {
+                    i += 1;
+                    &args[i - 1]
+                } else {
+                    return Err(error(format!("`-{short}` requires a value")));
+                };
+
+                let value =
+                    parse_value(option.value_type, value, &format!("-{short}")).map_err(error)?;
+                option_values
+                    .entry(option.name.clone())
+                    .or_default()
+                    .push(value);
+                break;
+            }
+            continue;
+        }
+
+        // #insight The command is the first positional argument.
+        if positional_args.is_empty() && !spec.commands.is_empty() {
+            if let Some(command) = spec.commands.iter().find(|c| &c.name == arg) {
+                let command_path = format!("{path} {}", command.name);
+                match parse_args(command, &args[i..], &command_path)? {
+                    Outcome::Help(help) => return Ok(Outcome::Help(help)),
+                    Outcome::Values(command_values) => {
+                        values.insert("command".to_string(), Expr::string(arg));
+                        values.insert("command-args".to_string(), Expr::map(command_values));
+                    }
+                }
+                break;
+            }
+            if spec.positionals.is_empty() {
+                return Err(error(format!("unknown command `{arg}`")));
+            }
+        }
+
+        positional_args.push(arg);
+    }
+
+    for option in &spec.options {
+        match option_values.remove(&option.name) {
+            Some(mut option_values) => {
+                let value = if option.multiple {
+                    Expr::array(option_values)
+                } else {
+                    // #insight The last value wins.
+                    option_values.pop().expect("at least one value")
+                };
+                values.insert(option.name.clone(), value);
+            }
+            None => {
+                if option.required {
+                    return Err(error(format!("`--{}` is required", option.name)));
+                }
+                if let Some(default) = &option.default {
+                    values.insert(option.name.clone(), default.clone());
+                } else if option.multiple {
+                    values.insert(option.name.clone(), Expr::array(Vec::new()));
+                }
+            }
+        }
+    }
+
+    let mut positional_args = positional_args.into_iter();
+
+    for positional in &spec.positionals {
+        let label = format!("<{}>", positional.name);
+
+        if positional.variadic {
+            let mut rest = Vec::new();
+            for arg in positional_args.by_ref() {
+                rest.push(parse_value(positional.value_type, arg, &label).map_err(error)?);
+            }
+            if rest.is_empty() && positional.required {
+                return Err(error(format!("missing required argument `{label}`")));
+            }
+            values.insert(positional.name.clone(), Expr::array(rest));
+            continue;
+        }
+
+        match positional_args.next() {
+            Some(arg) => {
+                let value = parse_value(positional.value_type, arg, &label).map_err(error)?;
+                values.insert(positional.name.clone(), value);
+            }
+            None if positional.required => {
+                return Err(error(format!("missing required argument `{label}`")));
+            }
+            None => {
+                if let Some(default) = &positional.default {
+                    values.insert(positional.name.clone(), default.clone());
+                }
+            }
+        }
+    }
+
+    if let Some(arg) = positional_args.next() {
+        return Err(error(format!("unexpected argument `{arg}`")));
+    }
+
+    Ok(Outcome::Values(values))
+}
+
+/// Parses the command-line arguments against a spec, returns a Map with the
+/// values of the flags, options and positionals. When a command is given,
+/// the Map contains the `command` name and the `command-args` Map.
+///
+/// Prints the help text and exits with code 0 on `--help`, prints the error
+/// and exits with code 2 on invalid arguments, unless `{:exit false}` is
+/// passed; then the help text is returned in the `help` key and usage errors
+/// are returned as errors.
+/// ```tan
+/// (let args (cli/parse spec (process/args)))
+/// (if (args :verbose) (writeln "port: ${(args :port)}"))
+/// ```
+pub fn cli_parse(args: &[Expr]) -> Result<Expr, Error> {
+    let spec_expr = unpack_arg(args, 0, "spec")?;
+    let argv = unpack_array_arg(args, 1, "args")?;
+
+    let should_exit = if let Ok(options) = unpack_map_arg(args, 2, "options") {
+        options
+            .get("exit")
+            .and_then(|x| x.as_bool())
+            .unwrap_or(true)
+    } else {
+        true
+    };
+
+    let mut argv: Vec<String> = argv
+        .iter()
+        .map(|arg| match arg.as_stringable() {
+            Some(arg) => arg.to_string(),
+            None => format_value(arg),
+        })
+        .collect();
+
+    // #insight The program name is used in the usage line.
+    let program = if argv.is_empty() {
+        String::new()
+    } else {
+        argv.remove(0)
+    };
+
+    let spec = Spec::from_expr(&program, spec_expr)?;
+    let path = spec.name.clone();
+
+    match parse_args(&spec, &argv, &path) {
+        Ok(Outcome::Values(values)) => Ok(Expr::map(values)),
+        Ok(Outcome::Help(help)) => {
+            if should_exit {
+                print!("{help}");
+                std::io::stdout().flush().expect("stdout flushed");
+                std::process::exit(0);
+            }
+            let mut values = HashMap::new();
+            values.insert("help".to_string(), Expr::string(help));
+            Ok(Expr::map(values))
+        }
+        Err(usage_error) => {
+            if should_exit {
+                std::io::stdout().flush().expect("stdout flushed");
+                eprintln!(
+                    "error: {}\n\n{}\n\nFor more information, try `--help`.",
+                    usage_error.message, usage_error.usage
+                );
+                std::process::exit(USAGE_ERROR_EXIT_CODE);
+            }
+            Err(Error::invalid_arguments(
+                &usage_error.message,
+                args[1].range(),
+            ))
+        }
+    }
+}
+
+/// Returns the help text of a spec.
+/// ```tan
+/// (writeln (cli/help spec))
+/// ```
+pub fn cli_help(args: &[Expr]) -> Result<Expr, Error> {
+    let spec_expr = unpack_arg(args, 0, "spec")?;
+
+    let spec = Spec::from_expr("", spec_expr)?;
+
+    Ok(Expr::string(spec.help(&spec.name)))
+}
+
+pub fn import_lib_cli(context: &mut Context) {
+    let module = require_module("cli", context);
+
+    module.insert_invocable("parse", Expr::foreign_func(&cli_parse));
+    module.insert_invocable("help", Expr::foreign_func(&cli_help));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use tan::expr::Expr;
+
+    use crate::cli::{parse_args, FlagSpec, OptionSpec, Outcome, PositionalSpec, Spec, ValueType};
+
+    fn deploy_spec() -> Spec {
+        Spec {
+            name: "deploy".to_string(),
+            description: "Deploys the site".to_string(),
+            flags: vec![FlagSpec {
+                name: "verbose".to_string(),
+                short: Some('v'),
+                help: "Verbose output".to_string(),
+            }],
+            options: vec![OptionSpec {
+                name: "port".to_string(),
+                short: Some('p'),
+                help: "The port".to_string(),
+                value_type: ValueType::Int,
+                default: None,
+                required: false,
+                multiple: false,
+            }],
+            positionals: vec![PositionalSpec {
+                name: "target".to_string(),
+                help: "The target host".to_string(),
+                value_type: ValueType::String,
+                default: None,
+                required: true,
+                variadic: false,
+            }],
+            commands: Vec::new(),
+        }
+    }
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_args_usage() {
+        let spec = deploy_spec();
+
+        let Ok(Outcome::Values(values)) =
+            parse_args(&spec, &args(&["-vp8000", "example.com"]), "deploy")
+        else {
+            panic!("expected values");
+        };
+        assert!(matches!(values["verbose"], Expr::Bool(true)));
+        assert!(matches!(values["port"], Expr::Int(8000)));
+        assert!(matches!(&values["target"], Expr::String(s) if s == "example.com"));
+
+        let Err(error) = parse_args(&spec, &args(&["--port", "http"]), "deploy") else {
+            panic!("expected an error");
+        };
+        assert!(error.message.contains("expected <INT>"));
+
+        let Err(error) = parse_args(&spec, &args(&["--verbose"]), "deploy") else {
+            panic!("expected an error");
+        };
+        assert!(error
+            .message
+            .contains("missing required argument `<target>`"));
+    }
+
+    #[test]
+    fn help_usage() {
+        let help = deploy_spec().help("deploy");
+
+        assert!(help.starts_with("Deploys the site\n\nUsage: deploy [OPTIONS] <target>"));
+        assert!(help.contains("-p, --port <INT>  The port"));
+        assert!(help.contains("-h, --help        Print help"));
+    }
+
+    #[test]
+    fn reserved_help_flag_is_rejected() {
+        let spec_expr = |kind: &str, name: &str, short: &str| {
+            let entry = Expr::map(HashMap::from([("short".to_string(), Expr::string(short))]));
+            let entries = Expr::map(HashMap::from([(name.to_string(), entry)]));
+            Expr::map(HashMap::from([(kind.to_string(), entries)]))
+        };
+
+        assert!(Spec::from_expr("deploy", &spec_expr("flags", "verbose", "v")).is_ok());
+        assert!(Spec::from_expr("deploy", &spec_expr("flags", "host", "h")).is_err());
+        assert!(Spec::from_expr("deploy", &spec_expr("options", "host", "h")).is_err());
+        assert!(Spec::from_expr("deploy", &spec_expr("flags", "help", "x")).is_err());
+    }
+}
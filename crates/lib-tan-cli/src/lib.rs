@@ -0,0 +1,9 @@
+use cli::import_lib_cli;
+use tan::context::Context;
+
+pub mod cli;
+
+#[no_mangle]
+pub fn install_foreign_dyn_lib(context: &mut Context) {
+    import_lib_cli(context);
+}
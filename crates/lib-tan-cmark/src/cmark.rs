@@ -1,5 +1,12 @@
-use comrak::{markdown_to_html, Options};
+use std::collections::HashMap;
 
+use comrak::{
+    format_html,
+    nodes::{AstNode, NodeCode, NodeMath, NodeValue},
+    parse_document, Anchorizer, Arena, ListStyleType, Options,
+};
+
+use parse::setup_lib_text_cmark_parse;
 use tan::{
     context::Context,
     error::Error,
@@ -7,24 +14,170 @@ use tan::{
     util::{args::unpack_stringable_arg, module_util::require_module},
 };
 
+pub mod parse;
+
 // #todo rename to `text/common-mark`?
 // #todo find a better name for this module.
 // #todo this should be extracted to a separate crate, and/or a dynamic library.
 
-// #insight some Github Flavored Markdown extensions are supported.
+// #insight some Github Flavored Markdown extensions are enabled by default,
+// the options Map can enable or disable all comrak extensions.
 
-pub fn html_from_common_mark(args: &[Expr]) -> Result<Expr, Error> {
-    let markup = unpack_stringable_arg(args, 0, "markup")?;
+// #insight
+// Front matter is YAML, delimited with `---`, or TOML, delimited with `+++`.
+// It is removed from the markup before rendering, use `{:front-matter false}`
+// to render it as markdown.
 
-    // #todo consider renaming :unsafe to :allow-html?
-    // Accepts an optional ..options parameter
-    // :unsafe
-    let options = args.get(1);
+// #see here are the Comrak options: https://docs.rs/comrak/latest/comrak/struct.ExtensionOptions.html
+// #todo cache the generation of options.
+
+#[derive(Debug, PartialEq)]
+enum FrontMatterFormat {
+    Yaml,
+    Toml,
+}
 
-    // #see here are the Comrak options: https://docs.rs/comrak/latest/comrak/struct.ExtensionOptions.html
-    // #todo consider allowing granular setting of options? probably no.
-    // #todo cache the generation of options.
+/// Splits the front matter from the markup, returns the format, the front
+/// matter (without the delimiters) and the rest of the markup.
+fn split_front_matter(markup: &str) -> Option<(FrontMatterFormat, &str, &str)> {
+    let (format, delimiter) = if markup.starts_with("---") {
+        (FrontMatterFormat::Yaml, "---")
+    } else if markup.starts_with("+++") {
+        (FrontMatterFormat::Toml, "+++")
+    } else {
+        return None;
+    };
 
+    let first_line_end = markup.find('\n')?;
+    if markup[..first_line_end].trim_end() != delimiter {
+        return None;
+    }
+
+    let rest = &markup[first_line_end + 1..];
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == delimiter {
+            return Some((format, &rest[..offset], &rest[offset + line.len()..]));
+        }
+        offset += line.len();
+    }
+
+    None
+}
+
+fn yaml_value_to_expr(value: serde_yaml::Value) -> Expr {
+    match value {
+        serde_yaml::Value::Null => Expr::None,
+        serde_yaml::Value::Bool(b) => Expr::Bool(b),
+        serde_yaml::Value::Number(n) => match n.as_i64() {
+            Some(n) => Expr::Int(n),
+            None => Expr::Float(n.as_f64().unwrap_or(f64::NAN)),
+        },
+        serde_yaml::Value::String(s) => Expr::String(s),
+        serde_yaml::Value::Sequence(items) => Expr::array(
+            items
+                .into_iter()
+                .map(yaml_value_to_expr)
+                .collect::<Vec<_>>(),
+        ),
+        serde_yaml::Value::Mapping(mapping) => {
+            let mut map: HashMap<String, Expr> = HashMap::new();
+            for (key, value) in mapping {
+                // #todo should support more key types.
+                let key = match key {
+                    serde_yaml::Value::String(key) => key,
+                    serde_yaml::Value::Number(key) => key.to_string(),
+                    serde_yaml::Value::Bool(key) => key.to_string(),
+                    _ => continue,
+                };
+                map.insert(key.replace('_', "-"), yaml_value_to_expr(value));
+            }
+            Expr::map(map)
+        }
+        serde_yaml::Value::Tagged(tagged) => yaml_value_to_expr(tagged.value),
+    }
+}
+
+fn toml_value_to_expr(value: toml::Value) -> Expr {
+    match value {
+        toml::Value::String(s) => Expr::String(s),
+        toml::Value::Integer(n) => Expr::Int(n),
+        toml::Value::Float(n) => Expr::Float(n),
+        toml::Value::Boolean(b) => Expr::Bool(b),
+        // #todo Convert to a chrono Date/DateTime.
+        toml::Value::Datetime(datetime) => Expr::String(datetime.to_string()),
+        toml::Value::Array(items) => Expr::array(
+            items
+                .into_iter()
+                .map(toml_value_to_expr)
+                .collect::<Vec<_>>(),
+        ),
+        toml::Value::Table(table) => {
+            let mut map: HashMap<String, Expr> = HashMap::new();
+            for (key, value) in table {
+                map.insert(key.replace('_', "-"), toml_value_to_expr(value));
+            }
+            Expr::map(map)
+        }
+    }
+}
+
+/// Parses the front matter into a Map, keys use kebab-case.
+fn parse_front_matter(format: FrontMatterFormat, front_matter: &str) -> Result<Expr, Error> {
+    match format {
+        FrontMatterFormat::Yaml => {
+            // #insight Empty front matter is parsed as null.
+            match serde_yaml::from_str::<serde_yaml::Value>(front_matter) {
+                Ok(serde_yaml::Value::Null) => Ok(Expr::map(HashMap::new())),
+                Ok(value) => Ok(yaml_value_to_expr(value)),
+                Err(error) => Err(Error::invalid_arguments(
+                    &format!("invalid YAML front matter: {error}"),
+                    None,
+                )),
+            }
+        }
+        FrontMatterFormat::Toml => match front_matter.parse::<toml::Table>() {
+            Ok(table) => Ok(toml_value_to_expr(toml::Value::Table(table))),
+            Err(error) => Err(Error::invalid_arguments(
+                &format!("invalid TOML front matter: {error}"),
+                None,
+            )),
+        },
+    }
+}
+
+fn options_from_args(args: &[Expr], index: usize) -> Result<HashMap<String, Expr>, Error> {
+    let Some(options) = args.get(index) else {
+        return Ok(HashMap::new());
+    };
+
+    let Some(options) = options.as_map() else {
+        return Err(Error::invalid_arguments(
+            "options argument should be a map",
+            options.range(),
+        ));
+    };
+
+    Ok(options.clone())
+}
+
+fn bool_option(options: &HashMap<String, Expr>, name: &str, default: bool) -> Result<bool, Error> {
+    let Some(value) = options.get(name) else {
+        return Ok(default);
+    };
+
+    match value.unpack() {
+        Expr::Bool(b) => Ok(*b),
+        _ => Err(Error::invalid_arguments(
+            &format!("`{name}` option should be a Bool"),
+            value.range(),
+        )),
+    }
+}
+
+/// Converts the options Map to comrak options, option names use kebab-case,
+/// e.g. `{:description-lists true :header-ids "h-" :unsafe true}`.
+fn comrak_options(options: &HashMap<String, Expr>) -> Result<Options, Error> {
     let mut comrak_options = Options::default();
     comrak_options.extension.strikethrough = true;
     comrak_options.extension.table = true;
@@ -32,27 +185,260 @@ pub fn html_from_common_mark(args: &[Expr]) -> Result<Expr, Error> {
     comrak_options.extension.tasklist = true;
     comrak_options.extension.superscript = true;
     comrak_options.extension.footnotes = true;
-    // #todo extract shortcodes support to separate module, e.g. `text/shortcodes`
-    // options.extension.shortcodes = true; // #insight needs feature `shortcodes`
-    if options.is_some() {
-        let Some(options) = options.unwrap().as_map() else {
-            return Err(Error::invalid_arguments(
-                "options argument should be a map",
-                None,
-            ));
+
+    let extension = &mut comrak_options.extension;
+    let parse = &mut comrak_options.parse;
+    let render = &mut comrak_options.render;
+
+    // #todo consider renaming :unsafe to :allow-html?
+    let flags = [
+        ("strikethrough", &mut extension.strikethrough),
+        ("tagfilter", &mut extension.tagfilter),
+        ("table", &mut extension.table),
+        ("autolink", &mut extension.autolink),
+        ("tasklist", &mut extension.tasklist),
+        ("superscript", &mut extension.superscript),
+        ("footnotes", &mut extension.footnotes),
+        ("description-lists", &mut extension.description_lists),
+        (
+            "multiline-block-quotes",
+            &mut extension.multiline_block_quotes,
+        ),
+        ("math-dollars", &mut extension.math_dollars),
+        ("math-code", &mut extension.math_code),
+        ("shortcodes", &mut extension.shortcodes),
+        ("smart", &mut parse.smart),
+        (
+            "relaxed-tasklist-matching",
+            &mut parse.relaxed_tasklist_matching,
+        ),
+        ("relaxed-autolinks", &mut parse.relaxed_autolinks),
+        ("hardbreaks", &mut render.hardbreaks),
+        ("github-pre-lang", &mut render.github_pre_lang),
+        ("full-info-string", &mut render.full_info_string),
+        // #insight this allows 'raw' html
+        ("unsafe", &mut render.unsafe_),
+        ("escape", &mut render.escape),
+        ("sourcepos", &mut render.sourcepos),
+        ("escaped-char-spans", &mut render.escaped_char_spans),
+    ];
+
+    for (name, flag) in flags {
+        *flag = bool_option(options, name, *flag)?;
+    }
+
+    // #insight `{:header-ids true}` generates ids without a prefix.
+    if let Some(header_ids) = options.get("header-ids") {
+        extension.header_ids = match header_ids.unpack() {
+            Expr::Bool(true) => Some(String::new()),
+            Expr::Bool(false) | Expr::None => None,
+            _ => match header_ids.as_stringable() {
+                Some(prefix) => Some(prefix.to_string()),
+                None => {
+                    return Err(Error::invalid_arguments(
+                        "`header-ids` option should be a Bool or a String prefix",
+                        header_ids.range(),
+                    ))
+                }
+            },
+        };
+    }
+
+    if let Some(info_string) = options.get("default-info-string") {
+        parse.default_info_string = info_string.as_stringable().map(String::from);
+    }
+
+    if let Some(width) = options.get("width") {
+        render.width = width.as_int().unwrap_or(0).max(0) as usize;
+    }
+
+    if let Some(list_style) = options.get("list-style") {
+        render.list_style = match list_style.as_stringable() {
+            Some("dash") => ListStyleType::Dash,
+            Some("plus") => ListStyleType::Plus,
+            Some("star") => ListStyleType::Star,
+            _ => {
+                return Err(Error::invalid_arguments(
+                    "`list-style` option should be :dash, :plus or :star",
+                    list_style.range(),
+                ))
+            }
         };
+    }
+
+    Ok(comrak_options)
+}
 
-        if options.contains_key("unsafe") {
-            // #todo actually check that :unsafe is true
-            // #todo add unit test
-            // #insight this allows 'raw' html
-            comrak_options.render.unsafe_ = true;
+#[derive(Debug, PartialEq)]
+struct Heading {
+    level: u8,
+    id: String,
+    title: String,
+}
+
+/// Collects the text content of a node, like the comrak HTML renderer does
+/// for heading ids.
+fn collect_text<'a>(node: &'a AstNode<'a>, text: &mut String) {
+    match node.data.borrow().value {
+        NodeValue::Text(ref literal)
+        | NodeValue::Code(NodeCode { ref literal, .. })
+        | NodeValue::Math(NodeMath { ref literal, .. }) => text.push_str(literal),
+        NodeValue::LineBreak | NodeValue::SoftBreak => text.push(' '),
+        _ => {
+            for child in node.children() {
+                collect_text(child, text);
+            }
         }
     }
+}
 
-    let html = markdown_to_html(markup, &comrak_options);
+/// Collects the headings in document order, the ids match the ids generated
+/// by the renderer.
+fn collect_headings<'a>(root: &'a AstNode<'a>, prefix: &str) -> Vec<Heading> {
+    let mut anchorizer = Anchorizer::new();
+    let mut headings = Vec::new();
+
+    for node in root.descendants() {
+        let NodeValue::Heading(ref heading) = node.data.borrow().value else {
+            continue;
+        };
 
-    Ok(Expr::String(html))
+        let mut title = String::new();
+        collect_text(node, &mut title);
+
+        let id = anchorizer.anchorize(title.clone());
+
+        headings.push(Heading {
+            level: heading.level,
+            id: format!("{prefix}{id}"),
+            title,
+        });
+    }
+
+    headings
+}
+
+/// Builds the table-of-contents tree, nested by heading level.
+fn toc_tree(headings: &[Heading], index: &mut usize, level: u8) -> Vec<Expr> {
+    let mut entries = Vec::new();
+
+    while let Some(heading) = headings.get(*index) {
+        if heading.level < level {
+            break;
+        }
+        *index += 1;
+
+        let children = toc_tree(headings, index, heading.level + 1);
+
+        entries.push(Expr::map(HashMap::from([
+            ("level".to_string(), Expr::Int(heading.level as i64)),
+            ("id".to_string(), Expr::string(&heading.id)),
+            ("title".to_string(), Expr::string(&heading.title)),
+            ("children".to_string(), Expr::array(children)),
+        ])));
+    }
+
+    entries
+}
+
+struct Document {
+    html: String,
+    front_matter: Expr,
+    headings: Vec<Heading>,
+}
+
+/// Removes the front matter, unless `{:front-matter false}`, returns the
+/// parsed front matter and the rest of the markup.
+fn strip_front_matter<'a>(
+    markup: &'a str,
+    options: &HashMap<String, Expr>,
+) -> Result<(Expr, &'a str), Error> {
+    if !bool_option(options, "front-matter", true)? {
+        return Ok((Expr::None, markup));
+    }
+
+    match split_front_matter(markup) {
+        Some((format, text, rest)) => Ok((parse_front_matter(format, text)?, rest)),
+        None => Ok((Expr::None, markup)),
+    }
+}
+
+fn render_document(markup: &str, options: &HashMap<String, Expr>) -> Result<Document, Error> {
+    let comrak_options = comrak_options(options)?;
+
+    let (front_matter, markup) = strip_front_matter(markup, options)?;
+
+    let arena = Arena::new();
+    let root = parse_document(&arena, markup, &comrak_options);
+
+    let prefix = comrak_options.extension.header_ids.as_deref().unwrap_or("");
+    let headings = collect_headings(root, prefix);
+
+    let mut html = Vec::new();
+    format_html(root, &comrak_options, &mut html)?;
+
+    let html = String::from_utf8(html)
+        .map_err(|_| Error::general("invalid UTF-8 in rendered markdown"))?;
+
+    Ok(Document {
+        html,
+        front_matter,
+        headings,
+    })
+}
+
+/// Renders CommonMark markup to HTML, the front matter is skipped.
+/// ```tan
+/// (cmark/to-html "# Hello *world*")
+/// (cmark/to-html post {:unsafe true :header-ids true :math-dollars true})
+/// ```
+pub fn html_from_common_mark(args: &[Expr]) -> Result<Expr, Error> {
+    let markup = unpack_stringable_arg(args, 0, "markup")?;
+    let options = options_from_args(args, 1)?;
+
+    let document = render_document(markup, &options)?;
+
+    Ok(Expr::String(document.html))
+}
+
+/// Renders CommonMark markup to a Map with the `:html`, the parsed
+/// `:front-matter` (None if missing) and the `:toc` tree of the headings.
+/// Heading ids are enabled by default, so the toc entries can link to them.
+/// ```tan
+/// (let post (cmark/to-document (fs/read-file-to-string "posts/hello.md")))
+/// (post :front-matter) ; => {:title "Hello" :tags ["tan"]}
+/// (post :toc) ; => [{:level 2 :id "intro" :title "Intro" :children [...]}]
+/// ```
+pub fn document_from_common_mark(args: &[Expr]) -> Result<Expr, Error> {
+    let markup = unpack_stringable_arg(args, 0, "markup")?;
+    let mut options = options_from_args(args, 1)?;
+
+    if !options.contains_key("header-ids") {
+        options.insert("header-ids".to_string(), Expr::Bool(true));
+    }
+
+    let document = render_document(markup, &options)?;
+
+    let toc = toc_tree(&document.headings, &mut 0, 1);
+
+    Ok(Expr::map(HashMap::from([
+        ("html".to_string(), Expr::String(document.html)),
+        ("front-matter".to_string(), document.front_matter),
+        ("toc".to_string(), Expr::array(toc)),
+    ])))
+}
+
+/// Returns the front matter of CommonMark markup as a Map, None if missing.
+/// ```tan
+/// (cmark/front-matter "---\ntitle: Hello\n---\n# Hello") ; => {:title "Hello"}
+/// ```
+pub fn common_mark_front_matter(args: &[Expr]) -> Result<Expr, Error> {
+    let markup = unpack_stringable_arg(args, 0, "markup")?;
+
+    match split_front_matter(markup) {
+        Some((format, text, _)) => parse_front_matter(format, text),
+        None => Ok(Expr::None),
+    }
 }
 
 pub fn import_lib_text_cmark(context: &mut Context) {
@@ -65,4 +451,112 @@ pub fn import_lib_text_cmark(context: &mut Context) {
     // (let html cmark/to-html markup)
     // (let html cmark/to-html markup {:unsafe true})
     module.insert_invocable("to-html", Expr::foreign_func(&html_from_common_mark));
+    module.insert_invocable(
+        "to-document",
+        Expr::foreign_func(&document_from_common_mark),
+    );
+    module.insert_invocable(
+        "front-matter",
+        Expr::foreign_func(&common_mark_front_matter),
+    );
+
+    setup_lib_text_cmark_parse(context);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use tan::expr::Expr;
+
+    use crate::cmark::{
+        parse_front_matter, render_document, split_front_matter, toc_tree, FrontMatterFormat,
+        Heading,
+    };
+
+    #[test]
+    fn split_front_matter_usage() {
+        let markup = "---\ntitle: Hello\n---\n# Hello\n";
+        assert_eq!(
+            split_front_matter(markup),
+            Some((FrontMatterFormat::Yaml, "title: Hello\n", "# Hello\n"))
+        );
+
+        let markup = "+++\ntitle = \"Hello\"\n+++\r\n# Hello";
+        assert_eq!(
+            split_front_matter(markup),
+            Some((FrontMatterFormat::Toml, "title = \"Hello\"\n", "# Hello"))
+        );
+
+        assert_eq!(split_front_matter("# Hello\n---\n"), None);
+        assert_eq!(split_front_matter("---\nunterminated"), None);
+        assert_eq!(split_front_matter("----\n"), None);
+    }
+
+    #[test]
+    fn parse_front_matter_usage() {
+        let expr = parse_front_matter(
+            FrontMatterFormat::Yaml,
+            "title: Hello\npublished_at: 2024-01-02\ntags: [tan, web]\ndraft: false\n",
+        )
+        .unwrap();
+        let map = expr.as_map().unwrap();
+        assert_eq!(map["title"].as_string(), Some("Hello"));
+        assert_eq!(map["published-at"].as_string(), Some("2024-01-02"));
+        assert_eq!(map["tags"].as_array().unwrap().len(), 2);
+        assert!(matches!(map["draft"], Expr::Bool(false)));
+
+        let expr =
+            parse_front_matter(FrontMatterFormat::Toml, "title = \"Hi\"\nweight = 3").unwrap();
+        let map = expr.as_map().unwrap();
+        assert_eq!(map["weight"].as_int(), Some(3));
+
+        assert!(parse_front_matter(FrontMatterFormat::Yaml, "title: [").is_err());
+    }
+
+    #[test]
+    fn render_document_usage() {
+        let options = HashMap::from([("header-ids".to_string(), Expr::string("h-"))]);
+        let document = render_document(
+            "---\ntitle: Hello\n---\n# Intro\n\n## `Setup` steps\n\n### Details\n\n## Intro\n",
+            &options,
+        )
+        .unwrap();
+
+        assert!(!document.html.contains("title: Hello"));
+        assert!(document.html.contains("id=\"h-intro\""));
+        assert_eq!(
+            document.headings,
+            vec![
+                Heading {
+                    level: 1,
+                    id: "h-intro".to_string(),
+                    title: "Intro".to_string()
+                },
+                Heading {
+                    level: 2,
+                    id: "h-setup-steps".to_string(),
+                    title: "Setup steps".to_string()
+                },
+                Heading {
+                    level: 3,
+                    id: "h-details".to_string(),
+                    title: "Details".to_string()
+                },
+                Heading {
+                    level: 2,
+                    id: "h-intro-1".to_string(),
+                    title: "Intro".to_string()
+                },
+            ]
+        );
+
+        let toc = toc_tree(&document.headings, &mut 0, 1);
+        assert_eq!(toc.len(), 1);
+        let children = toc[0].as_map().unwrap()["children"]
+            .as_array()
+            .unwrap()
+            .len();
+        assert_eq!(children, 2);
+    }
 }
@@ -0,0 +1,581 @@
+use std::collections::HashMap;
+
+use comrak::{
+    nodes::{AstNode, ListType, NodeValue, TableAlignment},
+    parse_document, Anchorizer, Arena, Options,
+};
+use tan::{
+    context::Context,
+    error::Error,
+    expr::Expr,
+    util::{args::unpack_stringable_arg, module_util::require_module},
+};
+
+use super::{collect_text, comrak_options, options_from_args, strip_front_matter};
+
+// #insight
+// The AST is converted to the expressions rendered by `html/to-html`, e.g.
+// `(p "Hello " (em "world"))`, so it can be transformed and rendered with the
+// html library. The output follows the comrak HTML renderer, with two
+// simplifications: heading ids are set on the heading, instead of an inner
+// `<a class="anchor">`, and a footnote definition ends with a single backref
+// after its content, instead of one backref per reference in the last
+// paragraph.
+
+// #insight
+// Raw HTML blocks and inline HTML are converted to `(raw "...")` only with the
+// `{:unsafe true}` option, otherwise they are omitted. Likewise, dangerous
+// link and image URLs are replaced with an empty String. With the
+// `{:tagfilter true}` option, the GFM disallowed tags are neutralized in the
+// raw HTML.
+
+// #todo Support the `github-pre-lang` and `full-info-string` options.
+// #todo Consider adding the source positions, with the `sourcepos` option.
+
+// #ref comrak 0.23 `html::dangerous_url`
+/// Returns true for `javascript:`, `vbscript:`, `file:` and `data:` URLs,
+/// except `data:` PNG, GIF, JPEG and WebP images.
+fn is_dangerous_url(url: &str) -> bool {
+    let url = url.as_bytes();
+    let starts_with = |prefix: &str| {
+        url.len() >= prefix.len() && url[..prefix.len()].eq_ignore_ascii_case(prefix.as_bytes())
+    };
+
+    if ["png", "gif", "jpeg", "webp"]
+        .iter()
+        .any(|format| starts_with(&format!("data:image/{format}")))
+    {
+        return false;
+    }
+
+    ["javascript:", "vbscript:", "file:", "data:"]
+        .iter()
+        .any(|scheme| starts_with(scheme))
+}
+
+// #ref comrak 0.23 `html::tagfilter`
+// #ref https://github.github.com/gfm/#disallowed-raw-html-extension-
+/// Returns true if the HTML starts with an opening or closing tag disallowed
+/// by GFM, e.g. `<script>` or `</title>`.
+fn is_filtered_tag(html: &[u8]) -> bool {
+    const FILTERED_TAGS: [&str; 9] = [
+        "title",
+        "textarea",
+        "style",
+        "xmp",
+        "iframe",
+        "noembed",
+        "noframes",
+        "script",
+        "plaintext",
+    ];
+
+    if html.len() < 3 || html[0] != b'<' {
+        return false;
+    }
+
+    let start = if html[1] == b'/' { 2 } else { 1 };
+    let name = &html[start..];
+
+    FILTERED_TAGS.iter().any(|tag| {
+        if name.len() <= tag.len() || !name[..tag.len()].eq_ignore_ascii_case(tag.as_bytes()) {
+            return false;
+        }
+        match &name[tag.len()..] {
+            [b'>', ..] | [b'/', b'>', ..] => true,
+            [c, ..] => c.is_ascii_whitespace(),
+            [] => false,
+        }
+    })
+}
+
+// #ref comrak 0.23 `html::tagfilter_block`
+/// Replaces the `<` of the GFM disallowed tags with `&lt;`.
+fn filter_tags(html: &str) -> String {
+    let mut filtered = String::with_capacity(html.len());
+
+    for (i, c) in html.char_indices() {
+        if c == '<' && is_filtered_tag(&html.as_bytes()[i..]) {
+            filtered.push_str("&lt;");
+        } else {
+            filtered.push(c);
+        }
+    }
+
+    filtered
+}
+
+fn element(tag: &str, attributes: Vec<(&str, Expr)>, children: Vec<Expr>) -> Expr {
+    let mut terms = vec![Expr::symbol(tag)];
+
+    if !attributes.is_empty() {
+        let attributes: HashMap<String, Expr> = attributes
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect();
+        terms.push(Expr::map(attributes));
+    }
+
+    terms.extend(children);
+
+    Expr::List(terms)
+}
+
+/// Paragraphs in tight lists and description terms are not wrapped in <p>.
+fn is_tight_paragraph<'a>(node: &'a AstNode<'a>) -> bool {
+    let Some(parent) = node.parent() else {
+        return false;
+    };
+
+    if matches!(parent.data.borrow().value, NodeValue::DescriptionTerm) {
+        return true;
+    }
+
+    match parent.parent().map(|n| n.data.borrow().value.clone()) {
+        Some(NodeValue::List(list)) => list.tight,
+        _ => false,
+    }
+}
+
+struct AstConverter<'o> {
+    options: &'o Options,
+    anchorizer: Anchorizer,
+    /// The footnote definitions, rendered after the content.
+    footnotes: Vec<Expr>,
+}
+
+impl AstConverter<'_> {
+    fn children<'a>(&mut self, node: &'a AstNode<'a>) -> Vec<Expr> {
+        let mut exprs = Vec::new();
+        for child in node.children() {
+            self.convert(child, &mut exprs);
+        }
+        exprs
+    }
+
+    fn raw_html(&self, literal: &str, exprs: &mut Vec<Expr>) {
+        if !self.options.render.unsafe_ {
+            return;
+        }
+
+        let html = if self.options.extension.tagfilter {
+            filter_tags(literal)
+        } else {
+            literal.to_string()
+        };
+
+        exprs.push(element("raw", vec![], vec![Expr::string(html)]));
+    }
+
+    fn safe_url(&self, url: String) -> String {
+        if self.options.render.unsafe_ || !is_dangerous_url(&url) {
+            url
+        } else {
+            String::new()
+        }
+    }
+
+    fn convert_table<'a>(&mut self, node: &'a AstNode<'a>, alignments: &[TableAlignment]) -> Expr {
+        let mut head = Vec::new();
+        let mut body = Vec::new();
+
+        for row in node.children() {
+            let NodeValue::TableRow(is_header) = row.data.borrow().value else {
+                continue;
+            };
+
+            let tag = if is_header { "th" } else { "td" };
+
+            let mut cells = Vec::new();
+            for (i, cell) in row.children().enumerate() {
+                let align = match alignments.get(i) {
+                    Some(TableAlignment::Left) => Some("left"),
+                    Some(TableAlignment::Center) => Some("center"),
+                    Some(TableAlignment::Right) => Some("right"),
+                    _ => None,
+                };
+                let attributes = match align {
+                    Some(align) => vec![("align", Expr::string(align))],
+                    None => vec![],
+                };
+                cells.push(element(tag, attributes, self.children(cell)));
+            }
+
+            let row = element("tr", vec![], cells);
+            if is_header {
+                head.push(row);
+            } else {
+                body.push(row);
+            }
+        }
+
+        let mut sections = Vec::new();
+        if !head.is_empty() {
+            sections.push(element("thead", vec![], head));
+        }
+        if !body.is_empty() {
+            sections.push(element("tbody", vec![], body));
+        }
+
+        element("table", vec![], sections)
+    }
+
+    fn convert<'a>(&mut self, node: &'a AstNode<'a>, exprs: &mut Vec<Expr>) {
+        let value = node.data.borrow().value.clone();
+
+        let expr = match value {
+            NodeValue::Document | NodeValue::DescriptionItem(_) | NodeValue::Escaped => {
+                exprs.extend(self.children(node));
+                return;
+            }
+            NodeValue::FrontMatter(_) | NodeValue::TableRow(_) | NodeValue::TableCell => return,
+            NodeValue::BlockQuote | NodeValue::MultilineBlockQuote(_) => {
+                element("blockquote", vec![], self.children(node))
+            }
+            NodeValue::List(list) => match list.list_type {
+                ListType::Bullet => element("ul", vec![], self.children(node)),
+                ListType::Ordered if list.start != 1 => element(
+                    "ol",
+                    vec![("start", Expr::Int(list.start as i64))],
+                    self.children(node),
+                ),
+                ListType::Ordered => element("ol", vec![], self.children(node)),
+            },
+            NodeValue::Item(_) => element("li", vec![], self.children(node)),
+            NodeValue::TaskItem(symbol) => {
+                let mut attributes = vec![("type", Expr::string("checkbox"))];
+                if symbol.is_some() {
+                    attributes.push(("checked", Expr::Bool(true)));
+                }
+                attributes.push(("disabled", Expr::Bool(true)));
+
+                let mut children = vec![element("input", attributes, vec![]), Expr::string(" ")];
+                children.extend(self.children(node));
+                element("li", vec![], children)
+            }
+            NodeValue::DescriptionList => element("dl", vec![], self.children(node)),
+            NodeValue::DescriptionTerm => element("dt", vec![], self.children(node)),
+            NodeValue::DescriptionDetails => element("dd", vec![], self.children(node)),
+            NodeValue::CodeBlock(code_block) => {
+                let lang = code_block.info.split_whitespace().next().unwrap_or("");
+                let attributes = if lang.is_empty() {
+                    vec![]
+                } else {
+                    vec![("class", Expr::string(format!("language-{lang}")))]
+                };
+                let code = element("code", attributes, vec![Expr::string(code_block.literal)]);
+                element("pre", vec![], vec![code])
+            }
+            NodeValue::HtmlBlock(html_block) => {
+                self.raw_html(&html_block.literal, exprs);
+                return;
+            }
+            NodeValue::HtmlInline(literal) => {
+                self.raw_html(&literal, exprs);
+                return;
+            }
+            NodeValue::Paragraph => {
+                if is_tight_paragraph(node) {
+                    exprs.extend(self.children(node));
+                    return;
+                }
+                element("p", vec![], self.children(node))
+            }
+            NodeValue::Heading(heading) => {
+                let tag = format!("h{}", heading.level);
+                let attributes = match &self.options.extension.header_ids {
+                    Some(prefix) => {
+                        let mut text = String::new();
+                        collect_text(node, &mut text);
+                        let id = self.anchorizer.anchorize(text);
+                        vec![("id", Expr::string(format!("{prefix}{id}")))]
+                    }
+                    None => vec![],
+                };
+                element(&tag, attributes, self.children(node))
+            }
+            NodeValue::ThematicBreak => element("hr", vec![], vec![]),
+            NodeValue::FootnoteDefinition(definition) => {
+                let mut children = self.children(node);
+                children.push(element(
+                    "a",
+                    vec![
+                        ("href", Expr::string(format!("#fnref-{}", definition.name))),
+                        ("class", Expr::string("footnote-backref")),
+                    ],
+                    vec![Expr::string("↩")],
+                ));
+                let id = format!("fn-{}", definition.name);
+                self.footnotes
+                    .push(element("li", vec![("id", Expr::string(id))], children));
+                return;
+            }
+            NodeValue::FootnoteReference(reference) => {
+                let mut id = format!("fnref-{}", reference.name);
+                if reference.ref_num > 1 {
+                    id = format!("{id}-{}", reference.ref_num);
+                }
+                let link = element(
+                    "a",
+                    vec![
+                        ("href", Expr::string(format!("#fn-{}", reference.name))),
+                        ("id", Expr::string(id)),
+                    ],
+                    vec![Expr::string(reference.ix.to_string())],
+                );
+                element(
+                    "sup",
+                    vec![("class", Expr::string("footnote-ref"))],
+                    vec![link],
+                )
+            }
+            NodeValue::Table(table) => self.convert_table(node, &table.alignments),
+            NodeValue::Text(text) => Expr::String(text),
+            NodeValue::SoftBreak if self.options.render.hardbreaks => element("br", vec![], vec![]),
+            NodeValue::SoftBreak => Expr::string("\n"),
+            NodeValue::LineBreak => element("br", vec![], vec![]),
+            NodeValue::Code(code) => element("code", vec![], vec![Expr::String(code.literal)]),
+            NodeValue::Emph => element("em", vec![], self.children(node)),
+            NodeValue::Strong => element("strong", vec![], self.children(node)),
+            NodeValue::Strikethrough => element("del", vec![], self.children(node)),
+            NodeValue::Superscript => element("sup", vec![], self.children(node)),
+            NodeValue::Link(link) => {
+                let mut attributes = vec![("href", Expr::String(self.safe_url(link.url)))];
+                if !link.title.is_empty() {
+                    attributes.push(("title", Expr::String(link.title)));
+                }
+                element("a", attributes, self.children(node))
+            }
+            NodeValue::Image(link) => {
+                let mut alt = String::new();
+                collect_text(node, &mut alt);
+                let mut attributes = vec![
+                    ("src", Expr::String(self.safe_url(link.url))),
+                    ("alt", Expr::String(alt)),
+                ];
+                if !link.title.is_empty() {
+                    attributes.push(("title", Expr::String(link.title)));
+                }
+                element("img", attributes, vec![])
+            }
+            NodeValue::ShortCode(shortcode) => Expr::string(shortcode.emoji()),
+            NodeValue::Math(math) => {
+                let style = if math.display_math {
+                    "display"
+                } else {
+                    "inline"
+                };
+                let tag = if math.dollar_math { "span" } else { "code" };
+                element(
+                    tag,
+                    vec![("data-math-style", Expr::string(style))],
+                    vec![Expr::String(math.literal)],
+                )
+            }
+        };
+
+        exprs.push(expr);
+    }
+}
+
+fn parse_common_mark(markup: &str, options: &Options) -> Vec<Expr> {
+    let arena = Arena::new();
+    let root = parse_document(&arena, markup, options);
+
+    let mut converter = AstConverter {
+        options,
+        anchorizer: Anchorizer::new(),
+        footnotes: Vec::new(),
+    };
+
+    let mut exprs = Vec::new();
+    converter.convert(root, &mut exprs);
+
+    if !converter.footnotes.is_empty() {
+        let footnotes = std::mem::take(&mut converter.footnotes);
+        exprs.push(element(
+            "section",
+            vec![
+                ("class", Expr::string("footnotes")),
+                ("data-footnotes", Expr::Bool(true)),
+            ],
+            vec![element("ol", vec![], footnotes)],
+        ));
+    }
+
+    exprs
+}
+
+/// Parses CommonMark markup into an Array of expressions, in the form rendered
+/// by `html/to-html`. The front matter is skipped, the options are the same as
+/// for `to-html`.
+/// ```tan
+/// (cmark/parse "# Hello *world*") ; => ['(h1 "Hello " (em "world"))]
+/// (html/to-html (cmark/parse post {:header-ids true}))
+/// ```
+pub fn common_mark_parse(args: &[Expr]) -> Result<Expr, Error> {
+    let markup = unpack_stringable_arg(args, 0, "markup")?;
+    let options = options_from_args(args, 1)?;
+
+    let comrak_options = comrak_options(&options)?;
+    let (_, markup) = strip_front_matter(markup, &options)?;
+
+    Ok(Expr::array(parse_common_mark(markup, &comrak_options)))
+}
+
+pub fn setup_lib_text_cmark_parse(context: &mut Context) {
+    let module = require_module("text/cmark", context);
+
+    module.insert_invocable("parse", Expr::foreign_func(&common_mark_parse));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use tan::expr::Expr;
+
+    use crate::cmark::{
+        comrak_options,
+        parse::{filter_tags, is_dangerous_url, parse_common_mark},
+    };
+
+    fn tag(expr: &Expr) -> &str {
+        let Expr::List(terms) = expr else {
+            panic!("expected a List");
+        };
+        terms[0].as_symbol().unwrap()
+    }
+
+    #[test]
+    fn parse_common_mark_usage() {
+        let options = HashMap::from([("header-ids".to_string(), Expr::Bool(true))]);
+        let options = comrak_options(&options).unwrap();
+
+        let exprs = parse_common_mark(
+            "# Hello *world*\n\n- [x] done\n- todo\n\n| a | b |\n|:-:|---|\n| 1 | 2 |\n\n<div>x</div>\n\nNote[^1].\n\n[^1]: A footnote.\n",
+            &options,
+        );
+
+        let tags: Vec<&str> = exprs.iter().map(tag).collect();
+        assert_eq!(tags, vec!["h1", "ul", "table", "p", "section"]);
+
+        let Expr::List(h1) = &exprs[0] else {
+            panic!("expected a List");
+        };
+        assert_eq!(
+            h1[1].as_map().unwrap()["id"].as_string(),
+            Some("hello-world")
+        );
+        assert!(matches!(&h1[2], Expr::String(s) if s == "Hello "));
+        assert_eq!(tag(&h1[3]), "em");
+
+        // Tight list items are not wrapped in paragraphs.
+        let Expr::List(ul) = &exprs[1] else {
+            panic!("expected a List");
+        };
+        let Expr::List(li) = &ul[1] else {
+            panic!("expected a List");
+        };
+        assert_eq!(tag(&li[1]), "input");
+        assert!(matches!(&li[3], Expr::String(s) if s == "done"));
+
+        // Raw HTML is omitted without the `unsafe` option.
+        let unsafe_options = HashMap::from([("unsafe".to_string(), Expr::Bool(true))]);
+        let unsafe_options = comrak_options(&unsafe_options).unwrap();
+        let exprs = parse_common_mark("<div>x</div>\n", &unsafe_options);
+        assert_eq!(tag(&exprs[0]), "raw");
+    }
+
+    #[test]
+    fn filter_tags_usage() {
+        assert_eq!(
+            filter_tags("<script>alert(1)</script>"),
+            "&lt;script>alert(1)&lt;/script>"
+        );
+        assert_eq!(
+            filter_tags("<TITLE x=1>a</Title >"),
+            "&lt;TITLE x=1>a&lt;/Title >"
+        );
+        assert_eq!(filter_tags("<xmp/>"), "&lt;xmp/>");
+        assert_eq!(filter_tags("<scripts> <b>é</b>"), "<scripts> <b>é</b>");
+        assert_eq!(filter_tags("<style"), "<style");
+    }
+
+    #[test]
+    fn parse_common_mark_filters_tags() {
+        let raw = |exprs: &[Expr]| {
+            let Expr::List(terms) = &exprs[0] else {
+                panic!("expected a List");
+            };
+            terms[1].as_string().unwrap().to_string()
+        };
+
+        let options = HashMap::from([
+            ("unsafe".to_string(), Expr::Bool(true)),
+            ("tagfilter".to_string(), Expr::Bool(true)),
+        ]);
+        let options = comrak_options(&options).unwrap();
+        let exprs = parse_common_mark("<div><script>x</script></div>\n", &options);
+        assert_eq!(raw(&exprs), "<div>&lt;script>x&lt;/script></div>\n");
+
+        // Inline HTML.
+        let exprs = parse_common_mark("a <iframe src=x> b\n", &options);
+        let Expr::List(p) = &exprs[0] else {
+            panic!("expected a List");
+        };
+        assert_eq!(raw(&p[2..]), "&lt;iframe src=x>");
+
+        // Without the option, the HTML is kept as is.
+        let options = HashMap::from([("unsafe".to_string(), Expr::Bool(true))]);
+        let options = comrak_options(&options).unwrap();
+        let exprs = parse_common_mark("<div><script>x</script></div>\n", &options);
+        assert_eq!(raw(&exprs), "<div><script>x</script></div>\n");
+    }
+
+    #[test]
+    fn is_dangerous_url_usage() {
+        assert!(is_dangerous_url("javascript:alert(1)"));
+        assert!(is_dangerous_url("JavaScript:alert(1)"));
+        assert!(is_dangerous_url("vbscript:msgbox"));
+        assert!(is_dangerous_url("file:///etc/passwd"));
+        assert!(is_dangerous_url("data:text/html,<script>"));
+        assert!(is_dangerous_url("data:image/svg+xml,<svg>"));
+        assert!(!is_dangerous_url("data:image/png;base64,iVBORw0"));
+        assert!(!is_dangerous_url("DATA:image/webp;base64,UklGR"));
+        assert!(!is_dangerous_url("https://tan.dev"));
+        assert!(!is_dangerous_url("/javascript:"));
+    }
+
+    #[test]
+    fn parse_common_mark_blanks_dangerous_urls() {
+        let markup = "[a](javascript:alert(1)) [b](https://tan.dev) ![c](data:text/html,x) ![d](data:image/gif;base64,R0)\n";
+
+        let url = |exprs: &[Expr], index: usize, name: &str| {
+            let Expr::List(p) = &exprs[0] else {
+                panic!("expected a List");
+            };
+            let Expr::List(element) = &p[index] else {
+                panic!("expected a List");
+            };
+            element[1].as_map().unwrap()[name]
+                .as_string()
+                .unwrap()
+                .to_string()
+        };
+
+        let options = comrak_options(&HashMap::new()).unwrap();
+        let exprs = parse_common_mark(markup, &options);
+        assert_eq!(url(&exprs, 1, "href"), "");
+        assert_eq!(url(&exprs, 3, "href"), "https://tan.dev");
+        assert_eq!(url(&exprs, 5, "src"), "");
+        assert_eq!(url(&exprs, 7, "src"), "data:image/gif;base64,R0");
+
+        // The URLs are kept with the `unsafe` option.
+        let unsafe_options = HashMap::from([("unsafe".to_string(), Expr::Bool(true))]);
+        let unsafe_options = comrak_options(&unsafe_options).unwrap();
+        let exprs = parse_common_mark(markup, &unsafe_options);
+        assert_eq!(url(&exprs, 1, "href"), "javascript:alert(1)");
+        assert_eq!(url(&exprs, 5, "src"), "data:text/html,x");
+    }
+}
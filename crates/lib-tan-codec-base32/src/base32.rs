@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+
+use base32::Alphabet;
+use tan::{
+    context::Context,
+    error::Error,
+    expr::Expr,
+    util::{args::unpack_stringable_arg, module_util::require_module},
+};
+use tanutil::{
+    args::{options_from_args, unpack_bytes_arg},
+    buffer::decoded_bytes_expr,
+};
+
+// #todo Support the z-base-32 alphabet.
+
+fn alphabet_from_options(options: &HashMap<String, Expr>) -> Result<Alphabet, Error> {
+    let padding = match options.get("padding").map(|p| p.unpack()) {
+        None => true,
+        Some(Expr::Bool(padding)) => *padding,
+        Some(padding) => {
+            return Err(Error::invalid_arguments(
+                "`padding` option should be a Bool",
+                padding.range(),
+            ));
+        }
+    };
+
+    let Some(alphabet) = options.get("alphabet") else {
+        return Ok(Alphabet::Rfc4648 { padding });
+    };
+
+    match alphabet.as_stringable() {
+        Some("standard") => Ok(Alphabet::Rfc4648 { padding }),
+        Some("hex") => Ok(Alphabet::Rfc4648Hex { padding }),
+        // #insight Crockford's alphabet is never padded.
+        Some("crockford") => Ok(Alphabet::Crockford),
+        _ => Err(Error::invalid_arguments(
+            "`alphabet` option should be :standard, :hex or :crockford",
+            alphabet.range(),
+        )),
+    }
+}
+
+fn encode(bytes: &[u8], alphabet: Alphabet, is_lowercase: bool) -> String {
+    let string = base32::encode(alphabet, bytes);
+
+    if is_lowercase {
+        string.to_lowercase()
+    } else {
+        string
+    }
+}
+
+// #insight
+// Decoding is case-insensitive and accepts both padded and unpadded input.
+fn decode(string: &str, alphabet: Alphabet) -> Option<Vec<u8>> {
+    let alphabet = match alphabet {
+        Alphabet::Rfc4648 { .. } => Alphabet::Rfc4648 { padding: true },
+        Alphabet::Rfc4648Hex { .. } => Alphabet::Rfc4648Hex { padding: true },
+        alphabet => alphabet,
+    };
+
+    base32::decode(alphabet, &string.to_uppercase())
+}
+
+/// Encodes a Buffer or String as base32 (RFC 4648), returns a String.
+/// ```tan
+/// (base32/encode "tan") ; => "ORQW4==="
+/// (base32/encode secret {:padding false :lowercase true})
+/// (base32/encode id {:alphabet :crockford})
+/// ```
+pub fn base32_encode(args: &[Expr]) -> Result<Expr, Error> {
+    let bytes = unpack_bytes_arg(args, 0, "data")?;
+    let options = options_from_args(args, 1);
+
+    let alphabet = alphabet_from_options(&options)?;
+    let is_lowercase = matches!(
+        options.get("lowercase").map(|l| l.unpack()),
+        Some(Expr::Bool(true))
+    );
+
+    Ok(Expr::string(encode(&bytes, alphabet, is_lowercase)))
+}
+
+/// Decodes a base32 String, returns a Buffer.
+/// ```tan
+/// (base32/decode "ORQW4===") ; => Buffer
+/// (base32/decode "orqw4" {:encoding :utf8}) ; => "tan"
+/// ```
+pub fn base32_decode(args: &[Expr]) -> Result<Expr, Error> {
+    let string = unpack_stringable_arg(args, 0, "string")?;
+    let options = options_from_args(args, 1);
+
+    let alphabet = alphabet_from_options(&options)?;
+
+    let Some(bytes) = decode(string, alphabet) else {
+        return Err(Error::invalid_arguments("invalid base32", args[0].range()));
+    };
+
+    decoded_bytes_expr(bytes, &options, &args[0])
+}
+
+pub fn import_lib_codec_base32(context: &mut Context) {
+    let module = require_module("codec/base32", context);
+
+    module.insert_invocable("encode", Expr::foreign_func(&base32_encode));
+    module.insert_invocable("decode", Expr::foreign_func(&base32_decode));
+}
+
+#[cfg(test)]
+mod tests {
+    use base32::Alphabet;
+
+    use crate::base32::{decode, encode};
+
+    #[test]
+    fn encode_decode_usage() {
+        let alphabet = Alphabet::Rfc4648 { padding: true };
+        assert_eq!(encode(b"tan", alphabet, false), "ORQW4===");
+        assert_eq!(
+            encode(b"tan", Alphabet::Rfc4648 { padding: false }, true),
+            "orqw4"
+        );
+
+        assert_eq!(decode("ORQW4===", alphabet).unwrap(), b"tan");
+        assert_eq!(decode("orqw4", alphabet).unwrap(), b"tan");
+        assert!(decode("ORQW1", alphabet).is_none());
+
+        let alphabet = Alphabet::Crockford;
+        assert_eq!(
+            decode(&encode(b"tan", alphabet, true), alphabet).unwrap(),
+            b"tan"
+        );
+    }
+}
@@ -0,0 +1,9 @@
+use base32::import_lib_codec_base32;
+use tan::context::Context;
+
+pub mod base32;
+
+#[no_mangle]
+pub fn install_foreign_dyn_lib(context: &mut Context) {
+    import_lib_codec_base32(context);
+}
@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+
+use base64::{
+    alphabet,
+    engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig},
+    Engine,
+};
+use tan::{
+    context::Context,
+    error::Error,
+    expr::Expr,
+    util::{args::unpack_stringable_arg, module_util::require_module},
+};
+use tanutil::{
+    args::{options_from_args, unpack_bytes_arg},
+    buffer::decoded_bytes_expr,
+};
+
+// #todo Support MIME (line-wrapped) base64.
+// #todo Add decode-data-uri.
+
+// #insight
+// Encoding accepts a Buffer or a String (as UTF-8 bytes) and returns a String,
+// decoding returns a Buffer, or a String with the `{:encoding :utf8}` option.
+
+fn alphabet_from_options(
+    options: &HashMap<String, Expr>,
+) -> Result<&'static alphabet::Alphabet, Error> {
+    let Some(alphabet) = options.get("alphabet") else {
+        return Ok(&alphabet::STANDARD);
+    };
+
+    match alphabet.as_stringable() {
+        Some("standard") => Ok(&alphabet::STANDARD),
+        Some("url-safe") | Some("url") => Ok(&alphabet::URL_SAFE),
+        _ => Err(Error::invalid_arguments(
+            "`alphabet` option should be :standard or :url-safe",
+            alphabet.range(),
+        )),
+    }
+}
+
+fn padding_from_options(options: &HashMap<String, Expr>) -> Result<Option<bool>, Error> {
+    let Some(padding) = options.get("padding") else {
+        return Ok(None);
+    };
+
+    match padding.unpack() {
+        Expr::Bool(padding) => Ok(Some(*padding)),
+        _ => Err(Error::invalid_arguments(
+            "`padding` option should be a Bool",
+            padding.range(),
+        )),
+    }
+}
+
+fn encode(bytes: &[u8], alphabet: &alphabet::Alphabet, padding: bool) -> String {
+    let config = GeneralPurposeConfig::new().with_encode_padding(padding);
+    GeneralPurpose::new(alphabet, config).encode(bytes)
+}
+
+// #insight
+// Decoding accepts both padded and unpadded input, unless the `padding` option
+// is given, e.g. `{:padding true}` rejects unpadded input.
+fn decode(
+    string: &str,
+    alphabet: &alphabet::Alphabet,
+    padding: Option<bool>,
+) -> Result<Vec<u8>, base64::DecodeError> {
+    let padding_mode = match padding {
+        Some(true) => DecodePaddingMode::RequireCanonical,
+        Some(false) => DecodePaddingMode::RequireNone,
+        None => DecodePaddingMode::Indifferent,
+    };
+    let config = GeneralPurposeConfig::new().with_decode_padding_mode(padding_mode);
+    GeneralPurpose::new(alphabet, config).decode(string)
+}
+
+/// Encodes a Buffer or String as base64, returns a String.
+/// ```tan
+/// (base64/encode "hello") ; => "aGVsbG8="
+/// (base64/encode token {:alphabet :url-safe :padding false})
+/// ```
+pub fn base64_encode(args: &[Expr]) -> Result<Expr, Error> {
+    let bytes = unpack_bytes_arg(args, 0, "data")?;
+    let options = options_from_args(args, 1);
+
+    let alphabet = alphabet_from_options(&options)?;
+    let padding = padding_from_options(&options)?.unwrap_or(true);
+
+    Ok(Expr::string(encode(&bytes, alphabet, padding)))
+}
+
+/// Decodes a base64 String, returns a Buffer.
+/// ```tan
+/// (base64/decode "aGVsbG8=") ; => Buffer
+/// (base64/decode "aGVsbG8" {:encoding :utf8}) ; => "hello"
+/// ```
+pub fn base64_decode(args: &[Expr]) -> Result<Expr, Error> {
+    let string = unpack_stringable_arg(args, 0, "string")?;
+    let options = options_from_args(args, 1);
+
+    let alphabet = alphabet_from_options(&options)?;
+    let padding = padding_from_options(&options)?;
+
+    let bytes = decode(string, alphabet, padding).map_err(|error| {
+        Error::invalid_arguments(&format!("invalid base64: {error}"), args[0].range())
+    })?;
+
+    decoded_bytes_expr(bytes, &options, &args[0])
+}
+
+/// Encodes a Buffer or String as a base64 data URI, e.g. to embed an image in
+/// an HTML page.
+/// ```tan
+/// (let src (base64/encode-data-uri png-buffer "image/png"))
+/// ['img {:src src}]
+/// ```
+pub fn base64_encode_data_uri(args: &[Expr]) -> Result<Expr, Error> {
+    let bytes = unpack_bytes_arg(args, 0, "data")?;
+    let media_type = unpack_stringable_arg(args, 1, "media-type")?;
+
+    let data = encode(&bytes, &alphabet::STANDARD, true);
+
+    Ok(Expr::string(format!("data:{media_type};base64,{data}")))
+}
+
+pub fn import_lib_codec_base64(context: &mut Context) {
+    let module = require_module("codec/base64", context);
+
+    module.insert_invocable("encode", Expr::foreign_func(&base64_encode));
+    module.insert_invocable("decode", Expr::foreign_func(&base64_decode));
+    module.insert_invocable(
+        "encode-data-uri",
+        Expr::foreign_func(&base64_encode_data_uri),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::alphabet;
+
+    use crate::base64::{decode, encode};
+
+    #[test]
+    fn encode_decode_usage() {
+        assert_eq!(encode(b"hello", &alphabet::STANDARD, true), "aGVsbG8=");
+        assert_eq!(encode(b"hello", &alphabet::STANDARD, false), "aGVsbG8");
+        assert_eq!(encode(&[0xfb, 0xff], &alphabet::URL_SAFE, false), "-_8");
+
+        assert_eq!(
+            decode("aGVsbG8=", &alphabet::STANDARD, None).unwrap(),
+            b"hello"
+        );
+        assert_eq!(
+            decode("aGVsbG8", &alphabet::STANDARD, None).unwrap(),
+            b"hello"
+        );
+        assert!(decode("aGVsbG8", &alphabet::STANDARD, Some(true)).is_err());
+        assert!(decode("-_8", &alphabet::STANDARD, None).is_err());
+    }
+}
@@ -0,0 +1,9 @@
+use base64::import_lib_codec_base64;
+use tan::context::Context;
+
+pub mod base64;
+
+#[no_mangle]
+pub fn install_foreign_dyn_lib(context: &mut Context) {
+    import_lib_codec_base64(context);
+}
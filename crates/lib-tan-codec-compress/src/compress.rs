@@ -0,0 +1,469 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, Read, Write},
+    sync::Arc,
+};
+
+use flate2::{read::MultiGzDecoder, write::GzEncoder, Compression};
+use tan::{
+    context::Context,
+    error::Error,
+    expr::Expr,
+    util::{args::unpack_arg, expect_lock_write, module_util::require_module},
+};
+use tanutil::{
+    args::{options_from_args, unpack_bytes_arg},
+    buffer::buffer_expr,
+};
+
+// #todo Support deflate, zlib, xz, lz4.
+// #todo Support streaming encoders/decoders as Tan values.
+// #todo Consider returning the number of bytes written by the file functions.
+
+// #insight
+// The codec is given with the `:codec` option, for files it can be inferred
+// from the target (encode) or source (decode) extension.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Codec {
+    Gzip,
+    Zstd,
+    Brotli,
+}
+
+impl Codec {
+    fn from_name(name: &str) -> Option<Codec> {
+        match name {
+            "gzip" | "gz" => Some(Codec::Gzip),
+            "zstd" | "zst" => Some(Codec::Zstd),
+            "brotli" | "br" => Some(Codec::Brotli),
+            _ => None,
+        }
+    }
+
+    fn from_path(path: &str) -> Option<Codec> {
+        let (_, extension) = path.rsplit_once('.')?;
+        Codec::from_name(&extension.to_lowercase())
+    }
+
+    // #insight Brotli defaults to the maximum quality, like the `brotli` CLI.
+    fn default_level(self) -> i32 {
+        match self {
+            Codec::Gzip => 6,
+            Codec::Zstd => 3,
+            Codec::Brotli => 11,
+        }
+    }
+
+    fn level_range(self) -> (i32, i32) {
+        match self {
+            Codec::Gzip => (0, 9),
+            Codec::Zstd => (1, 22),
+            Codec::Brotli => (0, 11),
+        }
+    }
+}
+
+/// Compresses the bytes of a reader into a writer.
+fn encode(
+    codec: Codec,
+    level: i32,
+    mut reader: impl Read,
+    mut writer: impl Write,
+) -> io::Result<()> {
+    match codec {
+        Codec::Gzip => {
+            let mut encoder = GzEncoder::new(writer, Compression::new(level as u32));
+            io::copy(&mut reader, &mut encoder)?;
+            encoder.finish()?;
+        }
+        Codec::Zstd => {
+            zstd::stream::copy_encode(reader, writer, level)?;
+        }
+        Codec::Brotli => {
+            // #insight 22 is the recommended window size.
+            let params = brotli::enc::BrotliEncoderParams {
+                quality: level,
+                lgwin: 22,
+                ..Default::default()
+            };
+            // #insight Unlike `CompressorWriter`, errors while finishing the stream are reported.
+            brotli::BrotliCompress(&mut reader, &mut writer, &params)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Decompresses the bytes of a reader into a writer.
+fn decode(codec: Codec, reader: impl Read, mut writer: impl Write) -> io::Result<()> {
+    match codec {
+        // #insight Concatenated (multi-member) gzip streams are decoded as a whole.
+        Codec::Gzip => {
+            let mut decoder = MultiGzDecoder::new(reader);
+            io::copy(&mut decoder, &mut writer)?;
+        }
+        Codec::Zstd => {
+            zstd::stream::copy_decode(reader, writer)?;
+        }
+        Codec::Brotli => {
+            let mut decoder = brotli::Decompressor::new(reader, 4096);
+            io::copy(&mut decoder, &mut writer)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn codec_from_options(options: &HashMap<String, Expr>, path: Option<&str>) -> Result<Codec, Error> {
+    if let Some(codec) = options.get("codec") {
+        let Some(name) = codec.as_stringable() else {
+            return Err(Error::invalid_arguments(
+                "`codec` option should be a String",
+                codec.range(),
+            ));
+        };
+
+        return Codec::from_name(name).ok_or_else(|| {
+            Error::invalid_arguments(&format!("unsupported codec `{name}`"), codec.range())
+        });
+    }
+
+    match path {
+        Some(path) => Codec::from_path(path).ok_or_else(|| {
+            Error::invalid_arguments(
+                &format!("cannot infer the codec of `{path}`, use the `codec` option"),
+                None,
+            )
+        }),
+        // #insight Buffers default to gzip.
+        None => Ok(Codec::Gzip),
+    }
+}
+
+fn level_from_options(codec: Codec, options: &HashMap<String, Expr>) -> Result<i32, Error> {
+    let Some(level) = options.get("level") else {
+        return Ok(codec.default_level());
+    };
+
+    let (min, max) = codec.level_range();
+
+    match level.as_int() {
+        Some(level) if (min as i64..=max as i64).contains(&level) => Ok(level as i32),
+        _ => Err(Error::invalid_arguments(
+            &format!("`level` option should be an Int between {min} and {max}"),
+            level.range(),
+        )),
+    }
+}
+
+/// Returns the path of a File-or-Path argument, None for File values.
+fn unpack_path_arg<'a>(
+    args: &'a [Expr],
+    index: usize,
+    name: &str,
+) -> Result<Option<&'a str>, Error> {
+    let expr = unpack_arg(args, index, name)?;
+
+    if let Expr::ForeignMut(_) = expr.unpack() {
+        return Ok(None);
+    }
+
+    let Some(path) = expr.as_stringable() else {
+        return Err(Error::invalid_arguments(
+            &format!("`{name}` argument should be a File or a Path"),
+            expr.range(),
+        ));
+    };
+
+    Ok(Some(path))
+}
+
+/// Compresses a Buffer or String, returns a Buffer.
+/// ```tan
+/// (let compressed (compress/encode data {:codec :zstd :level 19}))
+/// ```
+pub fn compress_encode(args: &[Expr]) -> Result<Expr, Error> {
+    let bytes = unpack_bytes_arg(args, 0, "data")?;
+    let options = options_from_args(args, 1);
+
+    let codec = codec_from_options(&options, None)?;
+    let level = level_from_options(codec, &options)?;
+
+    let mut output: Vec<u8> = Vec::new();
+    encode(codec, level, &bytes[..], &mut output)?;
+
+    Ok(buffer_expr(output))
+}
+
+/// Decompresses a Buffer, returns a Buffer.
+/// ```tan
+/// (let data (compress/decode compressed {:codec :zstd}))
+/// ```
+pub fn compress_decode(args: &[Expr]) -> Result<Expr, Error> {
+    let bytes = unpack_bytes_arg(args, 0, "data")?;
+    let options = options_from_args(args, 1);
+
+    let codec = codec_from_options(&options, None)?;
+
+    let mut output: Vec<u8> = Vec::new();
+    if let Err(io_error) = decode(codec, &bytes[..], &mut output) {
+        let mut error = Error::from(io_error);
+        error.push_note(&format!("while decoding {codec:?} data"), args[0].range());
+        return Err(error);
+    }
+
+    Ok(buffer_expr(output))
+}
+
+/// Returns the metadata of a File-or-Path argument, None if it does not exist.
+#[cfg(unix)]
+fn file_metadata(expr: &Expr, path: Option<&str>) -> Option<std::fs::Metadata> {
+    match (path, expr.unpack()) {
+        (Some(path), _) => std::fs::metadata(path).ok(),
+        (None, Expr::ForeignMut(file)) => {
+            let file = tan::util::expect_lock_read(file);
+            file.downcast_ref::<File>()?.metadata().ok()
+        }
+        _ => None,
+    }
+}
+
+/// Checks if two File-or-Path arguments refer to the same file, by file
+/// identity on Unix, by canonical path elsewhere.
+fn is_same_file(
+    source: &Expr,
+    source_path: Option<&str>,
+    target: &Expr,
+    target_path: Option<&str>,
+) -> bool {
+    if let (Expr::ForeignMut(source), Expr::ForeignMut(target)) = (source.unpack(), target.unpack())
+    {
+        if Arc::ptr_eq(source, target) {
+            return true;
+        }
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        let (Some(source), Some(target)) = (
+            file_metadata(source, source_path),
+            file_metadata(target, target_path),
+        ) else {
+            return false;
+        };
+        source.dev() == target.dev() && source.ino() == target.ino()
+    }
+
+    #[cfg(not(unix))]
+    {
+        let (Some(source), Some(target)) = (source_path, target_path) else {
+            return false;
+        };
+        match (std::fs::canonicalize(source), std::fs::canonicalize(target)) {
+            (Ok(source), Ok(target)) => source == target,
+            _ => false,
+        }
+    }
+}
+
+/// Streams the source into the target, (de)compressing on the fly.
+fn transcode_file(args: &[Expr], is_encode: bool) -> Result<Expr, Error> {
+    let source_path = unpack_path_arg(args, 0, "source")?;
+    let target_path = unpack_path_arg(args, 1, "target")?;
+    let options = options_from_args(args, 2);
+
+    let codec_path = if is_encode { target_path } else { source_path };
+
+    let codec = if options.contains_key("codec") || codec_path.is_some() {
+        codec_from_options(&options, codec_path)?
+    } else {
+        return Err(Error::invalid_arguments(
+            "cannot infer the codec of a File value, use the `codec` option",
+            None,
+        ));
+    };
+    let level = level_from_options(codec, &options)?;
+
+    // #insight Transcoding a file in place would truncate (or deadlock on) the source.
+    if is_same_file(&args[0], source_path, &args[1], target_path) {
+        return Err(Error::invalid_arguments(
+            "`source` and `target` should be different files",
+            args[1].range(),
+        ));
+    }
+
+    let source_guard;
+    let source_file;
+    let source: &File = match (source_path, args[0].unpack()) {
+        (Some(path), _) => {
+            source_file = File::open(path)?;
+            &source_file
+        }
+        (None, Expr::ForeignMut(file)) => {
+            source_guard = expect_lock_write(file);
+            source_guard
+                .downcast_ref::<File>()
+                .ok_or_else(|| Error::invalid_arguments("invalid File", args[0].range()))?
+        }
+        _ => unreachable!(),
+    };
+
+    let target_guard;
+    let target_file;
+    let target: &File = match (target_path, args[1].unpack()) {
+        (Some(path), _) => {
+            target_file = File::create(path)?;
+            &target_file
+        }
+        (None, Expr::ForeignMut(file)) => {
+            target_guard = expect_lock_write(file);
+            target_guard
+                .downcast_ref::<File>()
+                .ok_or_else(|| Error::invalid_arguments("invalid File", args[1].range()))?
+        }
+        _ => unreachable!(),
+    };
+
+    let reader = io::BufReader::new(source);
+    let mut writer = io::BufWriter::new(target);
+
+    if is_encode {
+        encode(codec, level, reader, &mut writer)?;
+    } else {
+        decode(codec, reader, &mut writer)?;
+    }
+
+    writer.flush()?;
+
+    // #todo What is a good return value?
+    Ok(Expr::None)
+}
+
+/// Compresses a file into another file, without loading it into memory.
+/// ```tan
+/// (compress/encode-file "public/app.js" "public/app.js.br")
+/// (compress/encode-file "cache.tan" "cache.tan.zst" {:level 19})
+/// ```
+pub fn compress_encode_file(args: &[Expr]) -> Result<Expr, Error> {
+    transcode_file(args, true)
+}
+
+/// Decompresses a file into another file, without loading it into memory.
+/// ```tan
+/// (compress/decode-file "cache.tan.zst" "cache.tan")
+/// ```
+pub fn compress_decode_file(args: &[Expr]) -> Result<Expr, Error> {
+    transcode_file(args, false)
+}
+
+pub fn import_lib_codec_compress(context: &mut Context) {
+    // #todo Consider `codec/compression`.
+    let module = require_module("codec/compress", context);
+
+    module.insert_invocable("encode", Expr::foreign_func(&compress_encode));
+    module.insert_invocable("decode", Expr::foreign_func(&compress_decode));
+
+    module.insert_invocable("encode-file", Expr::foreign_func(&compress_encode_file));
+    module.insert_invocable("decode-file", Expr::foreign_func(&compress_decode_file));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, RwLock};
+
+    use tan::expr::Expr;
+
+    use crate::compress::{compress_encode_file, decode, encode, Codec};
+
+    #[test]
+    fn encode_decode_roundtrip() {
+        let input = "Tan ".repeat(1000);
+
+        for codec in [Codec::Gzip, Codec::Zstd, Codec::Brotli] {
+            let mut compressed: Vec<u8> = Vec::new();
+            encode(
+                codec,
+                codec.default_level(),
+                input.as_bytes(),
+                &mut compressed,
+            )
+            .unwrap();
+            assert!(compressed.len() < input.len());
+
+            let mut decompressed: Vec<u8> = Vec::new();
+            decode(codec, &compressed[..], &mut decompressed).unwrap();
+            assert_eq!(decompressed, input.as_bytes());
+        }
+    }
+
+    #[test]
+    fn codec_from_path_usage() {
+        assert_eq!(Codec::from_path("app.js.gz"), Some(Codec::Gzip));
+        assert_eq!(Codec::from_path("cache.tan.ZST"), Some(Codec::Zstd));
+        assert_eq!(Codec::from_path("style.css.br"), Some(Codec::Brotli));
+        assert_eq!(Codec::from_path("style.css"), None);
+    }
+
+    #[test]
+    fn transcode_same_file_is_rejected() {
+        let path = std::env::temp_dir().join(format!("tan-compress-{}.gz", std::process::id()));
+        std::fs::write(&path, "Tan").unwrap();
+        let file = std::fs::File::open(&path).unwrap();
+        let file = Expr::ForeignMut(Arc::new(RwLock::new(file)));
+
+        let options = Expr::map(std::collections::HashMap::from([(
+            "codec".to_string(),
+            Expr::string("gzip"),
+        )]));
+        let result = compress_encode_file(&[file.clone(), file, options]);
+        assert!(result.is_err());
+
+        let path_arg = Expr::string(path.to_string_lossy());
+        let result = compress_encode_file(&[path_arg.clone(), path_arg]);
+        assert!(result.is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "Tan");
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn transcode_same_file_by_identity_is_rejected() {
+        let dir =
+            std::env::temp_dir().join(format!("tan-compress-identity-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("a.gz");
+        std::fs::write(&path, "Tan").unwrap();
+
+        // #insight The same file, spelled differently.
+        let source = Expr::string(path.to_string_lossy());
+        let target = Expr::string(dir.join(".").join("a.gz").to_string_lossy());
+        let result = compress_encode_file(&[source.clone(), target]);
+        assert!(result.is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "Tan");
+
+        // #insight The same file, given as a Path and as a File.
+        let file = std::fs::File::open(&path).unwrap();
+        let file = Expr::ForeignMut(Arc::new(RwLock::new(file)));
+        let result = compress_encode_file(&[file, source]);
+        assert!(result.is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "Tan");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn decode_multi_member_gzip() {
+        let mut compressed: Vec<u8> = Vec::new();
+        for part in ["Tan ", "rocks"] {
+            encode(Codec::Gzip, 6, part.as_bytes(), &mut compressed).unwrap();
+        }
+
+        let mut decompressed: Vec<u8> = Vec::new();
+        decode(Codec::Gzip, &compressed[..], &mut decompressed).unwrap();
+        assert_eq!(decompressed, b"Tan rocks");
+    }
+}
@@ -0,0 +1,10 @@
+use compress::import_lib_codec_compress;
+use tan::context::Context;
+
+pub mod compress;
+
+// #todo Find a good name for this: considere import_*, link_*
+#[no_mangle]
+pub fn install_foreign_dyn_lib(context: &mut Context) {
+    import_lib_codec_compress(context);
+}
@@ -0,0 +1,99 @@
+use tan::{
+    context::Context,
+    error::Error,
+    expr::Expr,
+    util::{args::unpack_stringable_arg, module_util::require_module},
+};
+use tanutil::{
+    args::{options_from_args, unpack_bytes_arg},
+    buffer::decoded_bytes_expr,
+};
+
+/// Encodes a Buffer or String as lowercase hexadecimal, returns a String.
+/// ```tan
+/// (hex/encode "tan") ; => "74616e"
+/// (hex/encode digest {:uppercase true})
+/// ```
+pub fn hex_encode(args: &[Expr]) -> Result<Expr, Error> {
+    let bytes = unpack_bytes_arg(args, 0, "data")?;
+    let options = options_from_args(args, 1);
+
+    let is_uppercase = matches!(
+        options.get("uppercase").map(|u| u.unpack()),
+        Some(Expr::Bool(true))
+    );
+
+    let string = if is_uppercase {
+        hex::encode_upper(bytes)
+    } else {
+        hex::encode(bytes)
+    };
+
+    Ok(Expr::string(string))
+}
+
+/// Decodes a hexadecimal String (in any case), returns a Buffer.
+/// ```tan
+/// (hex/decode "74616E") ; => Buffer
+/// (hex/decode "74616e" {:encoding :utf8}) ; => "tan"
+/// ```
+pub fn hex_decode(args: &[Expr]) -> Result<Expr, Error> {
+    let string = unpack_stringable_arg(args, 0, "string")?;
+    let options = options_from_args(args, 1);
+
+    let bytes = hex::decode(string).map_err(|error| {
+        Error::invalid_arguments(&format!("invalid hex: {error}"), args[0].range())
+    })?;
+
+    decoded_bytes_expr(bytes, &options, &args[0])
+}
+
+pub fn import_lib_codec_hex(context: &mut Context) {
+    let module = require_module("codec/hex", context);
+
+    module.insert_invocable("encode", Expr::foreign_func(&hex_encode));
+    module.insert_invocable("decode", Expr::foreign_func(&hex_decode));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{Arc, RwLock},
+    };
+
+    use tan::expr::Expr;
+
+    use crate::hex::{hex_decode, hex_encode};
+
+    #[test]
+    fn hex_encode_usage() {
+        let result = hex_encode(&[Expr::string("tan")]).unwrap();
+        assert_eq!(result.as_string(), Some("74616e"));
+
+        let buffer = Expr::Buffer(2, Arc::new(RwLock::new(vec![0xca, 0xfe, 0xff])));
+        let options = Expr::map(HashMap::from([("uppercase".to_string(), Expr::Bool(true))]));
+        let result = hex_encode(&[buffer, options]).unwrap();
+        assert_eq!(result.as_string(), Some("CAFE"));
+
+        assert!(hex_encode(&[Expr::Int(1)]).is_err());
+    }
+
+    #[test]
+    fn hex_decode_usage() {
+        let result = hex_decode(&[Expr::string("74616E")]).unwrap();
+        let (length, bytes) = result.as_buffer().unwrap();
+        assert_eq!(&bytes[..length], b"tan");
+
+        let options = Expr::map(HashMap::from([(
+            "encoding".to_string(),
+            Expr::key_symbol("utf8"),
+        )]));
+        let result = hex_decode(&[Expr::string("74616e"), options.clone()]).unwrap();
+        assert_eq!(result.as_string(), Some("tan"));
+
+        assert!(hex_decode(&[Expr::string("74616")]).is_err());
+        assert!(hex_decode(&[Expr::string("zz")]).is_err());
+        assert!(hex_decode(&[Expr::string("ff"), options]).is_err());
+    }
+}
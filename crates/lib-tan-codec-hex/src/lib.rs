@@ -0,0 +1,9 @@
+use hex::import_lib_codec_hex;
+use tan::context::Context;
+
+pub mod hex;
+
+#[no_mangle]
+pub fn install_foreign_dyn_lib(context: &mut Context) {
+    import_lib_codec_hex(context);
+}
@@ -1,21 +1,118 @@
+use std::path::{Component, Path, PathBuf};
+
 use tan::{
     context::Context,
     error::Error,
     expr::Expr,
     util::{
+        args::{unpack_map_arg, unpack_stringable_arg},
         fs::{get_dirname, get_full_extension},
         module_util::require_module,
     },
 };
+use tanutil::path::{glob_matcher_from_pattern, path_expr};
 
 // #todo Consider removing from core.
 
+// #insight
+// A `Path` is a String annotated with the `Path` type, similar to `Regex`.
+// All path functions accept both Strings and Paths.
+
 // #todo consider to associate most functions to the `Path` type.
 // #todo support (path :extension)
 // #todo support (path :full-extension)
 // #todo support (path :filename)
 // #todo support (path :directory)
 // #todo implement (get-parent ..)
+// #todo Consider Windows path separators.
+
+// #insight Does not touch the file-system, symlinks are not resolved.
+/// Lexically resolves `.` and `..` components.
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized: Vec<Component> = Vec::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => match normalized.last() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                }
+                // #insight `/..` is `/`.
+                Some(Component::RootDir) | Some(Component::Prefix(_)) => (),
+                // #insight Leading `..` are preserved in relative paths.
+                _ => normalized.push(component),
+            },
+            _ => normalized.push(component),
+        }
+    }
+
+    if normalized.is_empty() {
+        PathBuf::from(".")
+    } else {
+        normalized.iter().collect()
+    }
+}
+
+/// Computes the path that leads from `base` to `path`, lexically.
+/// Returns None if only one of the paths is absolute.
+pub fn relative_path(path: &Path, base: &Path) -> Option<PathBuf> {
+    if path.is_absolute() != base.is_absolute() {
+        return None;
+    }
+
+    let path = normalize_path(path);
+    let base = normalize_path(base);
+
+    let path_components: Vec<Component> = path
+        .components()
+        .filter(|c| *c != Component::CurDir)
+        .collect();
+    let base_components: Vec<Component> = base
+        .components()
+        .filter(|c| *c != Component::CurDir)
+        .collect();
+
+    let common_count = path_components
+        .iter()
+        .zip(base_components.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    // #insight Cannot climb out of a base that starts with `..`.
+    if base_components[common_count..].contains(&Component::ParentDir) {
+        return None;
+    }
+
+    let mut relative = PathBuf::new();
+
+    for _ in common_count..base_components.len() {
+        relative.push("..");
+    }
+
+    for component in &path_components[common_count..] {
+        relative.push(component);
+    }
+
+    if relative.as_os_str().is_empty() {
+        Some(PathBuf::from("."))
+    } else {
+        Some(relative)
+    }
+}
+
+// #todo Consider a `from` argument with a different type, e.g. Array.
+/// Creates a Path, the optional extra segments are joined.
+/// ```tan
+/// (let path (Path "content/blog"))
+/// ```
+pub fn path_new(args: &[Expr]) -> Result<Expr, Error> {
+    if args.is_empty() {
+        return Err(Error::invalid_arguments("requires a `path` argument", None));
+    }
+
+    path_join(args)
+}
 
 // #todo should it include the final `/`?
 /// Returns the directory part of a path.
@@ -37,9 +134,7 @@ pub fn path_get_dirname(args: &[Expr]) -> Result<Expr, Error> {
     // #todo should return a Maybe.
     let dirname = get_dirname(path).unwrap_or("");
 
-    // #todo should return a `Path` value.
-
-    Ok(Expr::string(dirname))
+    Ok(path_expr(dirname))
 }
 
 /// Returns the 'full' extension of a path.
@@ -66,14 +161,251 @@ pub fn path_get_extension(args: &[Expr]) -> Result<Expr, Error> {
     Ok(Expr::string(extension))
 }
 
+/// Joins path segments, an absolute segment replaces the preceding ones.
+/// ```tan
+/// (path/join "content" "blog" "post.md") ; => "content/blog/post.md"
+/// ```
+pub fn path_join(args: &[Expr]) -> Result<Expr, Error> {
+    if args.is_empty() {
+        return Err(Error::invalid_arguments(
+            "`join` requires at least one `segment` argument",
+            None,
+        ));
+    }
+
+    let mut path = PathBuf::new();
+
+    for (i, segment) in args.iter().enumerate() {
+        let Some(segment) = segment.as_stringable() else {
+            return Err(Error::invalid_arguments(
+                &format!("segment {i} `{segment}` should be a String or Path"),
+                segment.range(),
+            ));
+        };
+        path.push(segment);
+    }
+
+    Ok(path_expr(path))
+}
+
+/// Returns the final component of a path, or None.
+/// ```tan
+/// (path/get-filename "content/post.md") ; => "post.md"
+/// ```
+pub fn path_get_filename(args: &[Expr]) -> Result<Expr, Error> {
+    let path = unpack_stringable_arg(args, 0, "path")?;
+
+    match Path::new(path).file_name() {
+        Some(filename) => Ok(Expr::string(filename.to_string_lossy())),
+        None => Ok(Expr::None),
+    }
+}
+
+/// Returns the filename without the (last) extension, or None.
+/// ```tan
+/// (path/get-stem "content/post.md") ; => "post"
+/// ```
+pub fn path_get_stem(args: &[Expr]) -> Result<Expr, Error> {
+    let path = unpack_stringable_arg(args, 0, "path")?;
+
+    match Path::new(path).file_stem() {
+        Some(stem) => Ok(Expr::string(stem.to_string_lossy())),
+        None => Ok(Expr::None),
+    }
+}
+
+/// Replaces the (last) extension of a path, an empty extension removes it.
+/// ```tan
+/// (path/with-extension "content/post.md" "html") ; => "content/post.html"
+/// ```
+pub fn path_with_extension(args: &[Expr]) -> Result<Expr, Error> {
+    let path = unpack_stringable_arg(args, 0, "path")?;
+    let extension = unpack_stringable_arg(args, 1, "extension")?;
+
+    // #insight Also accept `.html`.
+    let extension = extension.strip_prefix('.').unwrap_or(extension);
+
+    Ok(path_expr(Path::new(path).with_extension(extension)))
+}
+
+/// Resolves `.` and `..` components without touching the file-system.
+/// ```tan
+/// (path/normalize "content/blog/../about/./index.md") ; => "content/about/index.md"
+/// ```
+pub fn path_normalize(args: &[Expr]) -> Result<Expr, Error> {
+    let path = unpack_stringable_arg(args, 0, "path")?;
+
+    Ok(path_expr(normalize_path(Path::new(path))))
+}
+
+/// Returns the path relative to a base path, without touching the file-system.
+/// ```tan
+/// (path/relative-to "content/blog/post.md" "content/about") ; => "../blog/post.md"
+/// ```
+pub fn path_relative_to(args: &[Expr]) -> Result<Expr, Error> {
+    let path = unpack_stringable_arg(args, 0, "path")?;
+    let base = unpack_stringable_arg(args, 1, "base")?;
+
+    let Some(relative) = relative_path(Path::new(path), Path::new(base)) else {
+        return Err(Error::invalid_arguments(
+            &format!("cannot compute the path of `{path}` relative to `{base}`"),
+            args[1].range(),
+        ));
+    };
+
+    Ok(path_expr(relative))
+}
+
+/// Checks if a path is absolute, i.e. independent of the current directory.
+/// ```tan
+/// (path/is-absolute? "/var/log") ; => true
+/// ```
+pub fn path_is_absolute(args: &[Expr]) -> Result<Expr, Error> {
+    let path = unpack_stringable_arg(args, 0, "path")?;
+
+    Ok(Expr::Bool(Path::new(path).is_absolute()))
+}
+
+/// Returns the components of a path as an Array of Strings.
+/// ```tan
+/// (path/components "/var/log/app.log") ; => ["/" "var" "log" "app.log"]
+/// ```
+pub fn path_components(args: &[Expr]) -> Result<Expr, Error> {
+    let path = unpack_stringable_arg(args, 0, "path")?;
+
+    let components: Vec<Expr> = Path::new(path)
+        .components()
+        .map(|c| Expr::string(c.as_os_str().to_string_lossy()))
+        .collect();
+
+    Ok(Expr::array(components))
+}
+
+/// Returns the home directory of the current user, or None.
+pub fn path_home(_args: &[Expr]) -> Result<Expr, Error> {
+    // #insight `std::env::home_dir` is deprecated.
+    let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"));
+
+    match home {
+        Some(home) if !home.is_empty() => Ok(path_expr(home)),
+        _ => Ok(Expr::None),
+    }
+}
+
+// #todo Consider a `Glob` type to avoid recompiling the pattern.
+/// Checks if a path matches a glob pattern.
+/// ```tan
+/// (path/matches-glob? "content/blog/post.MD" "content/**/*.md" {:case-insensitive true})
+/// ```
+pub fn path_matches_glob(args: &[Expr]) -> Result<Expr, Error> {
+    let path = unpack_stringable_arg(args, 0, "path")?;
+    let pattern = unpack_stringable_arg(args, 1, "pattern")?;
+
+    let case_insensitive = if let Ok(options) = unpack_map_arg(args, 2, "options") {
+        options
+            .get("case-insensitive")
+            .unwrap_or_else(|| &Expr::Bool(false))
+            .as_bool()
+            .unwrap_or_default()
+    } else {
+        false
+    };
+
+    let matcher = match glob_matcher_from_pattern(pattern, case_insensitive) {
+        Ok(matcher) => matcher,
+        Err(mut error) => {
+            error.push_note("while compiling the glob pattern", args[1].range());
+            return Err(error);
+        }
+    };
+
+    Ok(Expr::Bool(matcher.is_match(path)))
+}
+
 pub fn import_lib_path(context: &mut Context) {
     // #todo Move under fs/?
     // #insight not everything is fs-related.
     let module = require_module("path", context);
 
+    // #todo Also add to prelude?
+    module.insert_invocable("Path", Expr::foreign_func(&path_new));
+
+    module.insert_invocable("join", Expr::foreign_func(&path_join));
+
     // #todo think of a better name.
     module.insert_invocable("get-dirname", Expr::foreign_func(&path_get_dirname));
 
     // #todo think of a better name.
     module.insert_invocable("get-extension", Expr::foreign_func(&path_get_extension));
+
+    module.insert_invocable("get-filename", Expr::foreign_func(&path_get_filename));
+    module.insert_invocable("get-stem", Expr::foreign_func(&path_get_stem));
+    module.insert_invocable("with-extension", Expr::foreign_func(&path_with_extension));
+
+    // #todo Consider `canonicalize` for consistency with fs.
+    module.insert_invocable("normalize", Expr::foreign_func(&path_normalize));
+    module.insert_invocable("relative-to", Expr::foreign_func(&path_relative_to));
+    module.insert_invocable("is-absolute?", Expr::foreign_func(&path_is_absolute));
+    module.insert_invocable("components", Expr::foreign_func(&path_components));
+
+    module.insert_invocable("home", Expr::foreign_func(&path_home));
+
+    // #todo Consider `glob-matching?`.
+    module.insert_invocable("matches-glob?", Expr::foreign_func(&path_matches_glob));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use tan::expr::Expr;
+
+    use crate::path::{normalize_path, path_join, relative_path};
+
+    #[test]
+    fn normalize_path_usage() {
+        assert_eq!(
+            normalize_path(Path::new("content/blog/../about/./index.md")),
+            PathBuf::from("content/about/index.md")
+        );
+        assert_eq!(
+            normalize_path(Path::new("../a/../../b")),
+            PathBuf::from("../../b")
+        );
+        assert_eq!(normalize_path(Path::new("/../a")), PathBuf::from("/a"));
+        assert_eq!(normalize_path(Path::new("a/..")), PathBuf::from("."));
+    }
+
+    #[test]
+    fn relative_path_usage() {
+        assert_eq!(
+            relative_path(
+                Path::new("content/blog/post.md"),
+                Path::new("content/about")
+            ),
+            Some(PathBuf::from("../blog/post.md"))
+        );
+        assert_eq!(
+            relative_path(Path::new("/var/log/app.log"), Path::new("/var")),
+            Some(PathBuf::from("log/app.log"))
+        );
+        assert_eq!(
+            relative_path(Path::new("./a/b"), Path::new("a/b/")),
+            Some(PathBuf::from("."))
+        );
+        assert_eq!(relative_path(Path::new("/a"), Path::new("b")), None);
+        assert_eq!(relative_path(Path::new("a"), Path::new("../b")), None);
+    }
+
+    #[test]
+    fn path_join_usage() {
+        let path = path_join(&[Expr::string("content"), Expr::string("post.md")]).unwrap();
+        assert_eq!(path.as_stringable(), Some("content/post.md"));
+
+        let path = path_join(&[Expr::string("content"), Expr::string("/var")]).unwrap();
+        assert_eq!(path.as_stringable(), Some("/var"));
+
+        assert!(path_join(&[]).is_err());
+        assert!(path_join(&[Expr::string("content"), Expr::Int(1)]).is_err());
+    }
 }
@@ -4,12 +4,22 @@ use std::io::Write;
 use std::process::Stdio;
 
 use tan::error::Error;
+use tan::expr::format_value;
+use tan::util::args::{unpack_arg, unpack_stringable_arg};
 use tan::util::module_util::require_module;
 use tan::{context::Context, expr::Expr};
+use tanutil::path::path_expr;
+
+use self::child::setup_lib_process_child;
+use self::command::setup_lib_process_command;
+use self::signal::setup_lib_process_signal;
+
+pub mod child;
+pub mod command;
+pub mod signal;
 
 // https://doc.rust-lang.org/std/env/index.html
 
-// #todo process/env, (let version (process/env :TAN-VERSION))
 // #todo process/args, (let file (process/args 1)) (let file (1 (process/args)))
 
 // #todo move env-vars and args to an env package, like Rust?
@@ -78,7 +88,6 @@ pub fn process_args(_args: &[Expr], context: &mut Context) -> Result<Expr, Error
 }
 
 // #todo consider renaming to just `env`?
-// #todo optionally support key/name argument to return the value of a specific env variable.
 /// Return the process environment variables as a Map/Map.
 pub fn process_env_vars(_args: &[Expr]) -> Result<Expr, Error> {
     let mut env_vars = HashMap::new();
@@ -90,51 +99,99 @@ pub fn process_env_vars(_args: &[Expr]) -> Result<Expr, Error> {
     Ok(Expr::map(env_vars))
 }
 
-// #todo spawn
-// #todo shell
+/// Returns the value of an environment variable, or the default value (None
+/// by default) if the variable is not set.
+/// ```tan
+/// (let tan-path (process/get-env "TANPATH" "~/.tan"))
+/// ```
+pub fn process_get_env(args: &[Expr]) -> Result<Expr, Error> {
+    let key = unpack_stringable_arg(args, 0, "key")?;
+
+    match std::env::var(key) {
+        Ok(value) => Ok(Expr::string(value)),
+        Err(_) => Ok(args.get(1).cloned().unwrap_or(Expr::None)),
+    }
+}
+
+// #insight Non-string values are formatted, e.g. `(process/set-env "PORT" 8000)`.
+/// Sets an environment variable of the current process, inherited by children.
+pub fn process_set_env(args: &[Expr]) -> Result<Expr, Error> {
+    let key = unpack_stringable_arg(args, 0, "key")?;
+    let value = unpack_arg(args, 1, "value")?;
+
+    if key.is_empty() || key.contains(['=', '\0']) {
+        return Err(Error::invalid_arguments(
+            &format!("invalid environment variable name `{key}`"),
+            args[0].range(),
+        ));
+    }
 
-// #todo
-// pub fn process_spawn(args: &[Expr]) -> Result<Expr, Error> {
-//     let [cmd] = args else {
-//         return Err(Error::invalid_arguments(
-//             "`exec` requires `cmd` argument",
-//             None,
-//         ));
-//     };
-
-//     let Expr::String(cmd_string) = cmd.unpack() else {
-//         return Err(Error::invalid_arguments(
-//             "`cmd` argument should be a String", // #todo mention `Stringable` or `Stringer`
-//             cmd.range(),
-//         ));
-//     };
-
-//     let mut args = cmd_string.split(" ");
-
-//     let Some(cmd) = args.next() else {
-//         return Err(Error::invalid_arguments(
-//             "`cmd` argument can't be empty",
-//             cmd.range(),
-//         ));
-//     };
-
-//     let Ok(output) = std::process::Command::new(cmd).args(args).output() else {
-//         // #todo should be runtime error.
-//         // #todo even more it should be a Tan error.
-//         return Err(Error::general("failed to execute cmd `{cmd}`"));
-//     };
-
-//     // #todo also return status and stderr.
-//     // #todo proper conversion of stdout output.
-//     // #todo could return map {status, stdout, stderr}
-
-//     Ok(Expr::string(
-//         String::from_utf8(output.stdout).unwrap_or_default(),
-//     ))
-// }
+    let value = match value.as_stringable() {
+        Some(value) => value.to_string(),
+        None => format_value(value),
+    };
+
+    if value.contains('\0') {
+        return Err(Error::invalid_arguments(
+            "`value` argument should not contain NUL characters",
+            args[1].range(),
+        ));
+    }
+
+    // #insight Not thread-safe on some platforms, avoid calling from handlers.
+    std::env::set_var(key, value);
+
+    Ok(Expr::None)
+}
+
+/// Removes an environment variable of the current process.
+pub fn process_remove_env(args: &[Expr]) -> Result<Expr, Error> {
+    let key = unpack_stringable_arg(args, 0, "key")?;
+
+    if key.is_empty() || key.contains(['=', '\0']) {
+        return Err(Error::invalid_arguments(
+            &format!("invalid environment variable name `{key}`"),
+            args[0].range(),
+        ));
+    }
+
+    std::env::remove_var(key);
+
+    Ok(Expr::None)
+}
+
+/// Returns the OS-assigned id of the current process.
+pub fn process_id(_args: &[Expr]) -> Result<Expr, Error> {
+    Ok(Expr::Int(std::process::id() as i64))
+}
+
+/// Returns the current working directory as a Path.
+pub fn process_cwd(_args: &[Expr]) -> Result<Expr, Error> {
+    let cwd = std::env::current_dir()?;
+    Ok(path_expr(cwd))
+}
+
+/// Changes the current working directory.
+pub fn process_set_cwd(args: &[Expr]) -> Result<Expr, Error> {
+    let path = unpack_stringable_arg(args, 0, "path")?;
+
+    if let Err(io_error) = std::env::set_current_dir(path) {
+        let mut error = Error::from(io_error);
+        error.push_note(
+            &format!("while changing directory to `{path}`"),
+            args[0].range(),
+        );
+        return Err(error);
+    }
+
+    Ok(Expr::None)
+}
+
+// #todo shell
 
 // #todo rename to shell? or exec shell?
 // #todo shortcut?
+// #insight Prefer `process/run` with a Command, no shell parsing is involved.
 /// Similar to C's system function:
 /// The command specified by string is passed to the host environment to be
 /// executed by the command processor.
@@ -231,9 +288,7 @@ pub fn process_shell(args: &[Expr]) -> Result<Expr, Error> {
 
 // https://stackoverflow.com/questions/21011330/how-do-i-invoke-a-system-command-and-capture-its-output
 
-// #todo use one spawn for both string and ProcessSpec?
-// #todo (process/spawn-cmd "ls -al") ; spawn-str, spawn-command, cmd, sh, shell, exec, run
-// #todo (process/spawn-child child-process) -> Process(-Handle) ; just `spawn`
+// #todo (process/spawn-cmd "ls -al") ; spawn-str, spawn-command, cmd, sh, shell, exec
 // #todo (Process env args id stdin, stdout stderr status current-dir)
 
 // #todo consider removing the `std` prefix from module paths, like haskell.
@@ -261,12 +316,88 @@ pub fn import_lib_process(context: &mut Context) {
     module.insert_invocable("env-vars", Expr::foreign_func(&process_env_vars));
     module.insert_invocable("env-vars$$", Expr::foreign_func(&process_env_vars)); // #todo is this needed?
 
+    // (let debug (process/get-env "DEBUG" "false"))
+    module.insert_invocable("get-env", Expr::foreign_func(&process_get_env));
+    module.insert_invocable("set-env", Expr::foreign_func(&process_set_env));
+    module.insert_invocable("remove-env", Expr::foreign_func(&process_remove_env));
+
+    module.insert_invocable("id", Expr::foreign_func(&process_id));
+    module.insert_invocable("cwd", Expr::foreign_func(&process_cwd));
+    module.insert_invocable("set-cwd", Expr::foreign_func(&process_set_cwd));
+
     // (let output (process/exec "ls -al"))
     module.insert_invocable("exec", Expr::foreign_func(&process_exec));
     module.insert_invocable("exec$$String", Expr::foreign_func(&process_exec));
 
     module.insert_invocable("shell", Expr::foreign_func(&process_shell));
     module.insert_invocable("shell$$String", Expr::foreign_func(&process_shell));
+
+    setup_lib_process_child(context);
+    setup_lib_process_command(context);
+    setup_lib_process_signal(context);
 }
 
 // #todo add some tests, even without assertions, just to exercise these functions.
+
+#[cfg(test)]
+mod tests {
+    use tan::expr::Expr;
+
+    use crate::process::{
+        process_cwd, process_get_env, process_remove_env, process_set_cwd, process_set_env,
+    };
+
+    #[test]
+    fn process_env_usage() {
+        // #insight A per-process key, tests run in parallel threads.
+        let key = format!("TAN_PROCESS_TEST_{}", std::process::id());
+        let key = Expr::string(&key);
+
+        let value = process_get_env(std::slice::from_ref(&key)).unwrap();
+        assert!(matches!(value, Expr::None));
+        let value = process_get_env(&[key.clone(), Expr::string("default")]).unwrap();
+        assert_eq!(value.as_string(), Some("default"));
+
+        process_set_env(&[key.clone(), Expr::string("tan")]).unwrap();
+        let value = process_get_env(&[key.clone(), Expr::string("default")]).unwrap();
+        assert_eq!(value.as_string(), Some("tan"));
+
+        process_set_env(&[key.clone(), Expr::Int(8000)]).unwrap();
+        let value = process_get_env(std::slice::from_ref(&key)).unwrap();
+        assert_eq!(value.as_string(), Some("8000"));
+
+        process_remove_env(std::slice::from_ref(&key)).unwrap();
+        let value = process_get_env(std::slice::from_ref(&key)).unwrap();
+        assert!(matches!(value, Expr::None));
+    }
+
+    #[test]
+    fn process_env_invalid_arguments() {
+        assert!(process_get_env(&[]).is_err());
+        assert!(process_set_env(&[Expr::string("TAN_KEY")]).is_err());
+        assert!(process_set_env(&[Expr::string(""), Expr::string("tan")]).is_err());
+        assert!(process_set_env(&[Expr::string("TAN=KEY"), Expr::string("tan")]).is_err());
+        assert!(process_set_env(&[Expr::string("TAN_KEY"), Expr::string("t\0n")]).is_err());
+        assert!(process_remove_env(&[Expr::string("")]).is_err());
+        assert!(process_remove_env(&[Expr::string("TAN=KEY")]).is_err());
+    }
+
+    #[test]
+    fn process_cwd_usage() {
+        let cwd = process_cwd(&[]).unwrap();
+        assert_eq!(
+            cwd.as_stringable(),
+            Some(std::env::current_dir().unwrap().to_string_lossy().as_ref())
+        );
+
+        // #insight Changing to the current directory keeps the other tests unaffected.
+        process_set_cwd(std::slice::from_ref(&cwd)).unwrap();
+        assert_eq!(
+            process_cwd(&[]).unwrap().as_stringable(),
+            cwd.as_stringable()
+        );
+
+        assert!(process_set_cwd(&[]).is_err());
+        assert!(process_set_cwd(&[Expr::string("/tan/missing/directory")]).is_err());
+    }
+}
@@ -0,0 +1,837 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader, Read, Write},
+    process::{Child, ChildStdin, Command, ExitStatus, Stdio},
+    sync::{
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+        Arc, Mutex, RwLock,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use tan::{
+    context::Context,
+    error::Error,
+    expr::{annotate_type, format_value, Expr},
+    util::{
+        args::{unpack_arg, unpack_array_arg, unpack_map_arg},
+        expect_lock_write,
+        module_util::require_module,
+    },
+};
+
+use tanutil::{args::bytes_from_expr, buffer::buffer_expr};
+
+use super::command::unpack_command;
+
+// #insight
+// The STDOUT and STDERR of the child are drained by background threads, so
+// that a chatty STDERR cannot block a reader of STDOUT (and vice versa).
+
+// #insight
+// A Process can be a pipeline of children, the STDIN goes to the first child,
+// the STDOUT comes from the last child. The STDERR of each child is captured
+// separately, `read-stderr-line` reads the children in order.
+
+// #todo Support async/non-blocking reads with Tan channels.
+// #todo Expose the exit signal on Unix.
+
+/// Where the STDOUT or STDERR of a child goes.
+#[derive(Default)]
+pub enum Redirect {
+    /// Captured as a String.
+    #[default]
+    Pipe,
+    /// Captured as a Buffer.
+    Buffer,
+    Inherit,
+    Null,
+    File(File),
+}
+
+impl Redirect {
+    /// Accepts a File, or one of `:pipe`, `:buffer`, `:inherit`, `:null`.
+    pub fn from_expr(expr: &Expr, name: &str) -> Result<Self, Error> {
+        if let Expr::ForeignMut(file) = expr.unpack() {
+            let file = expect_lock_write(file);
+            if let Some(file) = file.downcast_ref::<File>() {
+                return Ok(Redirect::File(file.try_clone()?));
+            }
+        }
+
+        match expr.as_stringable() {
+            Some("pipe") => Ok(Redirect::Pipe),
+            Some("buffer") => Ok(Redirect::Buffer),
+            Some("inherit") => Ok(Redirect::Inherit),
+            Some("null") => Ok(Redirect::Null),
+            _ => Err(Error::invalid_arguments(
+                &format!("`{name}` option should be a File, :pipe, :buffer, :inherit or :null"),
+                expr.range(),
+            )),
+        }
+    }
+
+    fn is_captured(&self) -> bool {
+        matches!(self, Redirect::Pipe | Redirect::Buffer)
+    }
+
+    fn stdio(&self) -> Result<Stdio, Error> {
+        Ok(match self {
+            Redirect::Pipe | Redirect::Buffer => Stdio::piped(),
+            Redirect::Inherit => Stdio::inherit(),
+            Redirect::Null => Stdio::null(),
+            Redirect::File(file) => Stdio::from(file.try_clone()?),
+        })
+    }
+}
+
+/// Process options, shared by `spawn`, `run`, `Command` and `pipe`.
+#[derive(Default)]
+pub struct ProcessOptions {
+    pub cwd: Option<String>,
+    pub env: HashMap<String, String>,
+    pub clear_env: bool,
+    pub timeout: Option<Duration>,
+    pub input: Option<Vec<u8>>,
+    pub stdout: Redirect,
+    pub stderr: Redirect,
+}
+
+// #insight Non-string values are formatted, e.g. `(process/spawn "sleep" [1])`.
+fn string_from_expr(expr: &Expr) -> String {
+    match expr.as_stringable() {
+        Some(s) => s.to_string(),
+        None => format_value(expr),
+    }
+}
+
+/// Unpacks the optional `args` Array.
+pub fn unpack_command_args(args: &[Expr], index: usize) -> Result<Vec<String>, Error> {
+    if args.len() <= index {
+        return Ok(Vec::new());
+    }
+
+    let command_args = unpack_array_arg(args, index, "args")?;
+
+    Ok(command_args.iter().map(string_from_expr).collect())
+}
+
+/// Unpacks the optional `options` Map.
+pub fn unpack_process_options(args: &[Expr], index: usize) -> Result<ProcessOptions, Error> {
+    if args.len() <= index {
+        return Ok(ProcessOptions::default());
+    }
+
+    let options = unpack_map_arg(args, index, "options")?;
+
+    process_options_from_map(&options)
+}
+
+pub fn process_options_from_map(options: &HashMap<String, Expr>) -> Result<ProcessOptions, Error> {
+    let mut process_options = ProcessOptions::default();
+
+    if let Some(cwd) = options.get("cwd") {
+        let Some(cwd) = cwd.as_stringable() else {
+            return Err(Error::invalid_arguments(
+                "`cwd` option should be a String or Path",
+                cwd.range(),
+            ));
+        };
+        process_options.cwd = Some(cwd.to_string());
+    }
+
+    if let Some(env) = options.get("env") {
+        let Some(env) = env.as_map() else {
+            return Err(Error::invalid_arguments(
+                "`env` option should be a Map",
+                env.range(),
+            ));
+        };
+        for (key, value) in env.iter() {
+            process_options
+                .env
+                .insert(key.clone(), string_from_expr(value));
+        }
+    }
+
+    if let Some(clear_env) = options.get("clear-env") {
+        process_options.clear_env = clear_env.as_bool().unwrap_or_default();
+    }
+
+    // #insight The timeout is in seconds.
+    if let Some(timeout) = options.get("timeout") {
+        let seconds = match timeout.unpack() {
+            Expr::Int(n) => Some(*n as f64),
+            Expr::Float(n) => Some(*n),
+            _ => None,
+        };
+        let Some(seconds) = seconds.filter(|s| *s >= 0.0) else {
+            return Err(Error::invalid_arguments(
+                "`timeout` option should be a non-negative number of seconds",
+                timeout.range(),
+            ));
+        };
+        process_options.timeout = Some(Duration::from_secs_f64(seconds));
+    }
+
+    if let Some(input) = options.get("input") {
+        process_options.input = Some(bytes_from_expr(input, "input")?);
+    }
+
+    if let Some(stdout) = options.get("stdout") {
+        process_options.stdout = Redirect::from_expr(stdout, "stdout")?;
+    }
+
+    if let Some(stderr) = options.get("stderr") {
+        process_options.stderr = Redirect::from_expr(stderr, "stderr")?;
+    }
+
+    Ok(process_options)
+}
+
+/// Builds a Command, no shell parsing is performed.
+pub fn build_command(program: &str, args: &[String], options: &ProcessOptions) -> Command {
+    let mut command = Command::new(program);
+
+    command.args(args);
+
+    if let Some(cwd) = &options.cwd {
+        command.current_dir(cwd);
+    }
+
+    if options.clear_env {
+        command.env_clear();
+    }
+
+    command.envs(&options.env);
+
+    command
+}
+
+/// Forwards the lines of a reader to a channel, including the line terminator.
+fn spawn_line_reader(reader: impl Read + Send + 'static, sender: Sender<Vec<u8>>) {
+    thread::spawn(move || {
+        let mut reader = BufReader::new(reader);
+        loop {
+            let mut line = Vec::new();
+            match reader.read_until(b'\n', &mut line) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {
+                    if sender.send(line).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// The captured output of a child, None when redirected.
+struct Output {
+    lines: Mutex<Receiver<Vec<u8>>>,
+    is_buffer: bool,
+}
+
+impl Output {
+    fn new(lines: Receiver<Vec<u8>>, redirect: &Redirect) -> Self {
+        Self {
+            lines: Mutex::new(lines),
+            is_buffer: matches!(redirect, Redirect::Buffer),
+        }
+    }
+}
+
+// #insight
+// After a timeout kill the output is drained for a grace period only, a
+// grandchild that inherited the pipe may keep it open indefinitely.
+const OUTPUT_GRACE_PERIOD: Duration = Duration::from_millis(100);
+
+/// Collects the remaining output, until the end of the stream or the deadline.
+fn drain_output(output: &Output, deadline: Option<Instant>) -> Vec<u8> {
+    let lines = output.lines.lock().expect("not poisoned");
+
+    let mut bytes: Vec<u8> = Vec::new();
+    loop {
+        let line = match deadline {
+            None => lines.recv().ok(),
+            Some(deadline) => lines
+                .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                .ok(),
+        };
+        match line {
+            Some(line) => bytes.extend(line),
+            None => break,
+        }
+    }
+
+    bytes
+}
+
+fn bytes_expr(bytes: Vec<u8>, is_buffer: bool) -> Expr {
+    if is_buffer {
+        buffer_expr(bytes)
+    } else {
+        Expr::String(String::from_utf8_lossy(&bytes).into_owned())
+    }
+}
+
+fn output_expr(output: &Option<Output>, deadline: Option<Instant>) -> Expr {
+    match output {
+        Some(output) => bytes_expr(drain_output(output, deadline), output.is_buffer),
+        None => Expr::None,
+    }
+}
+
+/// The result of receiving a line from an Output.
+enum Received {
+    Line(Vec<u8>),
+    End,
+    TimedOut,
+}
+
+fn receive_line(output: &Output, deadline: Option<Instant>) -> Received {
+    let lines = output.lines.lock().expect("not poisoned");
+
+    let result = match deadline {
+        None => lines.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        Some(deadline) => lines.recv_timeout(deadline.saturating_duration_since(Instant::now())),
+    };
+
+    match result {
+        Ok(line) => Received::Line(line),
+        Err(RecvTimeoutError::Disconnected) => Received::End,
+        Err(RecvTimeoutError::Timeout) => Received::TimedOut,
+    }
+}
+
+fn exit_code_expr(status: &ExitStatus) -> Expr {
+    match status.code() {
+        Some(code) => Expr::Int(code as i64),
+        None => Expr::None,
+    }
+}
+
+/// A spawned child process, or pipeline of child processes.
+pub struct ChildProcess {
+    children: Vec<Child>,
+    stdin: Option<ChildStdin>,
+    stdout: Option<Output>,
+    /// The STDERR of each child, None when redirected.
+    stderr: Vec<Option<Output>>,
+    deadline: Option<Instant>,
+    timed_out: bool,
+}
+
+impl ChildProcess {
+    pub fn spawn(command: Command, options: ProcessOptions) -> Result<Self, Error> {
+        Self::spawn_pipeline(vec![(command, options)])
+    }
+
+    // #insight
+    // The `input` option of the first command and the `stdout` option of the
+    // last command apply, the shortest `timeout` applies to the whole pipeline.
+    // The `stderr` option applies to each command.
+    /// Spawns the commands, connecting the STDOUT of each to the STDIN of the next.
+    pub fn spawn_pipeline(stages: Vec<(Command, ProcessOptions)>) -> Result<Self, Error> {
+        let count = stages.len();
+
+        let mut process = Self {
+            children: Vec::with_capacity(count),
+            stdin: None,
+            stdout: None,
+            stderr: Vec::with_capacity(count),
+            deadline: None,
+            timed_out: false,
+        };
+
+        let mut previous_stdout = None;
+
+        for (i, (mut command, options)) in stages.into_iter().enumerate() {
+            let is_last = i == count - 1;
+
+            match previous_stdout.take() {
+                Some(stdout) => command.stdin(Stdio::from(stdout)),
+                None => command.stdin(Stdio::piped()),
+            };
+
+            if is_last {
+                command.stdout(options.stdout.stdio()?);
+            } else {
+                command.stdout(Stdio::piped());
+            }
+
+            command.stderr(options.stderr.stdio()?);
+
+            let mut child = match command.spawn() {
+                Ok(child) => child,
+                Err(io_error) => {
+                    process.kill()?;
+                    // #insight Reap the killed children, avoid zombies.
+                    for child in &mut process.children {
+                        child.wait()?;
+                    }
+                    let mut error = Error::from(io_error);
+                    let program = command.get_program().to_string_lossy();
+                    error.push_note(&format!("while spawning `{program}`"), None);
+                    return Err(error);
+                }
+            };
+
+            if let Some(stdin) = child.stdin.take() {
+                if let Some(input) = options.input {
+                    // #insight Write from a thread, the child may not consume its input.
+                    let mut stdin = stdin;
+                    thread::spawn(move || stdin.write_all(&input));
+                } else {
+                    process.stdin = Some(stdin);
+                }
+            }
+
+            if let Some(stdout) = child.stdout.take() {
+                if is_last {
+                    let (sender, lines) = mpsc::channel();
+                    spawn_line_reader(stdout, sender);
+                    process.stdout = Some(Output::new(lines, &options.stdout));
+                } else {
+                    previous_stdout = Some(stdout);
+                }
+            }
+
+            match child.stderr.take() {
+                Some(stderr) if options.stderr.is_captured() => {
+                    let (sender, lines) = mpsc::channel();
+                    spawn_line_reader(stderr, sender);
+                    process
+                        .stderr
+                        .push(Some(Output::new(lines, &options.stderr)));
+                }
+                _ => process.stderr.push(None),
+            }
+
+            if let Some(timeout) = options.timeout {
+                let deadline = Instant::now() + timeout;
+                process.deadline = Some(match process.deadline {
+                    Some(d) => d.min(deadline),
+                    None => deadline,
+                });
+            }
+
+            process.children.push(child);
+        }
+
+        Ok(process)
+    }
+
+    /// Kills the children that are still running.
+    fn kill(&mut self) -> Result<(), Error> {
+        for child in &mut self.children {
+            // #insight Killing an exited child is not an error.
+            if child.try_wait()?.is_none() {
+                child.kill()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn kill_on_timeout(&mut self) -> Result<(), Error> {
+        self.kill()?;
+        self.timed_out = true;
+        Ok(())
+    }
+
+    fn last_child(&mut self) -> &mut Child {
+        self.children.last_mut().expect("at least one child")
+    }
+
+    /// Returns the next line (without the terminator) of STDOUT or STDERR,
+    /// None at the end of the stream or on timeout.
+    fn read_line(&mut self, from_stderr: bool) -> Result<Option<String>, Error> {
+        let outputs: Vec<&Output> = if from_stderr {
+            self.stderr.iter().flatten().collect()
+        } else {
+            self.stdout.iter().collect()
+        };
+
+        if outputs.is_empty() {
+            let name = if from_stderr { "STDERR" } else { "STDOUT" };
+            return Err(Error::invalid_arguments(
+                &format!("the {name} of the process is redirected"),
+                None,
+            ));
+        }
+
+        let mut line = None;
+        let mut timed_out = false;
+
+        for output in outputs {
+            match receive_line(output, self.deadline) {
+                Received::Line(received) => {
+                    line = Some(received);
+                    break;
+                }
+                Received::End => (),
+                Received::TimedOut => {
+                    timed_out = true;
+                    break;
+                }
+            }
+        }
+
+        if timed_out {
+            self.kill_on_timeout()?;
+        }
+
+        Ok(line.map(|line| {
+            let line = String::from_utf8_lossy(&line);
+            line.strip_suffix('\n')
+                .map(|l| l.strip_suffix('\r').unwrap_or(l))
+                .unwrap_or(&line)
+                .to_string()
+        }))
+    }
+
+    /// Waits for the children to exit, killing them if the timeout expires.
+    fn wait(&mut self) -> Result<Vec<ExitStatus>, Error> {
+        // #insight Close STDIN, the child may be waiting for more input.
+        drop(self.stdin.take());
+
+        if let Some(deadline) = self.deadline {
+            loop {
+                let mut running = false;
+                for child in &mut self.children {
+                    running |= child.try_wait()?.is_none();
+                }
+                if !running {
+                    break;
+                }
+                if Instant::now() >= deadline {
+                    self.kill_on_timeout()?;
+                    break;
+                }
+                thread::sleep(Duration::from_millis(10));
+            }
+        }
+
+        let mut statuses = Vec::with_capacity(self.children.len());
+        for child in &mut self.children {
+            statuses.push(child.wait()?);
+        }
+
+        Ok(statuses)
+    }
+
+    /// Waits for the children and returns the result Map with the remaining output.
+    pub fn wait_result(&mut self) -> Result<Expr, Error> {
+        let statuses = self.wait()?;
+
+        let mut result = HashMap::new();
+        result.insert(
+            "exit-code".to_string(),
+            exit_code_expr(statuses.last().expect("at least one child")),
+        );
+        // #insight The exit codes of all the commands of a pipeline.
+        result.insert(
+            "exit-codes".to_string(),
+            Expr::array(statuses.iter().map(exit_code_expr).collect::<Vec<_>>()),
+        );
+
+        let drain_deadline = self.timed_out.then(|| Instant::now() + OUTPUT_GRACE_PERIOD);
+        result.insert(
+            "stdout".to_string(),
+            output_expr(&self.stdout, drain_deadline),
+        );
+
+        // #insight
+        // The combined STDERR of all the commands, a Buffer if the last
+        // captured STDERR uses `:buffer`. The per-command STDERR is in `:stderrs`.
+        let mut stderr: Option<(Vec<u8>, bool)> = None;
+        let mut stderrs = Vec::with_capacity(self.stderr.len());
+        for output in &self.stderr {
+            let Some(output) = output else {
+                stderrs.push(Expr::None);
+                continue;
+            };
+            let bytes = drain_output(output, drain_deadline);
+            let (combined, is_buffer) = stderr.get_or_insert_with(Default::default);
+            combined.extend(&bytes);
+            *is_buffer = output.is_buffer;
+            stderrs.push(bytes_expr(bytes, output.is_buffer));
+        }
+        result.insert(
+            "stderr".to_string(),
+            match stderr {
+                Some((bytes, is_buffer)) => bytes_expr(bytes, is_buffer),
+                None => Expr::None,
+            },
+        );
+        result.insert("stderrs".to_string(), Expr::array(stderrs));
+        result.insert("timed-out".to_string(), Expr::Bool(self.timed_out));
+
+        Ok(annotate_type(Expr::map(result), "Process-Result"))
+    }
+}
+
+// #insight
+// A process that is neither waited nor killed is killed when dropped, the
+// children are always reaped to avoid zombies.
+impl Drop for ChildProcess {
+    fn drop(&mut self) {
+        drop(self.stdin.take());
+        for child in &mut self.children {
+            if let Ok(None) = child.try_wait() {
+                let _ = child.kill();
+            }
+            let _ = child.wait();
+        }
+    }
+}
+
+fn with_child_process<T>(
+    args: &[Expr],
+    f: impl FnOnce(&mut ChildProcess) -> Result<T, Error>,
+) -> Result<T, Error> {
+    let this = unpack_arg(args, 0, "process")?;
+
+    let Expr::ForeignMut(process) = this.unpack() else {
+        return Err(Error::invalid_arguments(
+            "`process` argument should be a Process",
+            this.range(),
+        ));
+    };
+
+    let mut process = expect_lock_write(process);
+
+    let Some(process) = process.downcast_mut::<ChildProcess>() else {
+        return Err(Error::invalid_arguments(
+            "`process` argument should be a Process",
+            this.range(),
+        ));
+    };
+
+    f(process)
+}
+
+pub fn process_expr(process: ChildProcess) -> Expr {
+    let expr = Expr::ForeignMut(Arc::new(RwLock::new(process)));
+    annotate_type(expr, "Process")
+}
+
+/// Spawns a child process, the arguments are passed as-is, without shell parsing.
+/// Also accepts a Command.
+/// ```tan
+/// (let p (process/spawn "grep" ["-n" "tan"] {:cwd "./src" :timeout 10}))
+/// (process/write-stdin p "hello tan\n")
+/// (process/close-stdin p)
+/// (while (let line (process/read-line p))
+///     (writeln line)
+/// )
+/// (let result (process/wait p)) ; => {:exit-code 0 :stdout "" :stderr "" :timed-out false}
+/// ```
+pub fn process_spawn(args: &[Expr]) -> Result<Expr, Error> {
+    let (command, options) = unpack_command(args)?;
+
+    let process = ChildProcess::spawn(command, options)?;
+
+    Ok(process_expr(process))
+}
+
+/// Spawns a child process and waits for it, returns the result Map.
+/// Also accepts a Command.
+/// ```tan
+/// (let result (process/run "git" ["status" "--short"] {:timeout 5}))
+/// (if (= (result :exit-code) 0) (writeln (result :stdout)))
+/// ```
+pub fn process_run(args: &[Expr]) -> Result<Expr, Error> {
+    let (command, options) = unpack_command(args)?;
+
+    let mut process = ChildProcess::spawn(command, options)?;
+
+    process.wait_result()
+}
+
+/// Writes a String or Buffer to the STDIN of the process.
+pub fn process_write_stdin(args: &[Expr]) -> Result<Expr, Error> {
+    let data = unpack_arg(args, 1, "data")?;
+
+    let bytes = bytes_from_expr(data, "data")?;
+
+    with_child_process(args, |process| {
+        let Some(stdin) = process.stdin.as_mut() else {
+            return Err(Error::invalid_arguments(
+                "the STDIN of the process is closed",
+                args[0].range(),
+            ));
+        };
+        stdin.write_all(&bytes)?;
+        stdin.flush()?;
+        Ok(Expr::None)
+    })
+}
+
+/// Closes the STDIN of the process, signaling the end of the input.
+pub fn process_close_stdin(args: &[Expr]) -> Result<Expr, Error> {
+    with_child_process(args, |process| {
+        drop(process.stdin.take());
+        Ok(Expr::None)
+    })
+}
+
+/// Returns the next line of the STDOUT of the process, None at the end.
+pub fn process_read_line(args: &[Expr]) -> Result<Expr, Error> {
+    with_child_process(args, |process| match process.read_line(false)? {
+        Some(line) => Ok(Expr::String(line)),
+        None => Ok(Expr::None),
+    })
+}
+
+/// Returns the next line of the STDERR of the process, None at the end.
+pub fn process_read_stderr_line(args: &[Expr]) -> Result<Expr, Error> {
+    with_child_process(args, |process| match process.read_line(true)? {
+        Some(line) => Ok(Expr::String(line)),
+        None => Ok(Expr::None),
+    })
+}
+
+/// Waits for the process to exit, returns the result Map with the output that
+/// was not already read.
+pub fn process_wait(args: &[Expr]) -> Result<Expr, Error> {
+    with_child_process(args, |process| process.wait_result())
+}
+
+/// Kills the process.
+pub fn process_kill(args: &[Expr]) -> Result<Expr, Error> {
+    with_child_process(args, |process| {
+        process.kill()?;
+        Ok(Expr::None)
+    })
+}
+
+// #todo Consider `running?`.
+/// Returns the status of the process, without waiting.
+/// ```tan
+/// (process/status p) ; => {:running false :exit-code 0}
+/// ```
+pub fn process_status(args: &[Expr]) -> Result<Expr, Error> {
+    with_child_process(args, |process| {
+        let status = process.last_child().try_wait()?;
+
+        let mut map = HashMap::new();
+        map.insert("running".to_string(), Expr::Bool(status.is_none()));
+        map.insert(
+            "exit-code".to_string(),
+            match status {
+                Some(status) => exit_code_expr(&status),
+                None => Expr::None,
+            },
+        );
+
+        Ok(Expr::map(map))
+    })
+}
+
+pub fn setup_lib_process_child(context: &mut Context) {
+    let module = require_module("process", context);
+
+    module.insert_invocable("spawn", Expr::foreign_func(&process_spawn));
+    module.insert_invocable("run", Expr::foreign_func(&process_run));
+
+    module.insert_invocable("write-stdin", Expr::foreign_func(&process_write_stdin));
+    module.insert_invocable("close-stdin", Expr::foreign_func(&process_close_stdin));
+    // #todo Consider a generic `read-line` that also works with Files.
+    module.insert_invocable("read-line", Expr::foreign_func(&process_read_line));
+    module.insert_invocable(
+        "read-stderr-line",
+        Expr::foreign_func(&process_read_stderr_line),
+    );
+
+    module.insert_invocable("wait", Expr::foreign_func(&process_wait));
+    module.insert_invocable("kill", Expr::foreign_func(&process_kill));
+    module.insert_invocable("status", Expr::foreign_func(&process_status));
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use tan::expr::Expr;
+
+    use crate::process::child::{
+        process_read_line, process_run, process_spawn, process_wait, ChildProcess, ProcessOptions,
+    };
+
+    fn args(args: &[&str]) -> Expr {
+        Expr::array(args.iter().map(Expr::string).collect::<Vec<_>>())
+    }
+
+    fn options(entries: Vec<(&str, Expr)>) -> Expr {
+        Expr::map(
+            entries
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect::<std::collections::HashMap<_, _>>(),
+        )
+    }
+
+    #[test]
+    fn process_run_usage() {
+        let result = process_run(&[
+            Expr::string("sh"),
+            args(&["-c", "read x; echo out $x; echo err >&2; exit 3"]),
+            options(vec![("input", Expr::string("tan\n"))]),
+        ])
+        .unwrap();
+        let result = result.as_map().unwrap();
+
+        assert_eq!(result["exit-code"].as_int(), Some(3));
+        assert_eq!(result["stdout"].as_string(), Some("out tan\n"));
+        assert_eq!(result["stderr"].as_string(), Some("err\n"));
+        assert_eq!(result["timed-out"].as_bool(), Some(false));
+    }
+
+    #[test]
+    fn process_spawn_usage() {
+        let process = process_spawn(&[Expr::string("printf"), args(&["a\\nb\\n"])]).unwrap();
+
+        let line = process_read_line(std::slice::from_ref(&process)).unwrap();
+        assert_eq!(line.as_string(), Some("a"));
+
+        let result = process_wait(&[process]).unwrap();
+        let result = result.as_map().unwrap();
+        assert_eq!(result["exit-code"].as_int(), Some(0));
+        assert_eq!(result["stdout"].as_string(), Some("b\n"));
+    }
+
+    #[test]
+    fn process_run_timeout() {
+        let start = Instant::now();
+
+        // The background grandchild keeps the STDOUT pipe open after the kill.
+        let result = process_run(&[
+            Expr::string("sh"),
+            args(&["-c", "echo started; sleep 5 & sleep 5"]),
+            options(vec![("timeout", Expr::Float(0.2))]),
+        ])
+        .unwrap();
+        let result = result.as_map().unwrap();
+
+        assert!(start.elapsed() < Duration::from_secs(3));
+        assert_eq!(result["timed-out"].as_bool(), Some(true));
+        assert!(matches!(result["exit-code"], Expr::None));
+        assert_eq!(result["stdout"].as_string(), Some("started\n"));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn dropped_process_is_killed_and_reaped() {
+        let mut command = std::process::Command::new("sleep");
+        command.arg("10");
+        let process = ChildProcess::spawn(command, ProcessOptions::default()).unwrap();
+        let pid = process.children[0].id();
+
+        drop(process);
+
+        assert!(!std::path::Path::new(&format!("/proc/{pid}")).exists());
+    }
+}
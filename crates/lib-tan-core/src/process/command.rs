@@ -0,0 +1,251 @@
+use std::{collections::HashMap, process::Command};
+
+use tan::{
+    context::Context,
+    error::Error,
+    expr::{annotate_type, Expr},
+    util::{
+        args::{unpack_arg, unpack_stringable_arg},
+        module_util::require_module,
+    },
+};
+
+use super::child::{
+    build_command, process_expr, process_options_from_map, unpack_command_args,
+    unpack_process_options, ChildProcess, ProcessOptions,
+};
+
+// #insight
+// A Command is a plain Map, it can be inspected and updated like any other Map.
+// The program and the arguments are never parsed by a shell, this avoids
+// injection when building commands from user input.
+
+// #todo Consider a `process/shell-quote` for the cases where a shell is needed.
+
+/// Returns the Command and its options from a Command Map.
+fn command_from_map(command: &Expr) -> Result<(Command, ProcessOptions), Error> {
+    let Some(map) = command.as_map() else {
+        return Err(Error::invalid_arguments(
+            "`command` argument should be a Command",
+            command.range(),
+        ));
+    };
+
+    let Some(program) = map.get("program").and_then(|p| p.as_stringable()) else {
+        return Err(Error::invalid_arguments(
+            "`command` argument should have a `program`",
+            command.range(),
+        ));
+    };
+
+    let command_args = match map.get("args") {
+        Some(command_args) => unpack_command_args(std::slice::from_ref(command_args), 0)?,
+        None => Vec::new(),
+    };
+
+    let options = match map.get("options").and_then(|o| o.as_map()) {
+        Some(options) => process_options_from_map(&options)?,
+        None => ProcessOptions::default(),
+    };
+
+    let command = build_command(program, &command_args, &options);
+
+    Ok((command, options))
+}
+
+/// Unpacks either a Command, or the `program`, `args`, `options` arguments.
+pub fn unpack_command(args: &[Expr]) -> Result<(Command, ProcessOptions), Error> {
+    let first = unpack_arg(args, 0, "program")?;
+
+    if first.as_map().is_some() {
+        return command_from_map(first);
+    }
+
+    let program = unpack_stringable_arg(args, 0, "program")?;
+    let command_args = unpack_command_args(args, 1)?;
+    let options = unpack_process_options(args, 2)?;
+
+    let command = build_command(program, &command_args, &options);
+
+    Ok((command, options))
+}
+
+/// Builds a Command, the arguments are passed to the program as-is.
+/// ```tan
+/// (let cmd (process/Command "git" ["commit" "-m" message] {:cwd repo-dir}))
+/// (process/run cmd)
+/// (let log (process/Command "git" ["log"] {:stdout (fs/create "log.txt")}))
+/// ```
+pub fn process_command_new(args: &[Expr]) -> Result<Expr, Error> {
+    let program = unpack_stringable_arg(args, 0, "program")?;
+
+    let mut map = HashMap::new();
+    map.insert("program".to_string(), Expr::string(program));
+
+    let command_args = match args.get(1) {
+        Some(command_args) if command_args.as_array().is_some() => command_args.clone(),
+        Some(command_args) => {
+            return Err(Error::invalid_arguments(
+                "`args` argument should be an Array",
+                command_args.range(),
+            ));
+        }
+        None => Expr::array(Vec::new()),
+    };
+    map.insert("args".to_string(), command_args);
+
+    let options = match args.get(2) {
+        Some(options) => {
+            // #insight Validate the options early.
+            unpack_process_options(args, 2)?;
+            options.clone()
+        }
+        None => Expr::map(HashMap::new()),
+    };
+    map.insert("options".to_string(), options);
+
+    Ok(annotate_type(Expr::map(map), "Command"))
+}
+
+fn unpack_pipeline(args: &[Expr]) -> Result<Vec<(Command, ProcessOptions)>, Error> {
+    if args.is_empty() {
+        return Err(Error::invalid_arguments(
+            "`pipe` requires at least one Command",
+            None,
+        ));
+    }
+
+    args.iter().map(command_from_map).collect()
+}
+
+/// Runs a pipeline of Commands, the STDOUT of each Command is connected to the
+/// STDIN of the next, returns the result Map.
+/// ```tan
+/// (let result (process/pipe
+///     (process/Command "cat" ["access.log"])
+///     (process/Command "grep" ["GET"])
+///     (process/Command "wc" ["-l"] {:timeout 5})
+/// ))
+/// (result :stdout) ; => "42\n"
+/// (result :exit-codes) ; => [0 0 0]
+/// (result :stderrs) ; => ["" "" ""]
+/// ```
+pub fn process_pipe(args: &[Expr]) -> Result<Expr, Error> {
+    let stages = unpack_pipeline(args)?;
+
+    let mut process = ChildProcess::spawn_pipeline(stages)?;
+
+    process.wait_result()
+}
+
+/// Spawns a pipeline of Commands, returns a Process.
+/// ```tan
+/// (let p (process/spawn-pipe (process/Command "sort") (process/Command "uniq" ["-c"])))
+/// (process/write-stdin p "b\na\nb\n")
+/// (process/wait p)
+/// ```
+pub fn process_spawn_pipe(args: &[Expr]) -> Result<Expr, Error> {
+    let stages = unpack_pipeline(args)?;
+
+    let process = ChildProcess::spawn_pipeline(stages)?;
+
+    Ok(process_expr(process))
+}
+
+pub fn setup_lib_process_command(context: &mut Context) {
+    let module = require_module("process", context);
+
+    module.insert_invocable("Command", Expr::foreign_func(&process_command_new));
+    module.insert_invocable("pipe", Expr::foreign_func(&process_pipe));
+    module.insert_invocable("spawn-pipe", Expr::foreign_func(&process_spawn_pipe));
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::collections::HashMap;
+
+    use tan::expr::Expr;
+
+    use crate::process::{
+        child::{process_wait, process_write_stdin},
+        command::{process_command_new, process_pipe, process_spawn_pipe},
+    };
+
+    fn command(program: &str, args: &[&str]) -> Expr {
+        let args = Expr::array(args.iter().map(Expr::string).collect::<Vec<_>>());
+        process_command_new(&[Expr::string(program), args]).unwrap()
+    }
+
+    #[test]
+    fn process_command_new_usage() {
+        let cmd = command("git", &["status"]);
+        let cmd = cmd.as_map().unwrap();
+        assert_eq!(cmd["program"].as_string(), Some("git"));
+        assert_eq!(cmd["args"].as_array().unwrap().len(), 1);
+
+        let options = Expr::map(HashMap::from([("timeout".to_string(), Expr::Int(-1))]));
+        let result = process_command_new(&[Expr::string("git"), Expr::array(Vec::new()), options]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn process_pipe_usage() {
+        let result = process_pipe(&[
+            command("sh", &["-c", "echo b; echo a; echo b; echo first >&2"]),
+            command("sort", &[]),
+            command("sh", &["-c", "uniq; echo last >&2; exit 2"]),
+        ])
+        .unwrap();
+        let result = result.as_map().unwrap();
+
+        assert_eq!(result["stdout"].as_string(), Some("a\nb\n"));
+        assert_eq!(result["exit-code"].as_int(), Some(2));
+        let exit_codes: Vec<_> = result["exit-codes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|code| code.as_int())
+            .collect();
+        assert_eq!(exit_codes, [Some(0), Some(0), Some(2)]);
+
+        // The STDERR is attributed to each command.
+        assert_eq!(result["stderr"].as_string(), Some("first\nlast\n"));
+        let stderrs: Vec<_> = result["stderrs"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|stderr| stderr.as_string().map(|s| s.to_string()))
+            .collect();
+        assert_eq!(
+            stderrs,
+            [
+                Some("first\n".into()),
+                Some("".into()),
+                Some("last\n".into())
+            ]
+        );
+    }
+
+    #[test]
+    fn process_spawn_pipe_usage() {
+        let process =
+            process_spawn_pipe(&[command("sort", &[]), command("uniq", &["-c"])]).unwrap();
+
+        process_write_stdin(&[process.clone(), Expr::string("b\na\nb\n")]).unwrap();
+
+        let result = process_wait(&[process]).unwrap();
+        let result = result.as_map().unwrap();
+        let stdout = result["stdout"].as_string().unwrap();
+        let counts: Vec<_> = stdout.lines().map(|line| line.trim()).collect();
+        assert_eq!(counts, ["1 a", "2 b"]);
+    }
+
+    #[test]
+    fn process_spawn_pipe_fails_on_missing_program() {
+        let result = process_spawn_pipe(&[
+            command("sleep", &["5"]),
+            command("tan-missing-program", &[]),
+        ]);
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,125 @@
+use tan::{context::Context, error::Error, expr::Expr, util::module_util::require_module};
+
+// #insight
+// The callback is invoked from a background thread with a clone of the
+// context, like the `http/serve` handlers. Once a signal has a callback, the
+// default action (e.g. termination) is not performed, the callback should
+// call `process/exit` when done.
+
+// #todo Support removing a callback.
+// #todo Support Windows console events (Ctrl-C).
+
+/// Returns the signal number for names like `int`, `SIGINT`, `:term`.
+#[cfg(unix)]
+fn signal_from_name(name: &str) -> Option<i32> {
+    use signal_hook::consts::signal::*;
+
+    let name = name.to_uppercase();
+    let name = name.strip_prefix("SIG").unwrap_or(&name);
+
+    match name {
+        "INT" => Some(SIGINT),
+        "TERM" => Some(SIGTERM),
+        "HUP" => Some(SIGHUP),
+        "QUIT" => Some(SIGQUIT),
+        "USR1" => Some(SIGUSR1),
+        "USR2" => Some(SIGUSR2),
+        "WINCH" => Some(SIGWINCH),
+        "CHLD" => Some(SIGCHLD),
+        "PIPE" => Some(SIGPIPE),
+        "ALRM" => Some(SIGALRM),
+        _ => None,
+    }
+}
+
+/// Invokes a callback when the process receives a signal, the callback is
+/// passed the signal name.
+/// ```tan
+/// (process/on-signal :term (Func [signal]
+///     (writeln "received ${signal}, shutting down")
+///     (process/exit 0)
+/// ))
+/// (http/serve {:port 8000} handler)
+/// ```
+#[cfg(unix)]
+pub fn process_on_signal(args: &[Expr], context: &mut Context) -> Result<Expr, Error> {
+    use std::thread;
+
+    use signal_hook::iterator::Signals;
+    use tan::{
+        eval::invoke_func,
+        util::args::{unpack_arg, unpack_stringable_arg},
+    };
+
+    let name = unpack_stringable_arg(args, 0, "signal")?;
+    let callback = unpack_arg(args, 1, "callback")?.clone();
+
+    let Some(signal) = signal_from_name(name) else {
+        return Err(Error::invalid_arguments(
+            &format!("unsupported signal `{name}`"),
+            args[0].range(),
+        ));
+    };
+
+    let mut signals = match Signals::new([signal]) {
+        Ok(signals) => signals,
+        Err(io_error) => {
+            let mut error = Error::from(io_error);
+            error.push_note(
+                &format!("while registering a handler for `{name}`"),
+                args[0].range(),
+            );
+            return Err(error);
+        }
+    };
+
+    let signal_name = format!("SIG{}", name.to_uppercase().trim_start_matches("SIG"));
+
+    // #todo #think should have separate context per thread?
+    let mut context = context.clone();
+
+    thread::spawn(move || {
+        for _ in signals.forever() {
+            let result = invoke_func(
+                &callback,
+                vec![Expr::string(signal_name.clone())],
+                &mut context,
+            );
+            if let Err(error) = result {
+                // #todo Use a proper error reporter.
+                eprintln!("{error}");
+            }
+        }
+    });
+
+    Ok(Expr::None)
+}
+
+#[cfg(not(unix))]
+pub fn process_on_signal(_args: &[Expr], _context: &mut Context) -> Result<Expr, Error> {
+    Err(Error::general("signals are not supported on this platform"))
+}
+
+pub fn setup_lib_process_signal(context: &mut Context) {
+    let module = require_module("process", context);
+
+    module.insert_invocable(
+        "on-signal",
+        Expr::foreign_func_mut_context(&process_on_signal),
+    );
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use crate::process::signal::signal_from_name;
+
+    #[test]
+    fn signal_from_name_usage() {
+        assert_eq!(signal_from_name("int"), Some(signal_hook::consts::SIGINT));
+        assert_eq!(
+            signal_from_name("SIGTERM"),
+            Some(signal_hook::consts::SIGTERM)
+        );
+        assert_eq!(signal_from_name("kill"), None);
+    }
+}
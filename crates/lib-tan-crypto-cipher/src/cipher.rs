@@ -0,0 +1,309 @@
+use std::collections::HashMap;
+
+use aes_gcm::{
+    aead::{
+        generic_array::typenum::Unsigned, rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng,
+        Payload,
+    },
+    Aes256Gcm, Nonce,
+};
+use chacha20poly1305::ChaCha20Poly1305;
+use subtle::ConstantTimeEq;
+use tan::{context::Context, error::Error, expr::Expr, util::module_util::require_module};
+use tanutil::{
+    args::{bytes_from_expr, options_from_args, unpack_bytes_arg},
+    buffer::{buffer_expr, decoded_bytes_expr},
+};
+
+// #insight
+// The output of `encrypt` is the random nonce followed by the ciphertext and
+// the authentication tag, so it can be stored or sent as a single Buffer.
+
+// #insight
+// Random 96-bit nonces are safe for up to ~2^32 messages per key, rotate keys
+// for higher volumes.
+
+// #todo Support XChaCha20-Poly1305 with 192-bit nonces.
+// #todo Support key derivation (HKDF).
+
+/// Both algorithms use 256-bit keys.
+const KEY_SIZE: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CipherAlgorithm {
+    Aes256Gcm,
+    ChaCha20Poly1305,
+}
+
+impl CipherAlgorithm {
+    fn from_name(name: &str) -> Option<CipherAlgorithm> {
+        match name {
+            "aes-256-gcm" | "aes-gcm" => Some(CipherAlgorithm::Aes256Gcm),
+            "chacha20-poly1305" | "chacha" => Some(CipherAlgorithm::ChaCha20Poly1305),
+            _ => None,
+        }
+    }
+}
+
+fn seal<C: Aead + AeadCore + KeyInit>(
+    key: &[u8],
+    plaintext: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>, Error> {
+    let cipher = C::new_from_slice(key).map_err(|_| invalid_key_error(key))?;
+    let nonce = C::generate_nonce(&mut OsRng);
+
+    let ciphertext = cipher
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: plaintext,
+                aad,
+            },
+        )
+        .map_err(|_| Error::general("encryption failed"))?;
+
+    let mut output = nonce.to_vec();
+    output.extend(ciphertext);
+
+    Ok(output)
+}
+
+fn open<C: Aead + AeadCore + KeyInit>(
+    key: &[u8],
+    data: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>, Error> {
+    let cipher = C::new_from_slice(key).map_err(|_| invalid_key_error(key))?;
+
+    let nonce_size = C::NonceSize::USIZE;
+    if data.len() < nonce_size {
+        return Err(Error::invalid_arguments(
+            "the encrypted data is too short",
+            None,
+        ));
+    }
+
+    let (nonce, ciphertext) = data.split_at(nonce_size);
+    let nonce = Nonce::<C::NonceSize>::from_slice(nonce);
+
+    // #insight Do not leak the reason, it could help an attacker.
+    cipher
+        .decrypt(
+            nonce,
+            Payload {
+                msg: ciphertext,
+                aad,
+            },
+        )
+        .map_err(|_| Error::general("decryption failed, wrong key or tampered data"))
+}
+
+fn invalid_key_error(key: &[u8]) -> Error {
+    Error::invalid_arguments(
+        &format!("expected a {KEY_SIZE}-byte key, found {} bytes", key.len()),
+        None,
+    )
+}
+
+fn algorithm_from_options(options: &HashMap<String, Expr>) -> Result<CipherAlgorithm, Error> {
+    let Some(algorithm) = options.get("algorithm") else {
+        return Ok(CipherAlgorithm::ChaCha20Poly1305);
+    };
+
+    let name = algorithm.as_stringable().unwrap_or_default();
+
+    CipherAlgorithm::from_name(name).ok_or_else(|| {
+        Error::invalid_arguments(
+            &format!("unsupported algorithm `{name}`, expected :chacha20-poly1305 or :aes-256-gcm"),
+            algorithm.range(),
+        )
+    })
+}
+
+fn aad_from_options(options: &HashMap<String, Expr>) -> Result<Vec<u8>, Error> {
+    match options.get("associated-data") {
+        Some(aad) => bytes_from_expr(aad, "associated-data"),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Generates a random 256-bit key, from the operating system generator.
+/// ```tan
+/// (let key (cipher/generate-key))
+/// ```
+pub fn cipher_generate_key(_args: &[Expr]) -> Result<Expr, Error> {
+    let mut key = vec![0; KEY_SIZE];
+    OsRng.fill_bytes(&mut key);
+
+    Ok(buffer_expr(key))
+}
+
+/// Encrypts and authenticates a String or Buffer, returns a Buffer.
+/// ChaCha20-Poly1305 is used by default.
+/// ```tan
+/// (let sealed (cipher/encrypt key session-data))
+/// (let sealed (cipher/encrypt key session-data {:algorithm :aes-256-gcm :associated-data user-id}))
+/// ```
+pub fn cipher_encrypt(args: &[Expr]) -> Result<Expr, Error> {
+    let key = unpack_bytes_arg(args, 0, "key")?;
+    let plaintext = unpack_bytes_arg(args, 1, "data")?;
+    let options = options_from_args(args, 2);
+
+    let algorithm = algorithm_from_options(&options)?;
+    let aad = aad_from_options(&options)?;
+
+    let output = match algorithm {
+        CipherAlgorithm::Aes256Gcm => seal::<Aes256Gcm>(&key, &plaintext, &aad)?,
+        CipherAlgorithm::ChaCha20Poly1305 => seal::<ChaCha20Poly1305>(&key, &plaintext, &aad)?,
+    };
+
+    Ok(buffer_expr(output))
+}
+
+/// Decrypts and verifies a Buffer produced by `encrypt`, returns a Buffer, or
+/// a String with `{:encoding :utf8}`. Fails if the data was tampered with.
+/// ```tan
+/// (let session-data (cipher/decrypt key sealed {:encoding :utf8}))
+/// ```
+pub fn cipher_decrypt(args: &[Expr]) -> Result<Expr, Error> {
+    let key = unpack_bytes_arg(args, 0, "key")?;
+    let data = unpack_bytes_arg(args, 1, "data")?;
+    let options = options_from_args(args, 2);
+
+    let algorithm = algorithm_from_options(&options)?;
+    let aad = aad_from_options(&options)?;
+
+    let plaintext = match algorithm {
+        CipherAlgorithm::Aes256Gcm => open::<Aes256Gcm>(&key, &data, &aad)?,
+        CipherAlgorithm::ChaCha20Poly1305 => open::<ChaCha20Poly1305>(&key, &data, &aad)?,
+    };
+
+    decoded_bytes_expr(plaintext, &options, &args[1])
+}
+
+/// Compares two Strings or Buffers in constant time, to avoid timing attacks
+/// when comparing secrets, e.g. signatures or tokens. Only the length leaks.
+/// ```tan
+/// (if (cipher/constant-time-eq? signature expected-signature) (accept request))
+/// ```
+pub fn cipher_constant_time_eq(args: &[Expr]) -> Result<Expr, Error> {
+    let a = unpack_bytes_arg(args, 0, "a")?;
+    let b = unpack_bytes_arg(args, 1, "b")?;
+
+    Ok(Expr::Bool(a.ct_eq(&b).into()))
+}
+
+pub fn import_lib_crypto_cipher(context: &mut Context) {
+    let module = require_module("crypto/cipher", context);
+
+    module.insert_invocable("generate-key", Expr::foreign_func(&cipher_generate_key));
+    module.insert_invocable("encrypt", Expr::foreign_func(&cipher_encrypt));
+    module.insert_invocable("decrypt", Expr::foreign_func(&cipher_decrypt));
+    module.insert_invocable(
+        "constant-time-eq?",
+        Expr::foreign_func(&cipher_constant_time_eq),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use aes_gcm::Aes256Gcm;
+    use chacha20poly1305::ChaCha20Poly1305;
+    use tan::expr::Expr;
+    use tanutil::buffer::buffer_expr;
+
+    use crate::cipher::{
+        cipher_constant_time_eq, cipher_decrypt, cipher_encrypt, cipher_generate_key, open, seal,
+        KEY_SIZE,
+    };
+
+    fn options(entries: &[(&str, Expr)]) -> Expr {
+        Expr::map(
+            entries
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.clone()))
+                .collect::<HashMap<_, _>>(),
+        )
+    }
+
+    #[test]
+    fn seal_open_roundtrip() {
+        let key = [7u8; KEY_SIZE];
+
+        let sealed = seal::<ChaCha20Poly1305>(&key, b"tan", b"user-1").unwrap();
+        assert_eq!(
+            open::<ChaCha20Poly1305>(&key, &sealed, b"user-1").unwrap(),
+            b"tan"
+        );
+        assert!(open::<ChaCha20Poly1305>(&key, &sealed, b"user-2").is_err());
+
+        let mut sealed = seal::<Aes256Gcm>(&key, b"tan", b"").unwrap();
+        assert_eq!(open::<Aes256Gcm>(&key, &sealed, b"").unwrap(), b"tan");
+        sealed[12] ^= 1;
+        assert!(open::<Aes256Gcm>(&key, &sealed, b"").is_err());
+    }
+
+    #[test]
+    fn encrypt_decrypt_usage() {
+        let key = cipher_generate_key(&[]).unwrap();
+        let Expr::Buffer(length, _) = key.unpack() else {
+            panic!("expected a Buffer");
+        };
+        assert_eq!(*length, KEY_SIZE);
+
+        let utf8 = Expr::KeySymbol("utf8".to_string());
+
+        for algorithm in ["chacha20-poly1305", "aes-256-gcm"] {
+            let algorithm = Expr::KeySymbol(algorithm.to_string());
+            let sealed = cipher_encrypt(&[
+                key.clone(),
+                Expr::string("tan"),
+                options(&[("algorithm", algorithm.clone())]),
+            ])
+            .unwrap();
+
+            let data = cipher_decrypt(&[
+                key.clone(),
+                sealed.clone(),
+                options(&[("algorithm", algorithm.clone()), ("encoding", utf8.clone())]),
+            ])
+            .unwrap();
+            assert_eq!(data.as_string(), Some("tan"));
+
+            let data = cipher_decrypt(&[key.clone(), sealed, options(&[("algorithm", algorithm)])])
+                .unwrap();
+            assert!(matches!(data.unpack(), Expr::Buffer(3, _)));
+        }
+
+        // Invalid UTF-8 plaintext.
+        let sealed = cipher_encrypt(&[key.clone(), buffer_expr(vec![0xff])]).unwrap();
+        let result = cipher_decrypt(&[key, sealed, options(&[("encoding", utf8)])]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn encrypt_with_wrong_key_size() {
+        let result = cipher_encrypt(&[Expr::string("short"), Expr::string("tan")]);
+        assert!(result.is_err());
+
+        let result = cipher_decrypt(&[Expr::string("short"), Expr::string("tan")]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn constant_time_eq_usage() {
+        let eq = |a: &str, b: &str| {
+            cipher_constant_time_eq(&[Expr::string(a), Expr::string(b)])
+                .unwrap()
+                .as_bool()
+        };
+
+        assert_eq!(eq("signature", "signature"), Some(true));
+        assert_eq!(eq("signature", "signaturf"), Some(false));
+        assert_eq!(eq("signature", "sign"), Some(false));
+        assert!(cipher_constant_time_eq(&[Expr::string("a"), Expr::Int(1)]).is_err());
+    }
+}
@@ -0,0 +1,9 @@
+use cipher::import_lib_crypto_cipher;
+use tan::context::Context;
+
+pub mod cipher;
+
+#[no_mangle]
+pub fn install_foreign_dyn_lib(context: &mut Context) {
+    import_lib_crypto_cipher(context);
+}
@@ -0,0 +1,470 @@
+use std::{
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+};
+
+use base64::{engine::general_purpose, Engine};
+use hmac::{digest::KeyInit, Hmac, Mac};
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha512};
+use tan::{
+    context::Context,
+    error::Error,
+    expr::Expr,
+    util::{
+        args::{unpack_arg, unpack_map_arg, unpack_stringable_arg},
+        expect_lock_read, expect_lock_write,
+        module_util::require_module,
+    },
+};
+use tanutil::{args::unpack_bytes_arg, buffer::buffer_expr};
+
+// #insight
+// MD5 and SHA-1 are broken for security purposes, they are only provided for
+// legacy use, e.g. checksums of existing systems.
+
+// #todo Support SHA-3.
+// #todo Support incremental Hasher objects.
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Algorithm {
+    Md5,
+    Sha1,
+    Sha256,
+    Sha512,
+    Blake3,
+}
+
+impl Algorithm {
+    fn from_name(name: &str) -> Option<Algorithm> {
+        match name.to_lowercase().replace('-', "").as_str() {
+            "md5" => Some(Algorithm::Md5),
+            "sha1" => Some(Algorithm::Sha1),
+            "sha256" => Some(Algorithm::Sha256),
+            "sha512" => Some(Algorithm::Sha512),
+            "blake3" => Some(Algorithm::Blake3),
+            _ => None,
+        }
+    }
+}
+
+enum Hasher {
+    Md5(Md5),
+    Sha1(Sha1),
+    Sha256(Sha256),
+    Sha512(Sha512),
+    Blake3(Box<blake3::Hasher>),
+    HmacMd5(Hmac<Md5>),
+    HmacSha1(Hmac<Sha1>),
+    HmacSha256(Hmac<Sha256>),
+    HmacSha512(Hmac<Sha512>),
+}
+
+impl Hasher {
+    fn new(algorithm: Algorithm) -> Self {
+        match algorithm {
+            Algorithm::Md5 => Hasher::Md5(Md5::new()),
+            Algorithm::Sha1 => Hasher::Sha1(Sha1::new()),
+            Algorithm::Sha256 => Hasher::Sha256(Sha256::new()),
+            Algorithm::Sha512 => Hasher::Sha512(Sha512::new()),
+            Algorithm::Blake3 => Hasher::Blake3(Box::new(blake3::Hasher::new())),
+        }
+    }
+
+    // #insight BLAKE3 has a keyed mode that replaces HMAC, it requires a 32-byte key.
+    /// Creates an HMAC hasher with the given key.
+    fn new_keyed(algorithm: Algorithm, key: &[u8]) -> Result<Self, Error> {
+        Ok(match algorithm {
+            Algorithm::Md5 => Hasher::HmacMd5(hmac_new(key)),
+            Algorithm::Sha1 => Hasher::HmacSha1(hmac_new(key)),
+            Algorithm::Sha256 => Hasher::HmacSha256(hmac_new(key)),
+            Algorithm::Sha512 => Hasher::HmacSha512(hmac_new(key)),
+            Algorithm::Blake3 => {
+                let Ok(key) = <[u8; 32]>::try_from(key) else {
+                    return Err(Error::invalid_arguments(
+                        &format!(
+                            "the blake3 keyed mode requires a 32-byte key, found {} bytes",
+                            key.len()
+                        ),
+                        None,
+                    ));
+                };
+                Hasher::Blake3(Box::new(blake3::Hasher::new_keyed(&key)))
+            }
+        })
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        match self {
+            Hasher::Md5(hasher) => hasher.update(bytes),
+            Hasher::Sha1(hasher) => hasher.update(bytes),
+            Hasher::Sha256(hasher) => hasher.update(bytes),
+            Hasher::Sha512(hasher) => hasher.update(bytes),
+            Hasher::Blake3(hasher) => {
+                hasher.update(bytes);
+            }
+            Hasher::HmacMd5(mac) => Mac::update(mac, bytes),
+            Hasher::HmacSha1(mac) => Mac::update(mac, bytes),
+            Hasher::HmacSha256(mac) => Mac::update(mac, bytes),
+            Hasher::HmacSha512(mac) => Mac::update(mac, bytes),
+        }
+    }
+
+    fn finalize(self) -> Vec<u8> {
+        match self {
+            Hasher::Md5(hasher) => hasher.finalize().to_vec(),
+            Hasher::Sha1(hasher) => hasher.finalize().to_vec(),
+            Hasher::Sha256(hasher) => hasher.finalize().to_vec(),
+            Hasher::Sha512(hasher) => hasher.finalize().to_vec(),
+            Hasher::Blake3(hasher) => hasher.finalize().as_bytes().to_vec(),
+            Hasher::HmacMd5(mac) => mac.finalize().into_bytes().to_vec(),
+            Hasher::HmacSha1(mac) => mac.finalize().into_bytes().to_vec(),
+            Hasher::HmacSha256(mac) => mac.finalize().into_bytes().to_vec(),
+            Hasher::HmacSha512(mac) => mac.finalize().into_bytes().to_vec(),
+        }
+    }
+
+    /// Streams a reader into the hasher, without loading it into memory.
+    fn update_from_reader(&mut self, mut reader: impl Read) -> std::io::Result<()> {
+        let mut chunk = vec![0; CHUNK_SIZE];
+        loop {
+            let count = reader.read(&mut chunk)?;
+            if count == 0 {
+                return Ok(());
+            }
+            self.update(&chunk[..count]);
+        }
+    }
+}
+
+fn hmac_new<M: Mac + KeyInit>(key: &[u8]) -> M {
+    <M as KeyInit>::new_from_slice(key).expect("HMAC accepts keys of any size")
+}
+
+fn unpack_algorithm_arg(args: &[Expr], index: usize) -> Result<Algorithm, Error> {
+    let name = unpack_stringable_arg(args, index, "algorithm")?;
+
+    Algorithm::from_name(name).ok_or_else(|| {
+        Error::invalid_arguments(
+            &format!(
+                "unsupported algorithm `{name}`, expected :sha256, :sha512, :sha1, :md5 or :blake3"
+            ),
+            args[index].range(),
+        )
+    })
+}
+
+/// Feeds a String, Buffer or File into the hasher.
+fn update_from_expr(hasher: &mut Hasher, expr: &Expr, name: &str) -> Result<(), Error> {
+    match expr.unpack() {
+        Expr::Buffer(length, bytes) => {
+            let bytes = expect_lock_read(bytes);
+            hasher.update(&bytes[..*length]);
+        }
+        Expr::ForeignMut(file) => {
+            let mut file = expect_lock_write(file);
+            let Some(file) = file.downcast_mut::<File>() else {
+                return Err(Error::invalid_arguments(
+                    &format!("`{name}` argument should be a String, Buffer or File"),
+                    expr.range(),
+                ));
+            };
+            // #insight The whole file is hashed, even if it was partially read or written.
+            file.seek(SeekFrom::Start(0))?;
+            hasher.update_from_reader(file)?;
+        }
+        _ => {
+            let Some(s) = expr.as_stringable() else {
+                return Err(Error::invalid_arguments(
+                    &format!("`{name}` argument should be a String, Buffer or File"),
+                    expr.range(),
+                ));
+            };
+            hasher.update(s.as_bytes());
+        }
+    }
+
+    Ok(())
+}
+
+/// Encodes the digest as requested by the `:encoding` option, hex by default.
+fn digest_expr(digest: Vec<u8>, args: &[Expr], index: usize) -> Result<Expr, Error> {
+    let encoding = if let Ok(options) = unpack_map_arg(args, index, "options") {
+        options
+            .get("encoding")
+            .and_then(|e| e.as_stringable())
+            .unwrap_or("hex")
+            .to_string()
+    } else {
+        "hex".to_string()
+    };
+
+    match encoding.as_str() {
+        "hex" => Ok(Expr::string(hex::encode(digest))),
+        "base64" => Ok(Expr::string(general_purpose::STANDARD.encode(digest))),
+        "base64-url" => Ok(Expr::string(
+            general_purpose::URL_SAFE_NO_PAD.encode(digest),
+        )),
+        "buffer" => Ok(buffer_expr(digest)),
+        _ => Err(Error::invalid_arguments(
+            &format!(
+                "unsupported encoding `{encoding}`, expected :hex, :base64, :base64-url or :buffer"
+            ),
+            args[index].range(),
+        )),
+    }
+}
+
+fn digest_with(algorithm: Algorithm, args: &[Expr], index: usize) -> Result<Expr, Error> {
+    let data = unpack_arg(args, index, "data")?;
+
+    let mut hasher = Hasher::new(algorithm);
+    update_from_expr(&mut hasher, data, "data")?;
+
+    digest_expr(hasher.finalize(), args, index + 1)
+}
+
+/// Hashes a String, Buffer or File with the given algorithm.
+/// ```tan
+/// (hash/digest :sha256 "hello") ; => "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+/// (hash/digest :blake3 buffer {:encoding :base64})
+/// ```
+pub fn hash_digest(args: &[Expr]) -> Result<Expr, Error> {
+    let algorithm = unpack_algorithm_arg(args, 0)?;
+    digest_with(algorithm, args, 1)
+}
+
+/// Hashes the file at the given path, without loading it into memory.
+/// ```tan
+/// (let etag (hash/digest-file :sha256 "public/app.js"))
+/// ```
+pub fn hash_digest_file(args: &[Expr]) -> Result<Expr, Error> {
+    let algorithm = unpack_algorithm_arg(args, 0)?;
+    let path = unpack_stringable_arg(args, 1, "path")?;
+
+    let mut hasher = Hasher::new(algorithm);
+
+    let result = File::open(path).and_then(|file| hasher.update_from_reader(file));
+    if let Err(io_error) = result {
+        let mut error = Error::from(io_error);
+        error.push_note(&format!("while hashing `{path}`"), args[1].range());
+        return Err(error);
+    }
+
+    digest_expr(hasher.finalize(), args, 2)
+}
+
+/// Returns the SHA-256 digest of a String, Buffer or File.
+/// ```tan
+/// (let cache-key (hash/sha256 request-body))
+/// ```
+pub fn hash_sha256(args: &[Expr]) -> Result<Expr, Error> {
+    digest_with(Algorithm::Sha256, args, 0)
+}
+
+/// Returns the SHA-512 digest of a String, Buffer or File.
+pub fn hash_sha512(args: &[Expr]) -> Result<Expr, Error> {
+    digest_with(Algorithm::Sha512, args, 0)
+}
+
+/// Returns the SHA-1 digest of a String, Buffer or File, for legacy use.
+pub fn hash_sha1(args: &[Expr]) -> Result<Expr, Error> {
+    digest_with(Algorithm::Sha1, args, 0)
+}
+
+/// Returns the MD5 digest of a String, Buffer or File, for legacy use.
+pub fn hash_md5(args: &[Expr]) -> Result<Expr, Error> {
+    digest_with(Algorithm::Md5, args, 0)
+}
+
+/// Returns the BLAKE3 digest of a String, Buffer or File.
+pub fn hash_blake3(args: &[Expr]) -> Result<Expr, Error> {
+    digest_with(Algorithm::Blake3, args, 0)
+}
+
+/// Returns the HMAC of a String, Buffer or File with the given key.
+/// ```tan
+/// (let signature (hash/hmac :sha256 webhook-secret body))
+/// (let signature (hash/hmac :sha256 webhook-secret body {:encoding :base64}))
+/// ```
+pub fn hash_hmac(args: &[Expr]) -> Result<Expr, Error> {
+    let algorithm = unpack_algorithm_arg(args, 0)?;
+    let key = unpack_bytes_arg(args, 1, "key")?;
+    let data = unpack_arg(args, 2, "data")?;
+
+    let mut hasher = match Hasher::new_keyed(algorithm, &key) {
+        Ok(hasher) => hasher,
+        Err(mut error) => {
+            error.push_note("invalid `key` argument", args[1].range());
+            return Err(error);
+        }
+    };
+    update_from_expr(&mut hasher, data, "data")?;
+
+    digest_expr(hasher.finalize(), args, 3)
+}
+
+pub fn import_lib_crypto_hash(context: &mut Context) {
+    let module = require_module("crypto/hash", context);
+
+    module.insert_invocable("digest", Expr::foreign_func(&hash_digest));
+    module.insert_invocable("digest-file", Expr::foreign_func(&hash_digest_file));
+
+    module.insert_invocable("sha256", Expr::foreign_func(&hash_sha256));
+    module.insert_invocable("sha512", Expr::foreign_func(&hash_sha512));
+    module.insert_invocable("sha1", Expr::foreign_func(&hash_sha1));
+    module.insert_invocable("md5", Expr::foreign_func(&hash_md5));
+    module.insert_invocable("blake3", Expr::foreign_func(&hash_blake3));
+
+    module.insert_invocable("hmac", Expr::foreign_func(&hash_hmac));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        io::Write,
+        sync::{Arc, RwLock},
+    };
+
+    use tan::expr::Expr;
+
+    use crate::hash::{hash_hmac, hash_sha256, Algorithm, Hasher};
+
+    fn hex_digest(algorithm: Algorithm, data: &str) -> String {
+        let mut hasher = Hasher::new(algorithm);
+        hasher.update(data.as_bytes());
+        hex::encode(hasher.finalize())
+    }
+
+    fn encoding(name: &str) -> Expr {
+        Expr::map(HashMap::from([(
+            "encoding".to_string(),
+            Expr::KeySymbol(name.to_string()),
+        )]))
+    }
+
+    #[test]
+    fn digest_usage() {
+        assert_eq!(
+            hex_digest(Algorithm::Sha256, "hello"),
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        );
+        assert_eq!(
+            hex_digest(Algorithm::Sha512, "hello"),
+            "9b71d224bd62f3785d96d46ad3ea3d73319bfbc2890caadae2dff72519673ca7\
+             2323c3d99ba5c11d7c7acc6e14b8c5da0c4663475c2e5c3adef46f73bcdec043"
+        );
+        assert_eq!(
+            hex_digest(Algorithm::Blake3, ""),
+            "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262"
+        );
+        assert_eq!(
+            hex_digest(Algorithm::Md5, "hello"),
+            "5d41402abc4b2a76b9719d911017c592"
+        );
+        assert_eq!(
+            hex_digest(Algorithm::Sha1, "hello"),
+            "aaf4c61ddcc5e8a2dabede0f3b482cd9aea9434d"
+        );
+    }
+
+    #[test]
+    fn digest_encodings() {
+        let data = Expr::string("hello");
+
+        let digest = hash_sha256(&[data.clone(), encoding("base64")]).unwrap();
+        assert_eq!(
+            digest.as_string(),
+            Some("LPJNul+wow4m6DsqxbninhsWHlwfp0JecwQzYpOLmCQ=")
+        );
+
+        let digest = hash_sha256(&[data.clone(), encoding("base64-url")]).unwrap();
+        assert_eq!(
+            digest.as_string(),
+            Some("LPJNul-wow4m6DsqxbninhsWHlwfp0JecwQzYpOLmCQ")
+        );
+
+        let digest = hash_sha256(&[data.clone(), encoding("buffer")]).unwrap();
+        let Expr::Buffer(length, bytes) = digest.unpack() else {
+            panic!("expected a Buffer");
+        };
+        assert_eq!(*length, 32);
+        assert_eq!(bytes.read().unwrap()[0], 0x2c);
+
+        assert!(hash_sha256(&[data, encoding("base32")]).is_err());
+    }
+
+    #[test]
+    fn digest_file_usage() {
+        let path = std::env::temp_dir().join(format!("tan-hash-{}.txt", std::process::id()));
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .truncate(true)
+            .read(true)
+            .write(true)
+            .open(&path)
+            .unwrap();
+        // #insight The cursor is left at the end of the file.
+        file.write_all(b"hello").unwrap();
+        let file = Expr::ForeignMut(Arc::new(RwLock::new(file)));
+
+        // #insight Hashed twice, the file is rewound each time.
+        for _ in 0..2 {
+            let digest = hash_sha256(std::slice::from_ref(&file)).unwrap();
+            assert_eq!(
+                digest.as_string(),
+                Some("2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824")
+            );
+        }
+
+        let key = Expr::string("Jefe");
+        let signature =
+            hash_hmac(&[Expr::KeySymbol("sha256".to_string()), key.clone(), file]).unwrap();
+        let expected = hash_hmac(&[
+            Expr::KeySymbol("sha256".to_string()),
+            key,
+            Expr::string("hello"),
+        ])
+        .unwrap();
+        assert_eq!(signature.as_string(), expected.as_string());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn hmac_usage() {
+        // RFC 4231, test case 2.
+        for (algorithm, expected) in [
+            (
+                Algorithm::Sha256,
+                "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+            ),
+            (
+                Algorithm::Sha512,
+                "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea250554\
+                 9758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737",
+            ),
+        ] {
+            let mut hasher = Hasher::new_keyed(algorithm, b"Jefe").unwrap();
+            hasher.update(b"what do ya want for nothing?");
+            assert_eq!(hex::encode(hasher.finalize()), expected);
+        }
+    }
+
+    #[test]
+    fn hmac_blake3_key_length() {
+        let blake3 = Expr::KeySymbol("blake3".to_string());
+        let data = Expr::string("hello");
+
+        let result = hash_hmac(&[blake3.clone(), Expr::string("short"), data.clone()]);
+        assert!(result.is_err());
+
+        let key = Expr::string("k".repeat(32));
+        let signature = hash_hmac(&[blake3, key, data]).unwrap();
+        assert_eq!(signature.as_string().map(|s| s.len()), Some(64));
+    }
+}
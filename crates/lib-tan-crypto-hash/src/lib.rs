@@ -0,0 +1,9 @@
+use hash::import_lib_crypto_hash;
+use tan::context::Context;
+
+pub mod hash;
+
+#[no_mangle]
+pub fn install_foreign_dyn_lib(context: &mut Context) {
+    import_lib_crypto_hash(context);
+}
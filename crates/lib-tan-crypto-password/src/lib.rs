@@ -0,0 +1,9 @@
+use password::import_lib_crypto_password;
+use tan::context::Context;
+
+pub mod password;
+
+#[no_mangle]
+pub fn install_foreign_dyn_lib(context: &mut Context) {
+    import_lib_crypto_password(context);
+}
@@ -0,0 +1,244 @@
+use std::collections::HashMap;
+
+use argon2::{
+    password_hash::{
+        self, rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString,
+    },
+    Algorithm, Argon2, Params, Version,
+};
+use tan::{
+    context::Context,
+    error::Error,
+    expr::Expr,
+    util::{
+        args::{unpack_map_arg, unpack_stringable_arg},
+        module_util::require_module,
+    },
+};
+
+// #insight
+// The hashes are self-describing strings (PHC format for Argon2, modular crypt
+// format for bcrypt), they embed the algorithm, the parameters and the salt,
+// so `verify` needs no options.
+
+// #todo Add `needs-rehash?` to upgrade the parameters of stored hashes.
+// #todo Support scrypt.
+
+fn unpack_options_int(
+    options: &HashMap<String, Expr>,
+    key: &str,
+    default: u32,
+) -> Result<u32, Error> {
+    let Some(value) = options.get(key) else {
+        return Ok(default);
+    };
+
+    match value.as_int() {
+        Some(n) if n >= 0 && n <= u32::MAX as i64 => Ok(n as u32),
+        _ => Err(Error::invalid_arguments(
+            &format!("`{key}` option should be a non-negative Int"),
+            value.range(),
+        )),
+    }
+}
+
+fn hash_argon2(password: &str, params: Params) -> Result<String, Error> {
+    let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+    let salt = SaltString::generate(&mut OsRng);
+
+    match argon2.hash_password(password.as_bytes(), &salt) {
+        Ok(hash) => Ok(hash.to_string()),
+        Err(error) => Err(Error::general(&format!("cannot hash password: {error}"))),
+    }
+}
+
+/// Hashes a password with a random salt, Argon2id by default.
+/// ```tan
+/// (let hash (password/hash "correct horse battery staple"))
+/// (let hash (password/hash password {:algorithm :bcrypt :cost 12}))
+/// (let hash (password/hash password {:memory-cost 65536 :time-cost 3 :parallelism 1}))
+/// ```
+pub fn password_hash(args: &[Expr]) -> Result<Expr, Error> {
+    let password = unpack_stringable_arg(args, 0, "password")?;
+
+    let Ok(options) = unpack_map_arg(args, 1, "options") else {
+        return Ok(Expr::string(hash_argon2(password, Params::default())?));
+    };
+
+    let algorithm = options
+        .get("algorithm")
+        .and_then(|a| a.as_stringable())
+        .unwrap_or("argon2");
+
+    let hash = match algorithm {
+        "argon2" | "argon2id" => {
+            let params = Params::new(
+                unpack_options_int(&options, "memory-cost", Params::DEFAULT_M_COST)?,
+                unpack_options_int(&options, "time-cost", Params::DEFAULT_T_COST)?,
+                unpack_options_int(&options, "parallelism", Params::DEFAULT_P_COST)?,
+                None,
+            )
+            .map_err(|error| {
+                Error::invalid_arguments(&format!("invalid argon2 parameters: {error}"), None)
+            })?;
+            hash_argon2(password, params)?
+        }
+        "bcrypt" => {
+            let cost = unpack_options_int(&options, "cost", bcrypt::DEFAULT_COST)?;
+            // #insight bcrypt only uses the first 72 bytes of the password.
+            bcrypt::hash(password, cost).map_err(|error| {
+                Error::invalid_arguments(&format!("cannot hash password: {error}"), None)
+            })?
+        }
+        _ => {
+            return Err(Error::invalid_arguments(
+                &format!("unsupported algorithm `{algorithm}`, expected :argon2 or :bcrypt"),
+                args[1].range(),
+            ));
+        }
+    };
+
+    Ok(Expr::string(hash))
+}
+
+/// Verifies a password against a hash produced by `password/hash`, in
+/// constant time.
+/// ```tan
+/// (if (password/verify submitted-password (user :password-hash))
+///     (login user)
+/// )
+/// ```
+pub fn password_verify(args: &[Expr]) -> Result<Expr, Error> {
+    let password = unpack_stringable_arg(args, 0, "password")?;
+    let hash = unpack_stringable_arg(args, 1, "hash")?;
+
+    let is_valid = if hash.starts_with("$argon2") {
+        let parsed_hash = match PasswordHash::new(hash) {
+            Ok(parsed_hash) if parsed_hash.hash.is_some() => parsed_hash,
+            _ => {
+                return Err(Error::invalid_arguments(
+                    "invalid argon2 hash",
+                    args[1].range(),
+                ));
+            }
+        };
+        // #insight Only a mismatch is `false`, e.g. invalid parameters are errors.
+        match Argon2::default().verify_password(password.as_bytes(), &parsed_hash) {
+            Ok(()) => true,
+            Err(password_hash::Error::Password) => false,
+            Err(error) => {
+                return Err(Error::invalid_arguments(
+                    &format!("invalid argon2 hash: {error}"),
+                    args[1].range(),
+                ));
+            }
+        }
+    } else if hash.starts_with("$2") {
+        match bcrypt::verify(password, hash) {
+            Ok(is_valid) => is_valid,
+            Err(error) => {
+                return Err(Error::invalid_arguments(
+                    &format!("invalid bcrypt hash: {error}"),
+                    args[1].range(),
+                ));
+            }
+        }
+    } else {
+        return Err(Error::invalid_arguments(
+            "unsupported hash format, expected an argon2 or bcrypt hash",
+            args[1].range(),
+        ));
+    };
+
+    Ok(Expr::Bool(is_valid))
+}
+
+pub fn import_lib_crypto_password(context: &mut Context) {
+    let module = require_module("crypto/password", context);
+
+    module.insert_invocable("hash", Expr::foreign_func(&password_hash));
+    module.insert_invocable("verify", Expr::foreign_func(&password_verify));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use tan::expr::Expr;
+
+    use crate::password::{password_hash, password_verify};
+
+    // #insight Low costs keep the tests fast.
+    fn hash(password: &str, options: &[(&str, Expr)]) -> String {
+        let options = options
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.clone()))
+            .collect::<HashMap<_, _>>();
+        let hash = password_hash(&[Expr::string(password), Expr::map(options)]).unwrap();
+        hash.as_string().unwrap().to_string()
+    }
+
+    fn verify(password: &str, hash: &str) -> Option<bool> {
+        password_verify(&[Expr::string(password), Expr::string(hash)])
+            .unwrap()
+            .as_bool()
+    }
+
+    #[test]
+    fn argon2_round_trip() {
+        let argon2_hash = hash(
+            "correct horse",
+            &[
+                ("memory-cost", Expr::Int(1024)),
+                ("time-cost", Expr::Int(1)),
+                ("parallelism", Expr::Int(1)),
+            ],
+        );
+        assert!(argon2_hash.starts_with("$argon2id$v=19$m=1024,t=1,p=1$"));
+
+        assert_eq!(verify("correct horse", &argon2_hash), Some(true));
+        assert_eq!(verify("wrong horse", &argon2_hash), Some(false));
+
+        // The salt is random.
+        let other_hash = hash(
+            "correct horse",
+            &[
+                ("memory-cost", Expr::Int(1024)),
+                ("time-cost", Expr::Int(1)),
+            ],
+        );
+        assert_ne!(argon2_hash, other_hash);
+    }
+
+    #[test]
+    fn bcrypt_round_trip() {
+        let bcrypt_hash = hash(
+            "correct horse",
+            &[
+                ("algorithm", Expr::KeySymbol("bcrypt".to_string())),
+                ("cost", Expr::Int(4)),
+            ],
+        );
+        assert!(bcrypt_hash.starts_with("$2b$04$"));
+
+        assert_eq!(verify("correct horse", &bcrypt_hash), Some(true));
+        assert_eq!(verify("wrong horse", &bcrypt_hash), Some(false));
+    }
+
+    #[test]
+    fn password_errors() {
+        let verify_result = |hash: &str| password_verify(&[Expr::string("x"), Expr::string(hash)]);
+
+        assert!(verify_result("$argon2id$v=19$broken").is_err());
+        assert!(verify_result("$argon2id$v=19$m=1,t=1,p=1$c2FsdHNhbHQ$aGFzaA").is_err());
+        assert!(verify_result("$2b$04$broken").is_err());
+        assert!(verify_result("plain-text").is_err());
+
+        let options =
+            HashMap::from([("algorithm".to_string(), Expr::KeySymbol("md5".to_string()))]);
+        assert!(password_hash(&[Expr::string("x"), Expr::map(options)]).is_err());
+
+        let options = HashMap::from([("memory-cost".to_string(), Expr::Int(-1))]);
+        assert!(password_hash(&[Expr::string("x"), Expr::map(options)]).is_err());
+    }
+}
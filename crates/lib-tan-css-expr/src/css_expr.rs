@@ -3,129 +3,569 @@
 // #todo Conside `css-expr` name: https://docs.racket-lang.org/css-expr/
 // #todo Consider naming this a 'dialect' or 'dsl' or 'language' instead of text?
 
+use read::setup_lib_css_expr_read;
 use tan::{
     context::Context,
     error::Error,
     expr::{format_value, Expr},
-    util::{module_util::require_module, try_lock_read},
+    util::{
+        args::{unpack_arg, unpack_map_arg},
+        module_util::require_module,
+        try_lock_read,
+    },
 };
 
-// #todo #fixme key-symbol conversion skips the `:` chars.
-// #todo maybe move to expr.rs as an alternative `as_stringable`?
-fn try_string_from_expr(expr: &Expr) -> Option<String> {
-    let expr = expr.unpack();
+pub mod read;
 
-    match expr {
-        Expr::Symbol(s) => Some(s.clone()),
+// #insight
+// A rule is a List of the selector followed by declarations and nested rules:
+//
+// ```tan
+// '[
+//     (:root :--accent "#0a6")
+//     (a.button
+//         :color (var --accent)
+//         :padding ["0.5rem" "1rem"]
+//         (:hover :color white)
+//         ("& + &" :margin-left "1rem")
+//         (@media "(max-width: 600px)" :display block)
+//     )
+//     (@keyframes fade (from :opacity 0) (to :opacity 1))
+//     (@font-face :font-family "Inter" :src "url(/fonts/inter.woff2)")
+// ]
+// ```
+//
+// Nested selectors are combined with the parent selectors, `&` refers to the
+// parent, a leading `:` (e.g. `:hover`, `::before`) is appended to the parent,
+// otherwise the nested selector is a descendant. The nesting is flattened, so
+// the output works in all browsers.
+
+// #insight
+// Property names are KeySymbols (or Symbols, Strings), the `:` is not part of
+// the name. Values are Strings, Symbols or numbers, Arrays are joined with
+// spaces and Lists are CSS functions, e.g. `(var --accent)` renders as
+// `var(--accent)`. None values skip the declaration.
+
+// #todo Support `!important` without a String value.
+// #todo Quote the value of the `content` property.
+
+/// The declarations directly inside these at-rules apply to the enclosing
+/// rule, the nested rules are combined with the enclosing selectors.
+const CONDITIONAL_AT_RULES: [&str; 6] = [
+    "media",
+    "supports",
+    "container",
+    "layer",
+    "scope",
+    "starting-style",
+];
+
+/// At-rules that are statements (without a block), e.g. `@import "x.css";`.
+const STATEMENT_AT_RULES: [&str; 4] = ["import", "charset", "namespace", "layer"];
+
+/// The output format.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    /// One rule per line, the default.
+    Compact,
+    Minified,
+    /// Multi-line, with the given indentation width.
+    Pretty(usize),
+}
+
+type Declaration = (String, String);
+
+#[derive(Debug, PartialEq)]
+enum CssNode {
+    Rule {
+        selectors: Vec<String>,
+        declarations: Vec<Declaration>,
+    },
+    AtRule {
+        name: String,
+        prelude: String,
+        /// None for statement at-rules.
+        block: Option<AtRuleBlock>,
+    },
+    /// CSS text, included verbatim.
+    Raw(String),
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct AtRuleBlock {
+    declarations: Vec<Declaration>,
+    children: Vec<CssNode>,
+}
+
+fn selector_from_expr(expr: &Expr) -> Option<String> {
+    match expr.unpack() {
+        Expr::Symbol(s) | Expr::String(s) => Some(s.clone()),
+        // #insight `(:hover ...)` is a shorthand for `("&:hover" ...)`.
         Expr::KeySymbol(s) => Some(format!(":{s}")),
-        Expr::String(s) => Some(s.clone()),
         _ => None,
     }
 }
 
-// #todo is this evaluating something?
-// #todo consider always adding the trailing `;`?
+fn property_from_expr(expr: &Expr) -> Option<String> {
+    match expr.unpack() {
+        Expr::KeySymbol(s) | Expr::Symbol(s) | Expr::String(s) => Some(s.clone()),
+        _ => None,
+    }
+}
 
-fn render_css_expr(expr: &Expr) -> Result<Expr, Error> {
-    let expr = expr.unpack();
+fn render_value(expr: &Expr) -> Result<String, Error> {
+    match expr.unpack() {
+        Expr::String(s) | Expr::Symbol(s) | Expr::KeySymbol(s) => Ok(s.clone()),
+        Expr::Array(items) => {
+            let items = try_lock_read(items, None)?;
+            let mut values = Vec::new();
+            for item in items.iter() {
+                values.push(render_value(item)?);
+            }
+            Ok(values.join(" "))
+        }
+        Expr::List(terms) => {
+            let Some(func) = terms.first().and_then(|op| op.as_symbol()) else {
+                return Err(Error::invalid_arguments(
+                    &format!("{expr} is not a valid CSS value"),
+                    expr.range(),
+                ));
+            };
+            let mut values = Vec::new();
+            for term in &terms[1..] {
+                values.push(render_value(term)?);
+            }
+            Ok(format!("{func}({})", values.join(", ")))
+        }
+        _ => Ok(format_value(expr)),
+    }
+}
 
-    // #todo try to unquote!
+/// Splits a selector list at the top-level commas, e.g. `a, :is(b, c)`.
+fn split_selector_list(selector: &str) -> Vec<&str> {
+    let mut selectors = Vec::new();
 
-    match expr {
-        Expr::List(terms) => {
-            if let Some(op) = terms.first() {
-                let Some(sym) = try_string_from_expr(op) else {
-                    // #todo we could return the argument position here and enrich the error upstream.
-                    // #todo hmm, the error is too precise here, do we really need the annotations?
+    let mut depth = 0;
+    let mut quote = None;
+    let mut start = 0;
+
+    for (i, c) in selector.char_indices() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => (),
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '(' | '[') => depth += 1,
+            (None, ')' | ']') => depth -= 1,
+            (None, ',') if depth == 0 => {
+                selectors.push(selector[start..i].trim());
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+    selectors.push(selector[start..].trim());
+
+    selectors.retain(|s| !s.is_empty());
+    selectors
+}
+
+/// Combines a nested selector with the parent selectors.
+fn resolve_selectors(selector: &str, parents: &[String]) -> Vec<String> {
+    let selectors = split_selector_list(selector);
+
+    if parents.is_empty() {
+        return selectors.into_iter().map(String::from).collect();
+    }
+
+    let mut resolved = Vec::new();
+
+    for parent in parents {
+        for selector in &selectors {
+            if selector.contains('&') {
+                resolved.push(selector.replace('&', parent));
+            } else if selector.starts_with(':') {
+                resolved.push(format!("{parent}{selector}"));
+            } else {
+                resolved.push(format!("{parent} {selector}"));
+            }
+        }
+    }
+
+    resolved
+}
+
+/// Parses the body of a rule into declarations and nested rules. Arrays are
+/// spread, so reusable groups of declarations ('mixins') can be included.
+fn parse_block(
+    terms: &[Expr],
+    declarations: &mut Vec<Declaration>,
+    nested: &mut Vec<Expr>,
+) -> Result<(), Error> {
+    let mut i = 0;
+
+    while i < terms.len() {
+        let term = &terms[i];
+        i += 1;
+
+        match term.unpack() {
+            Expr::List(..) => nested.push(term.clone()),
+            Expr::Array(items) => {
+                let items = try_lock_read(items, None)?;
+                parse_block(&items, declarations, nested)?;
+            }
+            Expr::Map(map) => {
+                let map = try_lock_read(map, None)?;
+                // #insight Sorted for deterministic output, use pairs if the order matters.
+                let mut names: Vec<&String> = map.keys().collect();
+                names.sort();
+                for name in names {
+                    let value = &map[name];
+                    if !matches!(value.unpack(), Expr::None | Expr::Never) {
+                        declarations.push((name.clone(), render_value(value)?));
+                    }
+                }
+            }
+            // #insight Skipped to support conditionals, e.g. `(when dark? '(...))`.
+            Expr::None | Expr::Never | Expr::Bool(false) => (),
+            _ => {
+                let Some(name) = property_from_expr(term) else {
+                    return Err(Error::invalid_arguments(
+                        &format!("{term} is not a valid property name"),
+                        term.range(),
+                    ));
+                };
+                let Some(value) = terms.get(i) else {
                     return Err(Error::invalid_arguments(
-                        &format!("{op} is not a Symbol"),
-                        op.range(),
+                        &format!("missing value for property `{name}`"),
+                        term.range(),
                     ));
                 };
+                i += 1;
+                if !matches!(value.unpack(), Expr::None | Expr::Never) {
+                    declarations.push((name, render_value(value)?));
+                }
+            }
+        }
+    }
 
-                let mut i = 1;
+    Ok(())
+}
 
-                // #todo escape body/children
+fn render_prelude_term(name: &str, expr: &Expr) -> Result<String, Error> {
+    match expr.unpack() {
+        // #insight
+        // The url of @import and the encoding of @charset are quoted, unless
+        // the String is already quoted or a `url()`, e.g. read from CSS.
+        Expr::String(s)
+            if matches!(name, "import" | "charset" | "namespace")
+                && !s.contains("url(")
+                && !s.contains(['"', '\'']) =>
+        {
+            Ok(format!("\"{}\"", s.replace('"', "\\\"")))
+        }
+        _ => render_value(expr),
+    }
+}
 
-                let mut body: Vec<String> = Vec::new();
+fn lower_at_rule(
+    name: &str,
+    terms: &[Expr],
+    parents: &[String],
+    nodes: &mut Vec<CssNode>,
+) -> Result<(), Error> {
+    let mut prelude = Vec::new();
+    let mut i = 0;
+    while let Some(term) = terms.get(i) {
+        if matches!(
+            term.unpack(),
+            Expr::List(..) | Expr::Array(..) | Expr::Map(..) | Expr::KeySymbol(..)
+        ) {
+            break;
+        }
+        prelude.push(render_prelude_term(name, term)?);
+        i += 1;
+    }
+    let prelude = prelude.join(" ");
+    let body = &terms[i..];
 
-                while i < terms.len() {
-                    let prop = render_css_expr(&terms[i])?; // #todo no render needed
-                    let mut declaration = format_value(&prop);
-                    i += 1;
-                    if i < terms.len() {
-                        declaration.push_str(": ");
-                        let value = render_css_expr(&terms[i])?; // #todo no render needed
-                        declaration.push_str(&format_value(&value));
-                    }
-                    i += 1;
-                    body.push(declaration);
-                }
+    if body.is_empty() && STATEMENT_AT_RULES.contains(&name) {
+        nodes.push(CssNode::AtRule {
+            name: name.to_string(),
+            prelude,
+            block: None,
+        });
+        return Ok(());
+    }
 
-                if body.is_empty() {
-                    // #todo add exception for <script> tag.
-                    Ok(Expr::string(format!("{sym} {{}}")))
-                } else {
-                    Ok(Expr::string(format!("{sym} {{ {} }}", body.join("; "))))
-                }
-            } else {
+    let mut declarations = Vec::new();
+    let mut nested = Vec::new();
+    parse_block(body, &mut declarations, &mut nested)?;
+
+    // #insight e.g. @keyframes and @font-face are not combined with the enclosing rule.
+    let parents: &[String] = if CONDITIONAL_AT_RULES.contains(&name) {
+        parents
+    } else {
+        &[]
+    };
+
+    let mut block = AtRuleBlock::default();
+
+    if parents.is_empty() {
+        block.declarations = declarations;
+    } else if !declarations.is_empty() {
+        block.children.push(CssNode::Rule {
+            selectors: parents.to_vec(),
+            declarations,
+        });
+    }
+
+    for expr in &nested {
+        lower(expr, parents, &mut block.children)?;
+    }
+
+    nodes.push(CssNode::AtRule {
+        name: name.to_string(),
+        prelude,
+        block: Some(block),
+    });
+
+    Ok(())
+}
+
+/// Lowers a CSS-Expr into flat CSS nodes, nested rules are combined with the
+/// parent selectors.
+fn lower(expr: &Expr, parents: &[String], nodes: &mut Vec<CssNode>) -> Result<(), Error> {
+    match expr.unpack() {
+        Expr::List(terms) => {
+            let Some(op) = terms.first() else {
                 // #todo offer context, e.g. in which function we are.
-                Err(Error::invalid_arguments(
+                return Err(Error::invalid_arguments(
                     "empty expression, remove",
                     expr.range(),
-                )) // #todo
+                ));
+            };
+
+            let Some(selector) = selector_from_expr(op) else {
+                // #todo we could return the argument position here and enrich the error upstream.
+                return Err(Error::invalid_arguments(
+                    &format!("{op} is not a Symbol"),
+                    op.range(),
+                ));
+            };
+
+            if let Some(name) = selector.strip_prefix('@') {
+                return lower_at_rule(name, &terms[1..], parents, nodes);
+            }
+
+            let selectors = resolve_selectors(&selector, parents);
+
+            let mut declarations = Vec::new();
+            let mut nested = Vec::new();
+            parse_block(&terms[1..], &mut declarations, &mut nested)?;
+
+            // #insight Rules that only contain nested rules are omitted.
+            if !declarations.is_empty() || nested.is_empty() {
+                nodes.push(CssNode::Rule {
+                    selectors: selectors.clone(),
+                    declarations,
+                });
+            }
+
+            for expr in &nested {
+                lower(expr, &selectors, nodes)?;
             }
         }
-        // #todo write a unit test for this.
-        Expr::Array(rules) => {
-            let mut body: Vec<String> = Vec::new();
-            // #todo #hack ultra hackish way to emulate unquote-explode in CSS-Expr
-            let rules = try_lock_read(rules, None)?;
-            let is_explode = if let Some(flag) = rules[0].as_string() {
-                flag == "..."
-            } else {
-                false
-            };
-            if is_explode {
-                for expr in rules.iter().skip(1) {
-                    body.push(format_value(expr));
+        Expr::Array(exprs) => {
+            let exprs = try_lock_read(exprs, None)?;
+            for expr in exprs.iter() {
+                lower(expr, parents, nodes)?;
+            }
+        }
+        Expr::None | Expr::Never | Expr::Bool(false) => (),
+        _ => nodes.push(CssNode::Raw(format_value(expr))),
+    }
+
+    Ok(())
+}
+
+fn write_declarations(declarations: &[Declaration], format: Format, depth: usize) -> String {
+    match format {
+        Format::Compact => declarations
+            .iter()
+            .map(|(name, value)| format!("{name}: {value}"))
+            .collect::<Vec<_>>()
+            .join("; "),
+        Format::Minified => declarations
+            .iter()
+            .map(|(name, value)| format!("{name}:{value}"))
+            .collect::<Vec<_>>()
+            .join(";"),
+        Format::Pretty(indent) => declarations
+            .iter()
+            .map(|(name, value)| format!("{:width$}{name}: {value};\n", "", width = depth * indent))
+            .collect(),
+    }
+}
+
+fn write_node(node: &CssNode, format: Format, depth: usize) -> String {
+    let pad = match format {
+        Format::Pretty(indent) => " ".repeat(depth * indent),
+        _ => String::new(),
+    };
+
+    match node {
+        CssNode::Rule {
+            selectors,
+            declarations,
+        } => {
+            let declarations = write_declarations(declarations, format, depth + 1);
+            match format {
+                Format::Compact if declarations.is_empty() => {
+                    format!("{} {{}}", selectors.join(", "))
                 }
-                Ok(Expr::string(body.join(";")))
-            } else {
-                for expr in rules.iter() {
-                    let value = render_css_expr(expr)?;
-                    body.push(format_value(value));
+                Format::Compact => format!("{} {{ {declarations} }}", selectors.join(", ")),
+                Format::Minified => format!("{}{{{declarations}}}", selectors.join(",")),
+                Format::Pretty(_) => {
+                    format!("{pad}{} {{\n{declarations}{pad}}}", selectors.join(", "))
                 }
-                // #todo consider \n\n as separator.
-                Ok(Expr::string(body.join("\n")))
             }
         }
-        Expr::Map(..) => {
-            // #todo remove duplication with List above.
-            // #todo what is the coorect type for this?
-            // let items: &HashMap<String, Expr> = items.borrow();
-            // #todo #hack temp solution.
-            let items = expr.as_map().unwrap();
-            let mut body: Vec<String> = Vec::new();
-            for (key, value) in items.iter() {
-                body.push(format!("{key}: {}", format_value(value)));
+        CssNode::AtRule {
+            name,
+            prelude,
+            block,
+        } => {
+            let head = if prelude.is_empty() {
+                format!("@{name}")
+            } else {
+                format!("@{name} {prelude}")
+            };
+
+            let Some(block) = block else {
+                return format!("{pad}{head};");
+            };
+
+            let declarations = write_declarations(&block.declarations, format, depth + 1);
+            let children = write_nodes(&block.children, format, depth + 1);
+
+            match format {
+                Format::Compact => {
+                    let body: Vec<&str> = [declarations.as_str(), children.as_str()]
+                        .into_iter()
+                        .filter(|part| !part.is_empty())
+                        .collect();
+                    if body.is_empty() {
+                        format!("{head} {{}}")
+                    } else {
+                        format!("{head} {{ {} }}", body.join(" "))
+                    }
+                }
+                Format::Minified => {
+                    let separator = if !declarations.is_empty() && !children.is_empty() {
+                        ";"
+                    } else {
+                        ""
+                    };
+                    format!("{head}{{{declarations}{separator}{children}}}")
+                }
+                Format::Pretty(_) => {
+                    let children = if children.is_empty() {
+                        children
+                    } else {
+                        format!("{children}\n")
+                    };
+                    format!("{pad}{head} {{\n{declarations}{children}{pad}}}")
+                }
             }
-            Ok(Expr::string(body.join("; ")))
         }
-        _ => Ok(Expr::string(format_value(expr))),
+        CssNode::Raw(css) => format!("{pad}{css}"),
+    }
+}
+
+fn write_nodes(nodes: &[CssNode], format: Format, depth: usize) -> String {
+    let separator = match format {
+        Format::Compact if depth == 0 => "\n",
+        Format::Compact => " ",
+        Format::Minified => "",
+        Format::Pretty(_) if depth == 0 => "\n\n",
+        Format::Pretty(_) => "\n",
+    };
+
+    nodes
+        .iter()
+        .map(|node| write_node(node, format, depth))
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+fn render_css_expr(expr: &Expr, format: Format) -> Result<String, Error> {
+    let mut nodes = Vec::new();
+    lower(expr, &[], &mut nodes)?;
+    Ok(write_nodes(&nodes, format, 0))
+}
+
+fn format_from_args(args: &[Expr], index: usize) -> Format {
+    let Ok(options) = unpack_map_arg(args, index, "options") else {
+        return Format::Compact;
+    };
+
+    if matches!(
+        options.get("minify").map(|m| m.unpack()),
+        Some(Expr::Bool(true))
+    ) {
+        return Format::Minified;
+    }
+
+    if matches!(
+        options.get("pretty").map(|p| p.unpack()),
+        Some(Expr::Bool(true))
+    ) {
+        let indent = options
+            .get("indent")
+            .and_then(|i| i.as_int())
+            .unwrap_or(2)
+            .max(0) as usize;
+        return Format::Pretty(indent);
     }
+
+    Format::Compact
 }
 
 // #todo name `css_from_css_expr` ?
+/// Renders a CSS-Expr rule, or an Array of rules, to CSS. A Map renders the
+/// declarations only, e.g. for a `style` attribute. The options `{:minify true}`
+/// and `{:pretty true :indent 4}` select the output format.
+/// ```tan
+/// (css-expr/to-css '[(body :margin 0) (a :color red (:hover :color blue))])
+/// (css-expr/to-css stylesheet {:minify true})
+/// (css-expr/to-css {:color "red" :font-weight 600}) ; => "color: red; font-weight: 600"
+/// ```
 pub fn css_expr_to_css(args: &[Expr]) -> Result<Expr, Error> {
-    if let Some(expr) = args.first() {
-        render_css_expr(expr)
-    } else {
-        Err(Error::invalid_arguments(
-            "expected at least one argument",
-            None,
-        ))
+    let expr = unpack_arg(args, 0, "expr")?;
+    let format = format_from_args(args, 1);
+
+    if let Expr::Array(rules) = expr.unpack() {
+        // #todo #hack ultra hackish way to emulate unquote-explode in CSS-Expr
+        let rules = try_lock_read(rules, None)?;
+        if rules.first().and_then(|flag| flag.as_string()) == Some("...") {
+            let body: Vec<String> = rules.iter().skip(1).map(format_value).collect();
+            return Ok(Expr::string(body.join(";")));
+        }
+    }
+
+    if let Expr::Map(..) = expr.unpack() {
+        let mut declarations = Vec::new();
+        let mut nested = Vec::new();
+        parse_block(std::slice::from_ref(expr), &mut declarations, &mut nested)?;
+        // #insight The declarations are always on one line, e.g. for a `style` attribute.
+        let format = match format {
+            Format::Pretty(_) => Format::Compact,
+            format => format,
+        };
+        return Ok(Expr::string(write_declarations(&declarations, format, 0)));
     }
+
+    Ok(Expr::string(render_css_expr(expr, format)?))
 }
 
 // #todo consider naming the library just `css`?
@@ -137,4 +577,115 @@ pub fn import_lib_css_expr(context: &mut Context) {
 
     // (let css (css-expr/to-css expr))
     module.insert_invocable("to-css", Expr::foreign_func(&css_expr_to_css));
+
+    setup_lib_css_expr_read(context);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use tan::expr::Expr;
+
+    use crate::css_expr::{render_css_expr, resolve_selectors, split_selector_list, Format};
+
+    fn rule(terms: Vec<Expr>) -> Expr {
+        Expr::List(terms)
+    }
+
+    fn stylesheet() -> Expr {
+        Expr::array(vec![
+            rule(vec![
+                Expr::symbol("a.button"),
+                Expr::KeySymbol("color".to_string()),
+                rule(vec![Expr::symbol("var"), Expr::symbol("--accent")]),
+                Expr::KeySymbol("padding".to_string()),
+                Expr::array(vec![Expr::string("0.5rem"), Expr::string("1rem")]),
+                rule(vec![
+                    Expr::KeySymbol("hover".to_string()),
+                    Expr::KeySymbol("color".to_string()),
+                    Expr::symbol("white"),
+                ]),
+                rule(vec![
+                    Expr::symbol("@media"),
+                    Expr::string("(max-width: 600px)"),
+                    Expr::KeySymbol("display".to_string()),
+                    Expr::symbol("block"),
+                ]),
+            ]),
+            rule(vec![
+                Expr::symbol("@keyframes"),
+                Expr::symbol("fade"),
+                rule(vec![
+                    Expr::symbol("from"),
+                    Expr::KeySymbol("opacity".to_string()),
+                    Expr::Int(0),
+                ]),
+            ]),
+            rule(vec![
+                Expr::symbol("@font-face"),
+                Expr::map(HashMap::from([(
+                    "font-family".to_string(),
+                    Expr::string("Inter"),
+                )])),
+            ]),
+            rule(vec![Expr::symbol("@import"), Expr::string("reset.css")]),
+        ])
+    }
+
+    #[test]
+    fn split_selector_list_usage() {
+        assert_eq!(split_selector_list("a, b"), vec!["a", "b"]);
+        assert_eq!(
+            split_selector_list("a:is(b, c), [title=\"x,y\"]"),
+            vec!["a:is(b, c)", "[title=\"x,y\"]"]
+        );
+    }
+
+    #[test]
+    fn resolve_selectors_usage() {
+        let parents = vec!["ul".to_string(), "ol".to_string()];
+        assert_eq!(
+            resolve_selectors("li, :hover", &parents),
+            vec!["ul li", "ul:hover", "ol li", "ol:hover"]
+        );
+        assert_eq!(
+            resolve_selectors(".dark &", &parents[..1]),
+            vec![".dark ul"]
+        );
+        assert_eq!(resolve_selectors("a, b", &[]), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn render_css_expr_usage() {
+        let css = render_css_expr(&stylesheet(), Format::Compact).unwrap();
+        assert_eq!(
+            css,
+            "a.button { color: var(--accent); padding: 0.5rem 1rem }\n\
+             a.button:hover { color: white }\n\
+             @media (max-width: 600px) { a.button { display: block } }\n\
+             @keyframes fade { from { opacity: 0 } }\n\
+             @font-face { font-family: Inter }\n\
+             @import \"reset.css\";"
+        );
+
+        let css = render_css_expr(&stylesheet(), Format::Minified).unwrap();
+        assert_eq!(
+            css,
+            "a.button{color:var(--accent);padding:0.5rem 1rem}\
+             a.button:hover{color:white}\
+             @media (max-width: 600px){a.button{display:block}}\
+             @keyframes fade{from{opacity:0}}\
+             @font-face{font-family:Inter}\
+             @import \"reset.css\";"
+        );
+
+        let css = render_css_expr(&stylesheet(), Format::Pretty(2)).unwrap();
+        assert!(
+            css.starts_with("a.button {\n  color: var(--accent);\n  padding: 0.5rem 1rem;\n}\n\n")
+        );
+        assert!(css.contains(
+            "@media (max-width: 600px) {\n  a.button {\n    display: block;\n  }\n}\n\n"
+        ));
+    }
 }
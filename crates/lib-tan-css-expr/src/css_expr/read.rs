@@ -0,0 +1,347 @@
+use tan::{
+    context::Context,
+    error::Error,
+    expr::Expr,
+    util::{args::unpack_stringable_arg, module_util::require_module},
+};
+
+// #insight
+// The reader follows the structure of the CSS syntax spec: a stylesheet is a
+// list of rules and at-rules, a block contains declarations, nested rules and
+// at-rules. An item that ends with `{` is a rule, otherwise a declaration.
+// Selectors, preludes and values are kept as text (with whitespace collapsed),
+// so the CSS is reproduced faithfully by `to-css`.
+
+// #ref https://www.w3.org/TR/css-syntax-3/
+
+// #todo Optionally keep comments.
+// #todo Consider parsing values into Arrays and function Lists, e.g. `(var --x)`.
+
+/// Reads CSS text into CSS-Expr expressions.
+struct CssReader<'a> {
+    css: &'a str,
+    position: usize,
+}
+
+impl<'a> CssReader<'a> {
+    fn new(css: &'a str) -> Self {
+        Self { css, position: 0 }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.css[self.position..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn error(&self, message: &str) -> Error {
+        let line = self.css[..self.position].matches('\n').count() + 1;
+        Error::invalid_arguments(&format!("invalid CSS, {message} at line {line}"), None)
+    }
+
+    /// Skips a comment, returns false if there is no comment at the position.
+    fn skip_comment(&mut self) -> bool {
+        if !self.rest().starts_with("/*") {
+            return false;
+        }
+        // #insight An unterminated comment extends to the end of the input.
+        match self.rest()[2..].find("*/") {
+            Some(end) => self.position += end + 4,
+            None => self.position = self.css.len(),
+        }
+        true
+    }
+
+    fn skip_whitespace_and_comments(&mut self) {
+        loop {
+            let trimmed = self.rest().trim_start();
+            self.position = self.css.len() - trimmed.len();
+            if !self.skip_comment() {
+                break;
+            }
+        }
+    }
+
+    /// Reads a quoted string, including the quotes.
+    fn read_string(&mut self, quote: char, text: &mut String) {
+        let start = self.position;
+        let mut chars = self.rest().char_indices().skip(1);
+        let mut end = self.css.len() - start;
+        while let Some((i, c)) = chars.next() {
+            if c == '\\' {
+                chars.next();
+            } else if c == quote || c == '\n' {
+                end = i + c.len_utf8();
+                break;
+            }
+        }
+        text.push_str(&self.css[start..start + end]);
+        self.position = start + end;
+    }
+
+    /// Reads text up to a top-level `{`, `;` or `}`, returns the text with
+    /// collapsed whitespace and the delimiter. The `{` and `;` delimiters are
+    /// consumed, `}` is not.
+    fn read_until_delimiter(&mut self) -> (String, Option<char>) {
+        let mut text = String::new();
+        let mut depth = 0;
+
+        loop {
+            if self.skip_comment() {
+                continue;
+            }
+
+            let Some(c) = self.peek() else {
+                return (text.trim().to_string(), None);
+            };
+
+            match c {
+                '"' | '\'' => {
+                    self.read_string(c, &mut text);
+                    continue;
+                }
+                '(' | '[' => depth += 1,
+                ')' | ']' => depth -= 1,
+                '{' | ';' if depth <= 0 => {
+                    self.position += 1;
+                    return (text.trim().to_string(), Some(c));
+                }
+                '}' if depth <= 0 => return (text.trim().to_string(), Some(c)),
+                _ => (),
+            }
+
+            self.position += c.len_utf8();
+
+            if c.is_whitespace() {
+                if !text.ends_with(' ') {
+                    text.push(' ');
+                }
+            } else {
+                text.push(c);
+            }
+        }
+    }
+
+    /// Reads the contents of a block, after the `{`, up to and including the
+    /// closing `}`.
+    fn read_block(&mut self) -> Result<Vec<Expr>, Error> {
+        let mut terms = Vec::new();
+
+        loop {
+            self.skip_whitespace_and_comments();
+
+            match self.peek() {
+                // #insight Like browsers, unclosed blocks are closed at the end of the input.
+                None => return Ok(terms),
+                Some('}') => {
+                    self.position += 1;
+                    return Ok(terms);
+                }
+                Some(';') => self.position += 1,
+                Some('@') => terms.push(self.read_at_rule()?),
+                Some(_) => {
+                    let (text, delimiter) = self.read_until_delimiter();
+                    if delimiter == Some('{') {
+                        let mut rule = vec![selector_expr(&text)];
+                        rule.extend(self.read_block()?);
+                        terms.push(Expr::List(rule));
+                    } else if let Some((name, value)) = split_declaration(&text) {
+                        terms.push(Expr::KeySymbol(name.to_string()));
+                        terms.push(Expr::string(value));
+                    } else if !text.is_empty() {
+                        return Err(self.error(&format!("invalid declaration `{text}`")));
+                    }
+                }
+            }
+        }
+    }
+
+    fn read_at_rule(&mut self) -> Result<Expr, Error> {
+        // Skip the `@`.
+        self.position += 1;
+
+        let name_len = self
+            .rest()
+            .find(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_'))
+            .unwrap_or(self.rest().len());
+        let name = &self.rest()[..name_len];
+        if name.is_empty() {
+            return Err(self.error("missing at-rule name"));
+        }
+        self.position += name_len;
+
+        let mut terms = vec![Expr::symbol(format!("@{name}"))];
+
+        let (prelude, delimiter) = self.read_until_delimiter();
+        if !prelude.is_empty() {
+            terms.push(Expr::string(prelude));
+        }
+
+        if delimiter == Some('{') {
+            terms.extend(self.read_block()?);
+        }
+
+        Ok(Expr::List(terms))
+    }
+
+    fn read_stylesheet(&mut self) -> Result<Vec<Expr>, Error> {
+        let mut exprs = Vec::new();
+
+        loop {
+            self.skip_whitespace_and_comments();
+
+            match self.peek() {
+                None => return Ok(exprs),
+                Some('@') => exprs.push(self.read_at_rule()?),
+                Some('}') => return Err(self.error("unexpected `}`")),
+                Some(_) => {
+                    let (text, delimiter) = self.read_until_delimiter();
+                    if delimiter != Some('{') {
+                        return Err(self.error(&format!("expected a block after `{text}`")));
+                    }
+                    let mut rule = vec![selector_expr(&text)];
+                    rule.extend(self.read_block()?);
+                    exprs.push(Expr::List(rule));
+                }
+            }
+        }
+    }
+}
+
+// #insight
+// Symbols with `.` or `#`, e.g. `.card`, are not plain identifiers in Tan, so
+// class and id selectors are read as Strings.
+/// Plain tag identifiers, e.g. `nav` or `from`, are read as Symbols, the rest
+/// as Strings.
+fn selector_expr(selector: &str) -> Expr {
+    let is_identifier = selector.starts_with(|c: char| c.is_alphabetic())
+        && selector
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_'));
+
+    if is_identifier {
+        Expr::symbol(selector)
+    } else {
+        Expr::string(selector)
+    }
+}
+
+/// Splits a declaration at the first `:`, e.g. `color: red`.
+fn split_declaration(text: &str) -> Option<(&str, &str)> {
+    let (name, value) = text.split_once(':')?;
+    let name = name.trim();
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return None;
+    }
+    Some((name, value.trim()))
+}
+
+fn read_css(css: &str) -> Result<Vec<Expr>, Error> {
+    CssReader::new(css).read_stylesheet()
+}
+
+/// Parses CSS into an Array of CSS-Expr rules, comments are dropped.
+/// ```tan
+/// (css-expr/read "a:hover { color: red }") ; => ['("a:hover" :color "red")]
+/// (css-expr/to-css (css-expr/read (fs/read-file-to-string "style.css")) {:minify true})
+/// ```
+pub fn css_expr_read(args: &[Expr]) -> Result<Expr, Error> {
+    let css = unpack_stringable_arg(args, 0, "css")?;
+
+    Ok(Expr::array(read_css(css)?))
+}
+
+pub fn setup_lib_css_expr_read(context: &mut Context) {
+    let module = require_module("dialect/css-expr", context);
+
+    module.insert_invocable("read", Expr::foreign_func(&css_expr_read));
+}
+
+#[cfg(test)]
+mod tests {
+    use tan::expr::Expr;
+
+    use crate::css_expr::{
+        read::{read_css, selector_expr, split_declaration},
+        render_css_expr, Format,
+    };
+
+    fn round_trip(css: &str, format: Format) -> String {
+        let exprs = read_css(css).unwrap();
+        render_css_expr(&Expr::array(exprs), format).unwrap()
+    }
+
+    #[test]
+    fn split_declaration_usage() {
+        assert_eq!(split_declaration("color: red"), Some(("color", "red")));
+        assert_eq!(
+            split_declaration("background:url(data:image/png;base64,AA)"),
+            Some(("background", "url(data:image/png;base64,AA)"))
+        );
+        assert_eq!(split_declaration("a b: c"), None);
+        assert_eq!(split_declaration("color"), None);
+    }
+
+    #[test]
+    fn selector_expr_usage() {
+        assert!(matches!(selector_expr("nav"), Expr::Symbol(s) if s == "nav"));
+        assert!(matches!(selector_expr("from"), Expr::Symbol(s) if s == "from"));
+        assert!(matches!(selector_expr("#main"), Expr::String(s) if s == "#main"));
+        assert!(matches!(selector_expr(".card"), Expr::String(s) if s == ".card"));
+        assert!(matches!(selector_expr("a.button"), Expr::String(s) if s == "a.button"));
+        assert!(matches!(selector_expr("50%"), Expr::String(s) if s == "50%"));
+    }
+
+    #[test]
+    fn read_css_usage() {
+        let exprs = read_css(
+            "/* Header */\n\
+             a.button:hover,\n  a.link {\n  color: red; /* accent */\n  margin: 0 auto !important;\n}",
+        )
+        .unwrap();
+        assert_eq!(exprs.len(), 1);
+
+        let Expr::List(terms) = &exprs[0] else {
+            panic!("expected a List");
+        };
+        assert!(matches!(&terms[0], Expr::String(s) if s == "a.button:hover, a.link"));
+        assert!(matches!(&terms[1], Expr::KeySymbol(s) if s == "color"));
+        assert!(matches!(&terms[2], Expr::String(s) if s == "red"));
+        assert!(matches!(&terms[4], Expr::String(s) if s == "0 auto !important"));
+
+        assert!(read_css("a { color: red }}").is_err());
+        assert!(read_css("a b c;").is_err());
+    }
+
+    #[test]
+    fn round_trip_usage() {
+        let css = "@charset \"utf-8\";\n\
+                   @import url(\"reset.css\") screen;\n\
+                   :root { --accent: #0a6; --font: \"Inter\", sans-serif }\n\
+                   a.button, a.link { color: var(--accent, red); background: url(data:image/png;base64,AA) }\n\
+                   a.button:hover { content: \"{ ; }\" }\n\
+                   @media (max-width: 600px) { a.button { display: block } nav ul > li + li { margin: 0 } }\n\
+                   @supports (display: grid) { .grid { display: grid } }\n\
+                   @keyframes fade { from { opacity: 0 } 50% { opacity: 0.5 } to { opacity: 1 } }\n\
+                   @font-face { font-family: Inter; src: url(/fonts/inter.woff2) format(\"woff2\") }\n\
+                   @layer base, components;";
+        assert_eq!(round_trip(css, Format::Compact), css);
+
+        let minified = round_trip(css, Format::Minified);
+        assert_eq!(round_trip(&minified, Format::Compact), css);
+
+        let pretty = round_trip(css, Format::Pretty(4));
+        assert_eq!(round_trip(&pretty, Format::Compact), css);
+
+        // Comments are dropped, native nesting is flattened.
+        assert_eq!(
+            round_trip(
+                "/* a */ nav { /* b */ color: red; &:hover { color: blue } }",
+                Format::Compact
+            ),
+            "nav { color: red }\nnav:hover { color: blue }"
+        );
+    }
+}
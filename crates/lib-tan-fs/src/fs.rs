@@ -13,6 +13,7 @@ use tan::{
         module_util::require_module,
     },
 };
+use tanutil::path::{glob_matcher_from_pattern, path_expr};
 
 // #todo consider system/fs, host/fs, os/fs.
 
@@ -28,6 +29,8 @@ use tan::{
 
 // #todo extract File functions to separate file?
 
+// #insight Functions accept both Strings and Paths and return Paths, see the `path` module.
+
 // File < Resource
 // #todo extract file-system-related functionality to `fs` or even the more general `rs` == resource space.
 // #todo consider mapping `:` to `__` and use #[allow(snake_case)]
@@ -267,21 +270,20 @@ fn walk_dir(dir_path: &Path, preorder: bool) -> Result<Vec<Expr>, std::io::Error
 
         if entry_path.is_dir() {
             if preorder {
-                tree.push(Expr::String(format!("{entry_path_str}/")));
+                tree.push(path_expr(format!("{entry_path_str}/")));
                 tree.append(&mut walk_dir(&entry_path, preorder)?);
             } else {
                 tree.append(&mut walk_dir(&entry_path, preorder)?);
-                tree.push(Expr::String(format!("{entry_path_str}/")));
+                tree.push(path_expr(format!("{entry_path_str}/")));
             }
         } else {
-            tree.push(Expr::String(entry_path_str));
+            tree.push(path_expr(entry_path_str));
         }
     }
 
     Ok(tree)
 }
 
-// #todo Should return Paths.
 // #todo Separate file-path/dir-path?
 // #todo Add Tan unit tests.
 // #todo By default include direcotories, add options to filter!
@@ -311,13 +313,11 @@ pub fn list(args: &[Expr]) -> Result<Expr, Error> {
             // #todo should this also include dirs?
             if !entry_path.is_dir() {
                 // #todo annotate with `File-Path`
-                list.push(Expr::String(entry_path.to_str().unwrap().to_string()));
+                list.push(path_expr(entry_path));
             } else {
                 // #todo annotate with `Dir-Path``
-                let dir_name = entry_path.to_str().unwrap().to_string();
                 // #todo Consider adding trailing `/`?
-                // list.push(Expr::String(format!("{dir_name}/")));
-                list.push(Expr::String(dir_name));
+                list.push(path_expr(entry_path));
             }
         }
     } else {
@@ -328,7 +328,7 @@ pub fn list(args: &[Expr]) -> Result<Expr, Error> {
             // #todo should this also include dirs?
             if !entry_path.is_dir() {
                 // #todo annotate with `File-Path`
-                list.push(Expr::String(entry_path.to_str().unwrap().to_string()));
+                list.push(path_expr(entry_path));
             }
 
             // #todo #fix this skips the directories, also add dirs!
@@ -394,6 +394,106 @@ pub fn list_as_tree(args: &[Expr], context: &mut Context) -> Result<Expr, Error>
     }
 }
 
+// #insight The walk starts from the literal prefix of the pattern, e.g.
+// `content/**/*.md` only walks `content/`. Without `**` the walk is limited
+// to the depth of the pattern, e.g. `content/*/*.md` walks 2 levels.
+/// Returns the directory part of a glob pattern that contains no wildcards,
+/// and the maximum walk depth below it, None for patterns with `**`.
+fn glob_base_dir(pattern: &str) -> (String, Option<usize>) {
+    let components: Vec<&str> = pattern.split('/').collect();
+    let mut base: Vec<&str> = Vec::new();
+
+    for component in &components[..components.len() - 1] {
+        if component.contains(['*', '?', '[', '{']) {
+            break;
+        }
+        base.push(component);
+    }
+
+    let max_depth = if pattern.contains("**") {
+        None
+    } else {
+        Some(components.len() - base.len())
+    };
+
+    let base = base.join("/");
+
+    if base.is_empty() && pattern.starts_with('/') {
+        (String::from("/"), max_depth)
+    } else {
+        (base, max_depth)
+    }
+}
+
+// #todo Implement as generator/iterator.
+/// Returns the paths that match a glob pattern, sorted.
+/// Supports `**`, character classes, and brace alternation.
+/// ```tan
+/// (let posts (fs/glob "content/**/*.{md,txt}"))
+/// (let images (fs/glob "public/*.png" {:case-insensitive true}))
+/// ```
+pub fn fs_glob(args: &[Expr]) -> Result<Expr, Error> {
+    let pattern = unpack_stringable_arg(args, 0, "pattern")?;
+
+    let case_insensitive = if let Ok(options) = unpack_map_arg(args, 1, "options") {
+        options
+            .get("case-insensitive")
+            .unwrap_or_else(|| &Expr::Bool(false))
+            .as_bool()
+            .unwrap_or_default()
+    } else {
+        false
+    };
+
+    let matcher = match glob_matcher_from_pattern(pattern, case_insensitive) {
+        Ok(matcher) => matcher,
+        Err(mut error) => {
+            error.push_note("while compiling the glob pattern", args[0].range());
+            return Err(error);
+        }
+    };
+
+    let (base_dir, max_depth) = glob_base_dir(pattern);
+    let walk_root = if base_dir.is_empty() { "." } else { &base_dir };
+
+    // #insight A missing base directory just yields no matches.
+    if !Path::new(walk_root).is_dir() {
+        return Ok(Expr::array(Vec::new()));
+    }
+
+    let mut paths: Vec<String> = Vec::new();
+
+    let mut walk_dir = walkdir::WalkDir::new(walk_root).min_depth(1);
+    if let Some(max_depth) = max_depth {
+        walk_dir = walk_dir.max_depth(max_depth);
+    }
+
+    for entry in walk_dir {
+        // #insight Unreadable entries are skipped, instead of failing the whole glob.
+        let Ok(entry) = entry else {
+            continue;
+        };
+        let entry_path = entry.path().to_string_lossy();
+
+        // #insight Strip the implicit `./` so that relative patterns match.
+        let entry_path = if base_dir.is_empty() {
+            entry_path.strip_prefix("./").unwrap_or(&*entry_path)
+        } else {
+            &*entry_path
+        };
+
+        if matcher.is_match(entry_path) {
+            paths.push(entry_path.to_string());
+        }
+    }
+
+    paths.sort();
+
+    Ok(Expr::array(
+        paths.into_iter().map(path_expr).collect::<Vec<_>>(),
+    ))
+}
+
 /// Checks if a path exists.
 pub fn fs_exists(args: &[Expr]) -> Result<Expr, Error> {
     let [path] = args else {
@@ -549,9 +649,7 @@ pub fn fs_canonicalize(args: &[Expr]) -> Result<Expr, Error> {
 
     let path = fs::canonicalize(path)?;
 
-    // #todo should return a `Path` value.
-
-    Ok(Expr::string(path.to_string_lossy()))
+    Ok(path_expr(path))
 }
 
 // #todo Add some kind of unit/integration test.
@@ -639,6 +737,10 @@ pub fn import_lib_fs(context: &mut Context) {
         Expr::foreign_func_mut_context(&list_as_tree),
     );
 
+    // (let posts (fs/glob "content/**/*.md"))
+    module.insert_invocable("glob", Expr::foreign_func(&fs_glob));
+    module.insert_invocable("glob$$String", Expr::foreign_func(&fs_glob));
+
     module.insert_invocable("exists?", Expr::foreign_func(&fs_exists));
     module.insert_invocable("exists?$$String", Expr::foreign_func(&fs_exists));
 
@@ -661,3 +763,66 @@ pub fn import_lib_fs(context: &mut Context) {
 }
 
 // #todo add unit tests.
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tan::expr::Expr;
+
+    use crate::fs::{fs_glob, glob_base_dir};
+
+    #[test]
+    fn glob_base_dir_usage() {
+        assert_eq!(
+            glob_base_dir("content/**/*.md"),
+            ("content".to_string(), None)
+        );
+        assert_eq!(
+            glob_base_dir("content/blog/*.md"),
+            ("content/blog".to_string(), Some(1))
+        );
+        assert_eq!(glob_base_dir("*.md"), ("".to_string(), Some(1)));
+        assert_eq!(
+            glob_base_dir("src/{a,b}/*.rs"),
+            ("src".to_string(), Some(2))
+        );
+        assert_eq!(
+            glob_base_dir("/var/log/*.log"),
+            ("/var/log".to_string(), Some(1))
+        );
+        assert_eq!(glob_base_dir("/*.log"), ("/".to_string(), Some(1)));
+    }
+
+    #[test]
+    fn fs_glob_usage() {
+        let dir = std::env::temp_dir().join(format!("tan-fs-glob-{}", std::process::id()));
+        fs::create_dir_all(dir.join("blog/2024")).unwrap();
+        for path in ["a.md", "b.txt", "blog/c.md", "blog/2024/d.md"] {
+            fs::write(dir.join(path), "").unwrap();
+        }
+        let dir_path = dir.to_string_lossy();
+
+        let glob = |pattern: &str| {
+            let paths = fs_glob(&[Expr::string(format!("{dir_path}/{pattern}"))]).unwrap();
+            let paths = paths.as_array().unwrap();
+            paths
+                .iter()
+                .map(|path| path.as_string().unwrap().replace(&*dir_path, ""))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(glob("*.md"), vec!["/a.md"]);
+        assert_eq!(glob("*/*.md"), vec!["/blog/c.md"]);
+        assert_eq!(
+            glob("**/*.md"),
+            vec!["/a.md", "/blog/2024/d.md", "/blog/c.md"]
+        );
+        assert_eq!(glob("*.{md,txt}"), vec!["/a.md", "/b.txt"]);
+        assert!(glob("missing/*.md").is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(fs_glob(&[Expr::string("[a-")]).is_err());
+    }
+}
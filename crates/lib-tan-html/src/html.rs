@@ -5,21 +5,152 @@
 
 // #todo think a bit more about a good name
 // #todo probably should move out from std lib into platform lib
-// #todo also perform attribute and body escaping
-// #todo add unit tests
-// #todo what about attributes without value? for the moment just use true, e.g. { :attr true }, can optimize to no-value attr on rendering
-// #todo special handling for <!DOCTYPE html>
 
-// #todo translate (ul.nasty ..) to (ul {class: "nasty"})
-// #todo translate (ul#nasty ..) to (ul {id: "nasty"})
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+};
 
+use component::{invoke_component, is_component_tag, setup_lib_html_component, Slots};
+use escape::{escape_attribute, escape_raw_text, escape_text, setup_lib_html_escape};
+use read::setup_lib_html_read;
+use select::setup_lib_html_select;
 use tan::{
     context::Context,
     error::Error,
     expr::{format_value, Expr},
     parser::util::STRING_INTERPOLATION_FUNC,
-    util::{module_util::require_module, try_lock_read},
+    util::{args::unpack_arg, expect_lock_write, module_util::require_module, try_lock_read},
 };
+use tanutil::buffer::buffer_expr;
+
+pub mod component;
+pub mod escape;
+pub mod read;
+pub mod select;
+
+// #insight
+// Text children and attribute values are escaped, use `(raw "...")` or
+// `html/raw` to render trusted markup verbatim.
+
+// #insight
+// Boolean attributes use Bool values, e.g. `{:disabled true}` renders as
+// `disabled`, false or None values omit the attribute.
+
+// #ref https://html.spec.whatwg.org/multipage/syntax.html#void-elements
+const VOID_ELEMENTS: [&str; 13] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+fn is_void_element(tag: &str) -> bool {
+    VOID_ELEMENTS.contains(&tag)
+}
+
+/// Splits the `tag.class#id` shorthand, e.g. `(ul.nav.main#menu ...)` is
+/// equivalent to `(ul {:class "nav main" :id "menu"} ...)`. The tag defaults
+/// to `div`, e.g. `(.card ...)`.
+fn parse_tag(sym: &str) -> (&str, Option<&str>, Vec<&str>) {
+    let end = sym.find(['.', '#']).unwrap_or(sym.len());
+    let tag = if end == 0 { "div" } else { &sym[..end] };
+
+    let mut id = None;
+    let mut classes = Vec::new();
+
+    let mut rest = &sym[end..];
+    while let Some(marker) = rest.chars().next() {
+        let after_marker = &rest[1..];
+        let len = after_marker.find(['.', '#']).unwrap_or(after_marker.len());
+        let name = &after_marker[..len];
+        if !name.is_empty() {
+            if marker == '#' {
+                id = Some(name);
+            } else {
+                classes.push(name);
+            }
+        }
+        rest = &after_marker[len..];
+    }
+
+    (tag, id, classes)
+}
+
+// #ref https://html.spec.whatwg.org/multipage/syntax.html#attributes-2
+/// Returns true if the name can be written as an attribute name verbatim.
+fn is_valid_attribute_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.chars().any(|c| {
+            c.is_whitespace() || c.is_control() || matches!(c, '"' | '\'' | '>' | '/' | '=')
+        })
+}
+
+/// Renders the attributes, sorted by name for deterministic output. The
+/// shorthand classes are prepended to the `class` attribute, the `id`
+/// attribute overrides the shorthand id.
+fn render_attributes(
+    attributes_expr: Option<&Expr>,
+    id: Option<&str>,
+    classes: &[&str],
+) -> Result<String, Error> {
+    let attributes = attributes_expr.and_then(|expr| expr.as_map());
+    let attributes = attributes.as_deref();
+
+    let mut names: Vec<&String> = attributes.map(|a| a.keys().collect()).unwrap_or_default();
+    names.sort();
+
+    // #insight Attribute names are not escaped, an invalid name could inject markup.
+    if let Some(name) = names.iter().find(|name| !is_valid_attribute_name(name)) {
+        return Err(Error::invalid_arguments(
+            &format!("invalid attribute name `{name}`"),
+            attributes_expr.and_then(|expr| expr.range()),
+        ));
+    }
+
+    let mut html = String::new();
+
+    if let Some(id) = id {
+        if !names.iter().any(|name| *name == "id") {
+            html.push_str(&format!(" id=\"{}\"", escape_attribute(id)));
+        }
+    }
+
+    // #insight A false or None `class` keeps the shorthand classes.
+    let has_class = attributes
+        .and_then(|a| a.get("class"))
+        .is_some_and(|class| !matches!(class.unpack(), Expr::Bool(false) | Expr::None));
+
+    if !classes.is_empty() && !has_class {
+        html.push_str(&format!(
+            " class=\"{}\"",
+            escape_attribute(&classes.join(" "))
+        ));
+    }
+
+    let Some(attributes) = attributes else {
+        return Ok(html);
+    };
+
+    for name in names {
+        // #todo eval value!
+        let value = &attributes[name];
+        match value.unpack() {
+            Expr::Bool(true) => {
+                html.push(' ');
+                html.push_str(name);
+            }
+            Expr::Bool(false) | Expr::None => (),
+            _ => {
+                let mut value = format_value(value);
+                if name == "class" && !classes.is_empty() {
+                    value = format!("{} {value}", classes.join(" "));
+                }
+                html.push_str(&format!(" {name}=\"{}\"", escape_attribute(&value)));
+            }
+        }
+    }
+
+    Ok(html)
+}
 
 // #todo investigate the interaction between expr/string interpolation '$' and quoting, make string interpolation work in quoted expr.
 
@@ -32,30 +163,141 @@ use tan::{
 // )
 // ```
 
-// #todo how to interpolate values?
 // #todo consider using 'interned'/self-evaluating symbols instead of strings for text nodes.
 // #todo special handling of child strings with interpolation.
-// #todo support pretty-printing
-
-// #todo should take a list of expressions to handle '(!DOCTYPE "html")
-// #todo handle self-closing tags, e.g. <meta ... />, <link ... />
-// #todo should act on string, and only wrap in Expr::string(...) at the end.
-fn render_expr(expr: &Expr) -> Result<Expr, Error> {
-    let expr = expr.unpack();
-
-    match expr {
-        Expr::Array(terms) => {
-            // #todo this is a temp solution, investigate refactoring opportunities
-            let mut html = String::new();
-            let terms = try_lock_read(terms, None)?;
-            for term in terms.iter() {
-                let expr = render_expr(term)?;
-                html.push_str(&format_value(&expr));
+
+// #insight
+// The renderer writes into an io::Write, a Vec<u8> for `to-html` and
+// `to-buffer` or a File for `write`, instead of building (and copying) a
+// String per element. The Buffer from `to-buffer` can be returned as an HTTP
+// response body as is.
+
+// #insight
+// Pretty-printing only breaks lines around block content, inline elements and
+// text are kept on the same line, so the whitespace between words is not
+// changed. The contents of <pre>, <textarea>, <script> and <style>, including
+// nested elements, are never re-indented.
+
+const INLINE_ELEMENTS: [&str; 30] = [
+    "a", "abbr", "b", "bdi", "bdo", "br", "button", "cite", "code", "data", "dfn", "em", "i",
+    "img", "input", "kbd", "label", "mark", "q", "s", "samp", "select", "small", "span", "strong",
+    "sub", "sup", "time", "u", "var",
+];
+
+const PREFORMATTED_ELEMENTS: [&str; 4] = ["pre", "textarea", "script", "style"];
+
+/// Returns true if the expression renders block-level content, the slots are
+/// used to resolve the content of `(slot)` placeholders.
+fn is_block_expr(expr: &Expr, slots: &[Slots]) -> Result<bool, Error> {
+    match expr.unpack() {
+        Expr::List(terms) => {
+            let Some(sym) = terms.first().and_then(|op| op.as_symbol()) else {
+                return Ok(false);
+            };
+            if sym == "raw" || sym == STRING_INTERPOLATION_FUNC {
+                return Ok(false);
+            }
+            if sym == "slot" {
+                let Some((top, rest)) = slots.split_last() else {
+                    return Ok(true);
+                };
+                for expr in top.content(&terms[1..]) {
+                    if is_block_expr(expr, rest)? {
+                        return Ok(true);
+                    }
+                }
+                return Ok(false);
             }
-            Ok(Expr::string(html))
+            // #insight The output of a component is not known before invoking it.
+            if is_component_tag(sym) {
+                return Ok(true);
+            }
+            let (tag, _, _) = parse_tag(sym);
+            Ok(!INLINE_ELEMENTS.contains(&tag))
         }
-        Expr::List(terms) => {
-            if let Some(op) = terms.first() {
+        Expr::Array(array) => {
+            let array = try_lock_read(array, None)?;
+            for expr in array.iter() {
+                if is_block_expr(expr, slots)? {
+                    return Ok(true);
+                }
+            }
+            Ok(false)
+        }
+        _ => Ok(false),
+    }
+}
+
+/// Returns true for a `(slot ...)` placeholder without content, it renders nothing.
+fn is_empty_slot(terms: &[Expr], slots: &[Slots]) -> bool {
+    terms.first().and_then(|op| op.as_symbol()) == Some("slot")
+        && slots
+            .last()
+            .is_some_and(|top| top.content(&terms[1..]).is_empty())
+}
+
+fn is_doctype(expr: &Expr) -> bool {
+    match expr.unpack() {
+        Expr::List(terms) => terms.first().and_then(|op| op.as_symbol()) == Some("!DOCTYPE"),
+        _ => false,
+    }
+}
+
+struct Renderer<'a> {
+    writer: &'a mut dyn Write,
+    /// The indentation width, None for compact output.
+    indent: Option<usize>,
+    /// The context used to resolve and invoke components.
+    context: &'a mut Context,
+    /// The slots of the components being rendered, innermost last.
+    slots: Vec<Slots>,
+    /// True while rendering the content of a preformatted element.
+    preformatted: bool,
+    /// True while rendering the content of a <script> or <style> element.
+    raw_text: bool,
+}
+
+impl<'a> Renderer<'a> {
+    fn new(writer: &'a mut dyn Write, indent: Option<usize>, context: &'a mut Context) -> Self {
+        Self {
+            writer,
+            indent,
+            context,
+            slots: Vec::new(),
+            preformatted: false,
+            raw_text: false,
+        }
+    }
+
+    fn write_new_line(&mut self, depth: usize) -> Result<(), Error> {
+        if self.preformatted {
+            return Ok(());
+        }
+        if let Some(indent) = self.indent {
+            write!(self.writer, "\n{:width$}", "", width = depth * indent)?;
+        }
+        Ok(())
+    }
+
+    fn render(&mut self, expr: &Expr, depth: usize) -> Result<(), Error> {
+        let expr = expr.unpack();
+
+        match expr {
+            Expr::Array(terms) => {
+                let terms = try_lock_read(terms, None)?;
+                for term in terms.iter() {
+                    self.render(term, depth)?;
+                }
+            }
+            Expr::List(terms) => {
+                let Some(op) = terms.first() else {
+                    // #todo offer context, e.g. in which function we are.
+                    return Err(Error::invalid_arguments(
+                        "empty expression, remove",
+                        expr.range(),
+                    ));
+                };
+
                 let Some(sym) = op.as_symbol() else {
                     // #todo we could return the argument position here and enrich the error upstream.
                     // #todo hmm, the error is too precise here, do we really need the annotations?
@@ -65,114 +307,327 @@ fn render_expr(expr: &Expr) -> Result<Expr, Error> {
                     ));
                 };
 
-                // #insight #hack special handling of (!DOCTYPE html), html is optional, just (!DOCTYPE) works.
-                if sym == "!DOCTYPE" {
+                match sym {
+                    // #insight #hack special handling of (!DOCTYPE html), html is optional, just (!DOCTYPE) works.
                     // #todo also check the `html` part.
-                    return Ok(Expr::string("<!DOCTYPE html>\n"));
+                    "!DOCTYPE" => writeln!(self.writer, "<!DOCTYPE html>")?,
+                    "raw" => {
+                        for term in &terms[1..] {
+                            self.writer.write_all(format_value(term).as_bytes())?;
+                        }
+                    }
+                    // #todo #hack this is a temp fix
+                    STRING_INTERPOLATION_FUNC => {
+                        // #todo just use String/format
+                        for term in &terms[1..] {
+                            self.write_text(&format_value(term))?;
+                        }
+                    }
+                    "slot" => self.render_slot(&terms[1..], depth)?,
+                    _ if is_component_tag(sym) => self.render_component(op, &terms[1..], depth)?,
+                    _ => self.render_element(sym, &terms[1..], depth)?,
                 }
+            }
+            // #insight None (unit) expressions should be skipped.
+            // #insight false is skipped too, e.g. for `(and logged-in? '(a ...))`.
+            // #todo Is Never case needed here?
+            Expr::None | Expr::Never | Expr::Bool(false) => (),
+            _ => self.write_text(&format_value(expr))?,
+        }
+
+        Ok(())
+    }
+
+    // #insight <script> and <style> contain raw text, not markup.
+    fn write_text(&mut self, text: &str) -> Result<(), Error> {
+        let text = if self.raw_text {
+            escape_raw_text(text)
+        } else {
+            escape_text(text)
+        };
+        self.writer.write_all(text.as_bytes())?;
+        Ok(())
+    }
+
+    fn render_element(&mut self, sym: &str, terms: &[Expr], depth: usize) -> Result<(), Error> {
+        let (tag, id, classes) = parse_tag(sym);
+
+        let (attributes, children) = match terms.first() {
+            Some(term) if term.as_map().is_some() => (Some(term), &terms[1..]),
+            _ => (None, terms),
+        };
+        let attributes = render_attributes(attributes, id, &classes)?;
+
+        if is_void_element(tag) {
+            if let Some(child) = children.first() {
+                return Err(Error::invalid_arguments(
+                    &format!("void element `{tag}` cannot have children"),
+                    child.range(),
+                ));
+            }
+            write!(self.writer, "<{tag}{attributes}>")?;
+            return Ok(());
+        }
+
+        write!(self.writer, "<{tag}{attributes}>")?;
+
+        let is_preformatted = PREFORMATTED_ELEMENTS.contains(&tag);
 
-                // #todo #hack this is a temp fix
-                if sym == STRING_INTERPOLATION_FUNC {
-                    // #todo just use String/format
-                    let output = terms.iter().skip(1).fold(String::new(), |mut str, x| {
-                        str.push_str(&format_value(x));
-                        str
-                    });
-                    return Ok(Expr::string(output));
+        let mut is_block = false;
+        if self.indent.is_some() && !self.preformatted && !is_preformatted {
+            for child in children {
+                if is_block_expr(child, &self.slots)? {
+                    is_block = true;
+                    break;
                 }
+            }
+        }
 
-                let mut i = 1;
-
-                if let Some(term) = terms.get(i) {
-                    let attributes: String = if let Some(map) = term.as_map() {
-                        i += 1;
-                        // #todo eval value!
-                        // #todo escape value!
-                        format!(
-                            " {}",
-                            map.iter()
-                                .map(|(k, v)| format!("{k}=\"{}\"", format_value(v)))
-                                .collect::<Vec<String>>()
-                                .join(" ")
-                        )
-                    } else {
-                        "".to_string()
-                    };
-
-                    // #todo escape body/children
-                    let mut body = String::from("");
-
-                    while i < terms.len() {
-                        // #todo if term is an array, spread it!
-                        let term = &terms[i];
-
-                        match term.unpack() {
-                            Expr::Array(array) => {
-                                let array = try_lock_read(array, None)?;
-                                for t in array.iter() {
-                                    let child = render_expr(t)?;
-                                    body.push_str(&format_value(&child));
-                                }
-                            }
-                            _ => {
-                                let child = render_expr(&terms[i])?;
-                                body.push_str(&format_value(&child));
-                            }
-                        }
-                        // #todo handle () / empty / Never (for conditionals)
-                        // #insight spread will work nicely with for->list
-                        // let child = render_expr(&terms[i])?;
-                        // body.push_str(&format_value(&child));
-                        i += 1;
-                    }
+        let preformatted = self.preformatted;
+        let raw_text = self.raw_text;
+        self.preformatted = preformatted || is_preformatted;
+        self.raw_text = raw_text || tag == "script" || tag == "style";
+        let result = self.render_children(children, is_block, depth);
+        self.preformatted = preformatted;
+        self.raw_text = raw_text;
+        result?;
 
-                    // #todo eval body.
+        if is_block {
+            self.write_new_line(depth)?;
+        }
+
+        // #todo eval body.
 
-                    // #insight always close <script> tags.
+        // #insight Non-void elements are never self-closed, `<div />` is parsed as an open tag.
+        write!(self.writer, "</{tag}>")?;
 
-                    if body.is_empty() && sym != "script" {
-                        Ok(Expr::string(format!("<{sym}{attributes} />")))
-                    } else {
-                        Ok(Expr::string(format!("<{sym}{attributes}>{body}</{sym}>")))
+        Ok(())
+    }
+
+    fn render_children(
+        &mut self,
+        children: &[Expr],
+        is_block: bool,
+        depth: usize,
+    ) -> Result<(), Error> {
+        for child in children {
+            match child.unpack() {
+                // #insight spread will work nicely with for->list
+                Expr::Array(array) => {
+                    let array = try_lock_read(array, None)?;
+                    self.render_children(&array, is_block, depth)?;
+                }
+                Expr::None | Expr::Never | Expr::Bool(false) => (),
+                Expr::List(terms) if is_empty_slot(terms, &self.slots) => (),
+                _ => {
+                    if is_block {
+                        self.write_new_line(depth + 1)?;
                     }
-                } else {
-                    Ok(Expr::string(format!("<{sym} />")))
+                    self.render(child, depth + 1)?;
                 }
-            } else {
-                // #todo offer context, e.g. in which function we are.
-                Err(Error::invalid_arguments(
-                    "empty expression, remove",
-                    expr.range(),
-                )) // #todo
             }
         }
-        // #todo is there a better way to do this?
-        // #insight None (unit) expressions should be skipped.
-        Expr::None => Ok(Expr::string("")),
-        // #todo Is Never case needed here?
-        Expr::Never => Ok(Expr::string("")),
-        _ => Ok(Expr::string(format_value(expr))),
+
+        Ok(())
+    }
+
+    fn render_component(&mut self, op: &Expr, terms: &[Expr], depth: usize) -> Result<(), Error> {
+        let (output, slots) = invoke_component(op, terms, self.context)?;
+
+        self.slots.push(slots);
+        let result = self.render(&output, depth);
+        self.slots.pop();
+
+        result
+    }
+
+    fn render_slot_content(&mut self, content: &[Expr], depth: usize) -> Result<(), Error> {
+        for (i, expr) in content.iter().enumerate() {
+            if i > 0 && is_block_expr(expr, &self.slots)? {
+                self.write_new_line(depth)?;
+            }
+            self.render(expr, depth)?;
+        }
+
+        Ok(())
+    }
+
+    /// Renders the content passed to a `(slot)` of the enclosing component, or
+    /// the fallback content if the slot is not filled.
+    fn render_slot(&mut self, terms: &[Expr], depth: usize) -> Result<(), Error> {
+        // #insight Outside of components, <slot> is the Web Components element.
+        let Some(slots) = self.slots.pop() else {
+            return self.render_element("slot", terms, depth);
+        };
+
+        // #insight
+        // The slot content belongs to the caller of the component, it is
+        // rendered with the caller's slots, e.g. when passing through slots.
+        let result = self.render_slot_content(slots.content(terms), depth);
+
+        self.slots.push(slots);
+
+        result
     }
 }
 
-// #todo find a better name.
-pub fn html_from_expr(args: &[Expr]) -> Result<Expr, Error> {
-    if let Some(expr) = args.first() {
-        render_expr(expr)
-    } else {
-        Err(Error::invalid_arguments(
+/// Splits the trailing options Map from the expressions to render, returns
+/// the indentation width for pretty-printing.
+fn unpack_render_args(args: &[Expr]) -> (&[Expr], Option<usize>) {
+    let Some(options) = args.last().and_then(|arg| arg.as_map()) else {
+        return (args, None);
+    };
+
+    let exprs = &args[..args.len() - 1];
+
+    if !matches!(
+        options.get("pretty").map(|p| p.unpack()),
+        Some(Expr::Bool(true))
+    ) {
+        return (exprs, None);
+    }
+
+    let indent = options
+        .get("indent")
+        .and_then(|i| i.as_int())
+        .unwrap_or(2)
+        .max(0) as usize;
+
+    (exprs, Some(indent))
+}
+
+fn render_to_bytes(
+    exprs: &[Expr],
+    indent: Option<usize>,
+    context: &mut Context,
+) -> Result<Vec<u8>, Error> {
+    let mut output: Vec<u8> = Vec::new();
+
+    let mut renderer = Renderer::new(&mut output, indent, context);
+    for expr in exprs {
+        renderer.render(expr, 0)?;
+    }
+
+    Ok(output)
+}
+
+fn render_to_string(
+    exprs: &[Expr],
+    indent: Option<usize>,
+    context: &mut Context,
+) -> Result<String, Error> {
+    let output = render_to_bytes(exprs, indent, context)?;
+
+    String::from_utf8(output).map_err(|_| Error::general("invalid UTF-8 in rendered HTML"))
+}
+
+/// Renders one or more expressions to an HTML String, Arrays of expressions
+/// are rendered in sequence. A trailing options Map enables pretty-printing.
+/// ```tan
+/// (html/to-html
+///     '(!DOCTYPE html)
+///     '(html
+///         (head (meta {:charset "utf-8"}) (title "Tan"))
+///         (body (ul.nav#menu (li "Home")) (input {:type "checkbox" :checked true}))
+///     )
+/// )
+/// (html/to-html page {:pretty true :indent 4})
+/// (html/to-html '(Layout {:title "Blog"} (nav {:slot "sidebar"} ...) (Card {:title "Hi"} "Body")))
+/// ```
+pub fn html_from_expr(args: &[Expr], context: &mut Context) -> Result<Expr, Error> {
+    let (exprs, indent) = unpack_render_args(args);
+
+    if exprs.is_empty() {
+        return Err(Error::invalid_arguments(
             "expected at least one argument",
             None,
-        ))
+        ));
+    }
+
+    Ok(Expr::string(render_to_string(exprs, indent, context)?))
+}
+
+/// Renders one or more expressions to a Buffer, e.g. for an HTTP response
+/// body, a trailing options Map enables pretty-printing.
+/// ```tan
+/// (http/serve {:port 8000} (Func [req] [200 {:content-type "text/html"} (html/to-buffer page)]))
+/// ```
+pub fn html_to_buffer(args: &[Expr], context: &mut Context) -> Result<Expr, Error> {
+    let (exprs, indent) = unpack_render_args(args);
+
+    Ok(buffer_expr(render_to_bytes(exprs, indent, context)?))
+}
+
+/// Renders a full HTML document, the `<!DOCTYPE html>` is prepended if missing.
+/// ```tan
+/// (html/document '(html (head (title "Tan")) (body "Hello")))
+/// ```
+pub fn html_document(args: &[Expr], context: &mut Context) -> Result<Expr, Error> {
+    let (exprs, indent) = unpack_render_args(args);
+
+    let html = render_to_string(exprs, indent, context)?;
+
+    if exprs.first().is_some_and(is_doctype) {
+        Ok(Expr::string(html))
+    } else {
+        Ok(Expr::string(format!("<!DOCTYPE html>\n{html}")))
     }
 }
 
+/// Renders one or more expressions directly into a File, without building
+/// the whole HTML String in memory.
+/// ```tan
+/// (let file (fs/create "public/index.html"))
+/// (html/write file '(!DOCTYPE html) page {:pretty true})
+/// ```
+pub fn html_write(args: &[Expr], context: &mut Context) -> Result<Expr, Error> {
+    let target = unpack_arg(args, 0, "target")?;
+    let (exprs, indent) = unpack_render_args(&args[1..]);
+
+    let Expr::ForeignMut(file) = target.unpack() else {
+        return Err(Error::invalid_arguments(
+            "`target` argument should be a File",
+            target.range(),
+        ));
+    };
+
+    let file = expect_lock_write(file);
+    let Some(file) = file.downcast_ref::<File>() else {
+        return Err(Error::invalid_arguments("invalid File", target.range()));
+    };
+
+    let mut writer = BufWriter::new(file);
+
+    let mut renderer = Renderer::new(&mut writer, indent, context);
+    for expr in exprs {
+        renderer.render(expr, 0)?;
+    }
+
+    writer.flush()?;
+
+    // #todo What is a good return value?
+    Ok(Expr::None)
+}
+
 // #todo Consider use_lib_*
 // #todo Find a better module-path.
 pub fn import_lib_html(context: &mut Context) {
     let module = require_module("html", context);
 
-    module.insert_invocable("html-from-expr", Expr::foreign_func(&html_from_expr));
+    module.insert_invocable("to-html", Expr::foreign_func_mut_context(&html_from_expr));
+    // #todo Remove, kept for backwards compatibility.
+    module.insert_invocable(
+        "html-from-expr",
+        Expr::foreign_func_mut_context(&html_from_expr),
+    );
+    module.insert_invocable("to-buffer", Expr::foreign_func_mut_context(&html_to_buffer));
+    module.insert_invocable("document", Expr::foreign_func_mut_context(&html_document));
+    module.insert_invocable("write", Expr::foreign_func_mut_context(&html_write));
+
+    setup_lib_html_component(context);
+    setup_lib_html_escape(context);
+    setup_lib_html_read(context);
+    setup_lib_html_select(context);
 
     // #insight
     // This is currently an experiment to add additional methods implemented
@@ -182,3 +637,282 @@ pub fn import_lib_html(context: &mut Context) {
     // #todo Handle error
     // let _ = eval_module("html", context, true);
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        fs::File,
+        sync::{Arc, RwLock},
+    };
+
+    use tan::{context::Context, expr::Expr, parser::util::STRING_INTERPOLATION_FUNC};
+
+    use crate::html::{
+        html_document, html_from_expr, html_to_buffer, html_write, is_void_element, parse_tag,
+    };
+
+    fn element(sym: &str, terms: Vec<Expr>) -> Expr {
+        let mut list = vec![Expr::symbol(sym)];
+        list.extend(terms);
+        Expr::List(list)
+    }
+
+    fn attributes(attributes: Vec<(&str, Expr)>) -> Expr {
+        Expr::map(
+            attributes
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value))
+                .collect::<HashMap<_, _>>(),
+        )
+    }
+
+    fn pretty(indent: i64) -> Expr {
+        attributes(vec![
+            ("pretty", Expr::Bool(true)),
+            ("indent", Expr::Int(indent)),
+        ])
+    }
+
+    fn to_html(args: &[Expr]) -> String {
+        let mut context = Context::new();
+        let html = html_from_expr(args, &mut context).unwrap();
+        html.as_string().unwrap().to_string()
+    }
+
+    #[test]
+    fn parse_tag_usage() {
+        assert_eq!(parse_tag("ul"), ("ul", None, vec![]));
+        assert_eq!(parse_tag("ul.nav"), ("ul", None, vec!["nav"]));
+        assert_eq!(
+            parse_tag("ul.nav.main#menu"),
+            ("ul", Some("menu"), vec!["nav", "main"])
+        );
+        assert_eq!(parse_tag("#app.dark"), ("div", Some("app"), vec!["dark"]));
+        assert_eq!(parse_tag("my-element"), ("my-element", None, vec![]));
+    }
+
+    #[test]
+    fn is_void_element_usage() {
+        assert!(is_void_element("br"));
+        assert!(is_void_element("meta"));
+        assert!(!is_void_element("textarea"));
+        assert!(!is_void_element("script"));
+    }
+
+    #[test]
+    fn render_void_elements() {
+        let expr = element(
+            "p",
+            vec![Expr::string("a"), element("br", vec![]), Expr::string("b")],
+        );
+        assert_eq!(to_html(&[expr]), "<p>a<br>b</p>");
+
+        let expr = element(
+            "img",
+            vec![attributes(vec![("src", Expr::string("a.png"))])],
+        );
+        assert_eq!(to_html(&[expr]), r#"<img src="a.png">"#);
+
+        let mut context = Context::new();
+        let expr = element("br", vec![Expr::string("x")]);
+        assert!(html_from_expr(&[expr], &mut context).is_err());
+    }
+
+    #[test]
+    fn render_non_void_elements_are_never_self_closed() {
+        assert_eq!(to_html(&[element("div", vec![])]), "<div></div>");
+        assert_eq!(to_html(&[element("script", vec![])]), "<script></script>");
+        assert_eq!(
+            to_html(&[element("my-element", vec![])]),
+            "<my-element></my-element>"
+        );
+    }
+
+    #[test]
+    fn render_boolean_attributes() {
+        let expr = element(
+            "input",
+            vec![attributes(vec![
+                ("type", Expr::string("checkbox")),
+                ("checked", Expr::Bool(true)),
+                ("disabled", Expr::Bool(false)),
+                ("required", Expr::None),
+            ])],
+        );
+        assert_eq!(to_html(&[expr]), r#"<input checked type="checkbox">"#);
+    }
+
+    #[test]
+    fn render_shorthand_classes() {
+        let expr = element("ul.nav#menu", vec![]);
+        assert_eq!(to_html(&[expr]), r#"<ul id="menu" class="nav"></ul>"#);
+
+        let expr = element(
+            "div.a.b",
+            vec![attributes(vec![("class", Expr::string("c"))])],
+        );
+        assert_eq!(to_html(&[expr]), r#"<div class="a b c"></div>"#);
+
+        let expr = element(
+            "div.a",
+            vec![attributes(vec![("class", Expr::Bool(false))])],
+        );
+        assert_eq!(to_html(&[expr]), r#"<div class="a"></div>"#);
+
+        let expr = element("div.a", vec![attributes(vec![("class", Expr::None)])]);
+        assert_eq!(to_html(&[expr]), r#"<div class="a"></div>"#);
+
+        let expr = element("div#a", vec![attributes(vec![("id", Expr::string("b"))])]);
+        assert_eq!(to_html(&[expr]), r#"<div id="b"></div>"#);
+    }
+
+    #[test]
+    fn render_escapes_text_and_attributes() {
+        let expr = element(
+            "a",
+            vec![
+                attributes(vec![("title", Expr::string(r#""Tom" & Jerry"#))]),
+                Expr::string("<b>"),
+                element("raw", vec![Expr::string("<i>ok</i>")]),
+            ],
+        );
+        assert_eq!(
+            to_html(&[expr]),
+            r#"<a title="&quot;Tom&quot; &amp; Jerry">&lt;b&gt;<i>ok</i></a>"#
+        );
+    }
+
+    #[test]
+    fn render_escapes_raw_text() {
+        // (script "a && b </script>" $x 1)
+        let expr = element(
+            "script",
+            vec![
+                Expr::string("a && b </script>"),
+                element(
+                    STRING_INTERPOLATION_FUNC,
+                    vec![Expr::string("</script><b>")],
+                ),
+                Expr::Int(1),
+            ],
+        );
+        assert_eq!(
+            to_html(&[expr]),
+            r#"<script>a && b <\/script><\/script><b>1</script>"#
+        );
+
+        let expr = element("style", vec![Expr::string("a > b {} </style>")]);
+        assert_eq!(to_html(&[expr]), r#"<style>a > b {} <\/style></style>"#);
+    }
+
+    #[test]
+    fn render_rejects_invalid_attribute_names() {
+        let mut context = Context::new();
+
+        for name in ["a b", "a\"", "a'", "a>", "a/", "a=b", "a\u{7}", ""] {
+            let expr = element("div", vec![attributes(vec![(name, Expr::string("x"))])]);
+            assert!(html_from_expr(&[expr], &mut context).is_err(), "{name:?}");
+        }
+
+        let expr = element(
+            "div",
+            vec![attributes(vec![
+                ("data-id", Expr::Int(1)),
+                ("@click", Expr::string("go")),
+            ])],
+        );
+        assert_eq!(to_html(&[expr]), r#"<div @click="go" data-id="1"></div>"#);
+    }
+
+    #[test]
+    fn html_document_usage() {
+        let mut context = Context::new();
+
+        let page = element("html", vec![element("body", vec![Expr::string("Hi")])]);
+        let html = html_document(std::slice::from_ref(&page), &mut context).unwrap();
+        assert_eq!(
+            html.as_string(),
+            Some("<!DOCTYPE html>\n<html><body>Hi</body></html>")
+        );
+
+        // The DOCTYPE is not repeated.
+        let doctype = element("!DOCTYPE", vec![Expr::symbol("html")]);
+        let html = html_document(&[doctype, page], &mut context).unwrap();
+        assert_eq!(
+            html.as_string(),
+            Some("<!DOCTYPE html>\n<html><body>Hi</body></html>")
+        );
+    }
+
+    #[test]
+    fn render_pretty() {
+        let page = element(
+            "div",
+            vec![
+                element(
+                    "p",
+                    vec![Expr::string("a "), element("b", vec![Expr::string("b")])],
+                ),
+                element("ul", vec![element("li", vec![Expr::string("c")])]),
+            ],
+        );
+        assert_eq!(
+            to_html(&[page.clone(), pretty(2)]),
+            "<div>\n  <p>a <b>b</b></p>\n  <ul>\n    <li>c</li>\n  </ul>\n</div>"
+        );
+        assert_eq!(
+            to_html(&[page.clone(), pretty(4)]),
+            "<div>\n    <p>a <b>b</b></p>\n    <ul>\n        <li>c</li>\n    </ul>\n</div>"
+        );
+
+        let options = attributes(vec![("pretty", Expr::Bool(false))]);
+        assert_eq!(
+            to_html(&[page, options]),
+            "<div><p>a <b>b</b></p><ul><li>c</li></ul></div>"
+        );
+    }
+
+    #[test]
+    fn render_pretty_keeps_preformatted_content() {
+        let pre = element(
+            "pre",
+            vec![element("div", vec![element("p", vec![Expr::string("x")])])],
+        );
+        let page = element("section", vec![pre]);
+        assert_eq!(
+            to_html(&[page, pretty(2)]),
+            "<section>\n  <pre><div><p>x</p></div></pre>\n</section>"
+        );
+    }
+
+    #[test]
+    fn html_to_buffer_usage() {
+        let mut context = Context::new();
+
+        let page = element("p", vec![Expr::string("Hi")]);
+        let buffer = html_to_buffer(&[page], &mut context).unwrap();
+        let (length, bytes) = buffer.as_buffer().unwrap();
+        assert_eq!(&bytes[..length], b"<p>Hi</p>");
+    }
+
+    #[test]
+    fn html_write_usage() {
+        let path = std::env::temp_dir().join(format!("tan-html-{}.html", std::process::id()));
+        let file = File::create(&path).unwrap();
+        let file = Expr::ForeignMut(Arc::new(RwLock::new(file)));
+
+        let mut context = Context::new();
+
+        let doctype = element("!DOCTYPE", vec![Expr::symbol("html")]);
+        let page = element("html", vec![element("body", vec![Expr::string("Hi")])]);
+        html_write(&[file, doctype, page, pretty(2)], &mut context).unwrap();
+
+        let html = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(html, "<!DOCTYPE html>\n<html>\n  <body>Hi</body>\n</html>");
+
+        let target = Expr::string("index.html");
+        assert!(html_write(&[target, Expr::string("Hi")], &mut context).is_err());
+    }
+}
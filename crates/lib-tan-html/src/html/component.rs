@@ -0,0 +1,525 @@
+use std::collections::{HashMap, HashSet};
+
+use tan::{
+    context::Context,
+    error::Error,
+    eval::invoke,
+    expr::{format_value, Expr},
+    util::{
+        args::{unpack_arg, unpack_array_arg},
+        module_util::require_module,
+        try_lock_read,
+    },
+};
+
+use super::parse_tag;
+
+// #insight
+// A component is a Tan function bound to a capitalized Symbol, used as a
+// custom tag, e.g. `(Card {:title "Hi"} (p "Body"))`. It is invoked with the
+// attributes Map and the Array of children, and the returned expression is
+// rendered in place:
+//
+// ```tan
+// (let Card (Func [attrs children]
+//     '(article.card
+//         (header (h2 $(attrs :title)) (slot :actions))
+//         (slot)
+//     )
+// ))
+// ```
+
+// #insight
+// Layouts are components with named slots. Children with a `slot` attribute
+// fill the slot of that name, the rest fill the default `(slot)`. The `slot`
+// attribute is removed when routing the children. Fallback content is
+// rendered when a slot is not filled, e.g. `(slot :footer (p "©"))`. The slot
+// name is a KeySymbol, so fallback text is not mistaken for a name, e.g.
+// `(slot "No items")`. Children for a slot the component does not declare are
+// reported as an error instead of being dropped.
+
+// #insight
+// The `tag.class#id` shorthand is supported, e.g. `(Layout.wide ...)` invokes
+// `Layout` with the shorthand merged into the `class` and `id` attributes.
+
+// #todo Consider passing the slots to the component function as well.
+
+/// The children passed to a component, grouped by slot.
+#[derive(Default)]
+pub struct Slots {
+    pub default: Vec<Expr>,
+    pub named: HashMap<String, Vec<Expr>>,
+}
+
+impl Slots {
+    pub fn get(&self, name: Option<&str>) -> &[Expr] {
+        match name {
+            Some(name) => self.named.get(name).map(|c| c.as_slice()).unwrap_or(&[]),
+            None => &self.default,
+        }
+    }
+
+    /// Returns the content for a `(slot ...)` placeholder, the fallback
+    /// content if the slot is not filled.
+    pub fn content<'a>(&'a self, terms: &'a [Expr]) -> &'a [Expr] {
+        let (name, fallback) = unpack_slot_terms(terms);
+        let content = self.get(name);
+        if content.is_empty() {
+            fallback
+        } else {
+            content
+        }
+    }
+}
+
+/// Component tags start with an uppercase letter, e.g. `Card`, HTML tags are
+/// lowercase.
+pub fn is_component_tag(sym: &str) -> bool {
+    sym.starts_with(|c: char| c.is_ascii_uppercase())
+}
+
+/// Returns the value of the `slot` attribute of an element.
+fn slot_name(expr: &Expr) -> Option<String> {
+    let Expr::List(terms) = expr.unpack() else {
+        return None;
+    };
+    let attributes = terms.get(1)?.as_map()?;
+    let name = attributes.get("slot")?.as_stringable()?;
+    Some(name.to_string())
+}
+
+/// Returns the element without the `slot` attribute, the attributes Map is
+/// removed if empty.
+fn without_slot_attribute(expr: &Expr) -> Expr {
+    let Expr::List(terms) = expr.unpack() else {
+        return expr.clone();
+    };
+    let Some(attributes) = terms.get(1).and_then(|term| term.as_map()) else {
+        return expr.clone();
+    };
+
+    let mut attributes = attributes.clone();
+    attributes.remove("slot");
+
+    let mut element = vec![terms[0].clone()];
+    if !attributes.is_empty() {
+        element.push(Expr::map(attributes));
+    }
+    element.extend(terms[2..].iter().cloned());
+
+    Expr::List(element)
+}
+
+fn collect_slots(children: &[Expr], slots: &mut Slots) -> Result<(), Error> {
+    for child in children {
+        match child.unpack() {
+            Expr::Array(array) => {
+                let array = try_lock_read(array, None)?;
+                collect_slots(&array, slots)?;
+            }
+            Expr::None | Expr::Never | Expr::Bool(false) => (),
+            _ => match slot_name(child) {
+                Some(name) => slots
+                    .named
+                    .entry(name)
+                    .or_default()
+                    .push(without_slot_attribute(child)),
+                None => slots.default.push(child.clone()),
+            },
+        }
+    }
+
+    Ok(())
+}
+
+/// Merges the `tag.class#id` shorthand into the attributes, the shorthand
+/// classes are prepended to the `class` attribute.
+fn merge_shorthand(attributes: &mut HashMap<String, Expr>, id: Option<&str>, classes: &[&str]) {
+    if let Some(id) = id {
+        attributes
+            .entry("id".to_string())
+            .or_insert_with(|| Expr::string(id));
+    }
+
+    if classes.is_empty() {
+        return;
+    }
+
+    let class = match attributes.get("class").map(|class| class.unpack()) {
+        Some(Expr::Bool(false) | Expr::None) | None => classes.join(" "),
+        Some(class) => format!("{} {}", classes.join(" "), format_value(class)),
+    };
+    attributes.insert("class".to_string(), Expr::string(class));
+}
+
+/// Invokes the component bound to the `op` Symbol, returns the expression to
+/// render and the slots filled by the caller.
+pub fn invoke_component(
+    op: &Expr,
+    terms: &[Expr],
+    context: &mut Context,
+) -> Result<(Expr, Slots), Error> {
+    let Some(sym) = op.as_symbol() else {
+        return Err(Error::invalid_arguments(
+            &format!("{op} is not a Symbol"),
+            op.range(),
+        ));
+    };
+
+    let (name, id, classes) = parse_tag(sym);
+
+    let Some(component) = context.scope.get(name) else {
+        return Err(Error::invalid_arguments(
+            &format!("unknown component `{name}`"),
+            op.range(),
+        ));
+    };
+
+    let (mut attributes, children) = match terms.first().and_then(|term| term.as_map()) {
+        Some(attributes) => (attributes.clone(), &terms[1..]),
+        None => (HashMap::new(), terms),
+    };
+
+    merge_shorthand(&mut attributes, id, &classes);
+
+    let mut slots = Slots::default();
+    collect_slots(children, &mut slots)?;
+
+    let output = invoke(
+        &component,
+        vec![Expr::map(attributes), Expr::array(slots.default.clone())],
+        context,
+    )?;
+
+    let mut declared = HashSet::new();
+    collect_slot_names(&output, &mut declared)?;
+
+    let mut undeclared: Vec<&String> = slots
+        .named
+        .keys()
+        .filter(|slot| !declared.contains(slot.as_str()))
+        .collect();
+    undeclared.sort();
+    if let Some(slot) = undeclared.first() {
+        return Err(Error::invalid_arguments(
+            &format!("component `{name}` has no `{slot}` slot"),
+            op.range(),
+        ));
+    }
+
+    Ok((output, slots))
+}
+
+/// Collects the names of the `(slot :name ...)` placeholders in the output of
+/// a component.
+fn collect_slot_names(expr: &Expr, names: &mut HashSet<String>) -> Result<(), Error> {
+    match expr.unpack() {
+        Expr::List(terms) => {
+            if terms.first().and_then(|op| op.as_symbol()) == Some("slot") {
+                if let (Some(name), _) = unpack_slot_terms(&terms[1..]) {
+                    names.insert(name.to_string());
+                }
+            }
+            for term in terms {
+                collect_slot_names(term, names)?;
+            }
+        }
+        Expr::Array(array) => {
+            let array = try_lock_read(array, None)?;
+            for expr in array.iter() {
+                collect_slot_names(expr, names)?;
+            }
+        }
+        _ => (),
+    }
+
+    Ok(())
+}
+
+/// Splits the arguments of a `(slot)` placeholder into the optional slot name
+/// and the fallback content.
+fn unpack_slot_terms(terms: &[Expr]) -> (Option<&str>, &[Expr]) {
+    match terms.first().map(|term| term.unpack()) {
+        Some(Expr::KeySymbol(name)) => (Some(name), &terms[1..]),
+        _ => (None, terms),
+    }
+}
+
+fn is_truthy(expr: &Expr) -> bool {
+    !matches!(expr.unpack(), Expr::Bool(false) | Expr::None | Expr::Never)
+}
+
+/// Maps a function over an Array, returns an Array of the results, None
+/// results are skipped.
+/// ```tan
+/// '(ul $(html/each posts (Func [post] '(li (a {:href $(post :url)} $(post :title))))))
+/// ```
+pub fn html_each(args: &[Expr], context: &mut Context) -> Result<Expr, Error> {
+    let items = unpack_array_arg(args, 0, "items")?;
+    let func = unpack_arg(args, 1, "func")?;
+
+    let mut exprs = Vec::new();
+
+    for item in items.iter() {
+        let expr = invoke(func, vec![item.clone()], context)?;
+        if !matches!(expr.unpack(), Expr::None | Expr::Never) {
+            exprs.push(expr);
+        }
+    }
+
+    Ok(Expr::array(exprs))
+}
+
+/// Returns the expressions when the condition is truthy, None otherwise, so
+/// nothing is rendered.
+/// ```tan
+/// '(nav $(html/when logged-in? '(a {:href "/logout"} "Logout")))
+/// ```
+pub fn html_when(args: &[Expr]) -> Result<Expr, Error> {
+    let condition = unpack_arg(args, 0, "condition")?;
+
+    if is_truthy(condition) {
+        Ok(Expr::array(args[1..].to_vec()))
+    } else {
+        Ok(Expr::None)
+    }
+}
+
+pub fn setup_lib_html_component(context: &mut Context) {
+    let module = require_module("html", context);
+
+    module.insert_invocable("each", Expr::foreign_func_mut_context(&html_each));
+    module.insert_invocable("when", Expr::foreign_func(&html_when));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use tan::{context::Context, error::Error, expr::Expr};
+
+    use crate::html::{
+        component::{collect_slots, is_component_tag, unpack_slot_terms, Slots},
+        html_from_expr,
+    };
+
+    fn element(sym: &str, terms: Vec<Expr>) -> Expr {
+        let mut list = vec![Expr::symbol(sym)];
+        list.extend(terms);
+        Expr::List(list)
+    }
+
+    fn slot_attribute(name: &str) -> Expr {
+        Expr::map(HashMap::from([("slot".to_string(), Expr::string(name))]))
+    }
+
+    /// `(article {:class $(attrs :class)} (h2 $(attrs :title)) (slot))`
+    fn card(args: &[Expr], _context: &mut Context) -> Result<Expr, Error> {
+        let attributes = args[0].as_map().unwrap();
+        let class = attributes.get("class").cloned().unwrap_or(Expr::None);
+        let title = attributes.get("title").cloned().unwrap_or(Expr::None);
+
+        Ok(element(
+            "article",
+            vec![
+                Expr::map(HashMap::from([("class".to_string(), class)])),
+                element("h2", vec![title]),
+                element("slot", vec![]),
+            ],
+        ))
+    }
+
+    /// `(div.layout (aside (slot :sidebar (p "No sidebar"))) (main (slot)))`
+    fn layout(_args: &[Expr], _context: &mut Context) -> Result<Expr, Error> {
+        Ok(element(
+            "div.layout",
+            vec![
+                element(
+                    "aside",
+                    vec![element(
+                        "slot",
+                        vec![
+                            Expr::KeySymbol("sidebar".to_string()),
+                            element("p", vec![Expr::string("No sidebar")]),
+                        ],
+                    )],
+                ),
+                element("main", vec![element("slot", vec![])]),
+            ],
+        ))
+    }
+
+    /// `(Layout (nav {:slot "sidebar"} (slot :menu)) (slot))`
+    fn page(_args: &[Expr], _context: &mut Context) -> Result<Expr, Error> {
+        Ok(element(
+            "Layout",
+            vec![
+                element(
+                    "nav",
+                    vec![
+                        slot_attribute("sidebar"),
+                        element("slot", vec![Expr::KeySymbol("menu".to_string())]),
+                    ],
+                ),
+                element("slot", vec![]),
+            ],
+        ))
+    }
+
+    fn to_html(expr: Expr) -> Result<String, Error> {
+        let mut context = Context::new();
+        context
+            .scope
+            .insert("Card", Expr::foreign_func_mut_context(&card));
+        context
+            .scope
+            .insert("Layout", Expr::foreign_func_mut_context(&layout));
+        context
+            .scope
+            .insert("Page", Expr::foreign_func_mut_context(&page));
+
+        let html = html_from_expr(&[expr], &mut context)?;
+        Ok(html.as_string().unwrap().to_string())
+    }
+
+    #[test]
+    fn is_component_tag_usage() {
+        assert!(is_component_tag("Card"));
+        assert!(is_component_tag("Layout.wide"));
+        assert!(!is_component_tag("div"));
+        assert!(!is_component_tag(".card"));
+        assert!(!is_component_tag("!DOCTYPE"));
+    }
+
+    #[test]
+    fn collect_slots_usage() {
+        let sidebar = Expr::List(vec![
+            Expr::symbol("nav"),
+            Expr::map(HashMap::from([(
+                "slot".to_string(),
+                Expr::string("sidebar"),
+            )])),
+            Expr::string("Menu"),
+        ]);
+        let children = vec![
+            Expr::List(vec![Expr::symbol("p"), Expr::string("One")]),
+            sidebar,
+            Expr::None,
+            Expr::Bool(false),
+            Expr::array(vec![Expr::List(vec![
+                Expr::symbol("p"),
+                Expr::string("Two"),
+            ])]),
+        ];
+
+        let mut slots = Slots::default();
+        collect_slots(&children, &mut slots).unwrap();
+
+        assert_eq!(slots.get(None).len(), 2);
+        assert_eq!(slots.get(Some("sidebar")).len(), 1);
+        assert!(slots.get(Some("footer")).is_empty());
+    }
+
+    #[test]
+    fn unpack_slot_terms_usage() {
+        let terms = [Expr::KeySymbol("footer".to_string()), Expr::string("©")];
+        let (name, fallback) = unpack_slot_terms(&terms);
+        assert_eq!(name, Some("footer"));
+        assert_eq!(fallback.len(), 1);
+
+        // Strings are fallback content, not names.
+        let terms = [Expr::string("No items")];
+        let (name, fallback) = unpack_slot_terms(&terms);
+        assert_eq!(name, None);
+        assert_eq!(fallback.len(), 1);
+
+        let (name, fallback) = unpack_slot_terms(&[]);
+        assert_eq!(name, None);
+        assert!(fallback.is_empty());
+    }
+
+    #[test]
+    fn render_component_usage() {
+        let attributes = Expr::map(HashMap::from([("title".to_string(), Expr::string("Hi"))]));
+        let expr = element(
+            "Card",
+            vec![attributes.clone(), element("p", vec![Expr::string("Body")])],
+        );
+        assert_eq!(
+            to_html(expr).unwrap(),
+            "<article><h2>Hi</h2><p>Body</p></article>"
+        );
+
+        // The shorthand is stripped from the component name.
+        let expr = element("Card.wide", vec![attributes, Expr::string("Body")]);
+        assert_eq!(
+            to_html(expr).unwrap(),
+            r#"<article class="wide"><h2>Hi</h2>Body</article>"#
+        );
+
+        assert!(to_html(element("Missing", vec![])).is_err());
+        assert!(to_html(element("Missing.wide", vec![])).is_err());
+    }
+
+    #[test]
+    fn render_layout_with_named_slots() {
+        let expr = element(
+            "Layout",
+            vec![
+                element("p", vec![Expr::string("Main")]),
+                element("nav", vec![slot_attribute("sidebar"), Expr::string("Menu")]),
+            ],
+        );
+        // The `slot` attribute is not rendered.
+        assert_eq!(
+            to_html(expr).unwrap(),
+            r#"<div class="layout"><aside><nav>Menu</nav></aside><main><p>Main</p></main></div>"#
+        );
+    }
+
+    #[test]
+    fn render_slot_fallback_content() {
+        let expr = element("Layout", vec![element("p", vec![Expr::string("Main")])]);
+        assert_eq!(
+            to_html(expr).unwrap(),
+            r#"<div class="layout"><aside><p>No sidebar</p></aside><main><p>Main</p></main></div>"#
+        );
+    }
+
+    #[test]
+    fn render_pass_through_slots() {
+        let expr = element(
+            "Page",
+            vec![
+                element("a", vec![slot_attribute("menu"), Expr::string("Home")]),
+                Expr::string("Text"),
+            ],
+        );
+        assert_eq!(
+            to_html(expr).unwrap(),
+            r#"<div class="layout"><aside><nav><a>Home</a></nav></aside><main>Text</main></div>"#
+        );
+    }
+
+    #[test]
+    fn render_undeclared_slot_is_rejected() {
+        let expr = element(
+            "Layout",
+            vec![element(
+                "p",
+                vec![slot_attribute("footer"), Expr::string("©")],
+            )],
+        );
+        assert!(to_html(expr).is_err());
+
+        // Card declares only the default slot.
+        let expr = element(
+            "Card",
+            vec![element(
+                "p",
+                vec![slot_attribute("sidebar"), Expr::string("Menu")],
+            )],
+        );
+        assert!(to_html(expr).is_err());
+    }
+}
@@ -0,0 +1,147 @@
+use tan::{
+    context::Context,
+    error::Error,
+    expr::Expr,
+    util::{args::unpack_stringable_arg, module_util::require_module},
+};
+
+// #insight
+// The contents of <script> and <style> are 'raw text', the browser does not
+// decode entities there, so entity-escaping would corrupt the code. Instead
+// only the sequences that could close the element early are neutralized.
+
+// #ref https://html.spec.whatwg.org/multipage/parsing.html#escapingString
+
+/// Escapes text content, e.g. the text children of an element.
+pub fn escape_text(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// Escapes a (double-quoted) attribute value.
+pub fn escape_attribute(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// Escapes the raw text content of a <script> or <style> element, i.e.
+/// `</script` becomes `<\/script` and `<!--` becomes `<\!--`.
+pub fn escape_raw_text(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+
+    let mut rest = s;
+    while let Some(i) = rest.find('<') {
+        escaped.push_str(&rest[..i]);
+        rest = &rest[i..];
+
+        // #insight Any `</` is escaped, this covers all cases, e.g. `</SCRIPT`.
+        let is_closing_tag = rest.len() >= 2 && rest.as_bytes()[1] == b'/';
+        let is_comment = rest.starts_with("<!--");
+
+        if is_closing_tag {
+            escaped.push_str("<\\/");
+            rest = &rest[2..];
+        } else if is_comment {
+            escaped.push_str("<\\!--");
+            rest = &rest[4..];
+        } else {
+            escaped.push('<');
+            rest = &rest[1..];
+        }
+    }
+    escaped.push_str(rest);
+
+    escaped
+}
+
+/// Escapes a String for use as HTML text content or attribute value.
+/// ```tan
+/// (html/escape "<b>Tom & Jerry</b>") ; => "&lt;b&gt;Tom &amp; Jerry&lt;/b&gt;"
+/// ```
+pub fn html_escape(args: &[Expr]) -> Result<Expr, Error> {
+    let s = unpack_stringable_arg(args, 0, "string")?;
+
+    Ok(Expr::string(escape_attribute(s)))
+}
+
+/// Marks a String as trusted markup, it is rendered without escaping.
+/// Equivalent to the quoted `(raw "...")` form.
+/// ```tan
+/// (html/html-from-expr '(div $(html/raw (cmark/to-html post))))
+/// ```
+pub fn html_raw(args: &[Expr]) -> Result<Expr, Error> {
+    let s = unpack_stringable_arg(args, 0, "markup")?;
+
+    Ok(Expr::List(vec![Expr::symbol("raw"), Expr::string(s)]))
+}
+
+pub fn setup_lib_html_escape(context: &mut Context) {
+    let module = require_module("html", context);
+
+    module.insert_invocable("escape", Expr::foreign_func(&html_escape));
+    module.insert_invocable("raw", Expr::foreign_func(&html_raw));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::html::escape::{escape_attribute, escape_raw_text, escape_text};
+
+    #[test]
+    fn escape_text_usage() {
+        assert_eq!(
+            escape_text("<script>alert('x')</script>"),
+            "&lt;script&gt;alert('x')&lt;/script&gt;"
+        );
+        assert_eq!(escape_text("Tom & Jerry"), "Tom &amp; Jerry");
+        // #insight Already escaped input is escaped again, not passed through.
+        assert_eq!(escape_text("&amp;"), "&amp;amp;");
+        assert_eq!(escape_text("καλημέρα \"κόσμε\""), "καλημέρα \"κόσμε\"");
+    }
+
+    #[test]
+    fn escape_attribute_usage() {
+        assert_eq!(
+            escape_attribute("\" onmouseover=\"alert(1)"),
+            "&quot; onmouseover=&quot;alert(1)"
+        );
+        assert_eq!(escape_attribute("' onload='x"), "&#39; onload=&#39;x");
+        assert_eq!(escape_attribute("a=1&b=2"), "a=1&amp;b=2");
+    }
+
+    #[test]
+    fn escape_raw_text_usage() {
+        assert_eq!(
+            escape_raw_text("let s = \"</script><script>alert(1)</script>\";"),
+            "let s = \"<\\/script><script>alert(1)<\\/script>\";"
+        );
+        assert_eq!(escape_raw_text("</STYLE>"), "<\\/STYLE>");
+        assert_eq!(escape_raw_text("<!-- x"), "<\\!-- x");
+        assert_eq!(
+            escape_raw_text("if (a < b && c > d) {}"),
+            "if (a < b && c > d) {}"
+        );
+        assert_eq!(escape_raw_text("a <"), "a <");
+    }
+}
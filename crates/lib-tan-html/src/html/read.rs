@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+
+use ego_tree::NodeRef;
+use scraper::{ElementRef, Html, Node};
+use tan::{
+    context::Context,
+    error::Error,
+    expr::Expr,
+    parser::util::STRING_INTERPOLATION_FUNC,
+    util::{
+        args::{unpack_map_arg, unpack_stringable_arg},
+        module_util::require_module,
+    },
+};
+
+use super::{INLINE_ELEMENTS, PREFORMATTED_ELEMENTS};
+
+// #insight
+// The parser is html5ever (via scraper), it follows the HTML5 parsing
+// algorithm, so malformed markup is repaired exactly like browsers do.
+
+// #insight
+// Whitespace-only text between block elements (the indentation of the source)
+// is dropped, between inline elements it is collapsed to a single space.
+
+// #todo Optionally keep comments, e.g. as `(!-- "...")`.
+// #todo Consider converting empty values of boolean attributes to true.
+
+fn is_inline_node(node: Option<NodeRef<Node>>) -> bool {
+    match node.as_ref().map(|node| node.value()) {
+        Some(Node::Element(element)) => INLINE_ELEMENTS.contains(&element.name()),
+        Some(Node::Text(text)) => !text.trim().is_empty(),
+        _ => false,
+    }
+}
+
+fn children_to_exprs(node: NodeRef<Node>, is_preformatted: bool) -> Vec<Expr> {
+    let mut exprs = Vec::new();
+
+    for child in node.children() {
+        match child.value() {
+            Node::Text(text) => {
+                if is_preformatted || !text.trim().is_empty() {
+                    exprs.push(Expr::string(&**text));
+                } else if is_inline_node(child.prev_sibling())
+                    && is_inline_node(child.next_sibling())
+                {
+                    exprs.push(Expr::string(" "));
+                }
+            }
+            Node::Element(_) => exprs.push(element_to_expr(child)),
+            Node::Doctype(doctype) => exprs.push(Expr::List(vec![
+                Expr::symbol("!DOCTYPE"),
+                Expr::symbol(doctype.name()),
+            ])),
+            _ => (),
+        }
+    }
+
+    exprs
+}
+
+// #insight
+// Tags that the renderer would interpret, i.e. `raw`, `slot`, the string
+// interpolation function, or names with the `.`/`#` shorthand markers, are
+// read as `(raw "...")` with the original markup, so they render unchanged.
+
+/// Returns true if the tag name cannot be used as the head of an element expression.
+fn is_reserved_tag(name: &str) -> bool {
+    matches!(name, "raw" | "slot" | STRING_INTERPOLATION_FUNC) || name.contains(['.', '#'])
+}
+
+fn element_to_expr(node: NodeRef<Node>) -> Expr {
+    let Node::Element(element) = node.value() else {
+        return Expr::None;
+    };
+
+    let name = element.name();
+
+    if is_reserved_tag(name) {
+        let html = ElementRef::wrap(node).map(|e| e.html()).unwrap_or_default();
+        return Expr::List(vec![Expr::symbol("raw"), Expr::string(html)]);
+    }
+
+    let mut terms = vec![Expr::symbol(name)];
+
+    let attributes: HashMap<String, Expr> = element
+        .attrs()
+        .map(|(name, value)| (name.to_string(), Expr::string(value)))
+        .collect();
+    if !attributes.is_empty() {
+        terms.push(Expr::map(attributes));
+    }
+
+    let is_preformatted = PREFORMATTED_ELEMENTS.contains(&name);
+    terms.extend(children_to_exprs(node, is_preformatted));
+
+    Expr::List(terms)
+}
+
+fn is_document(html: &str) -> bool {
+    let start = html.trim_start().as_bytes();
+    let starts_with = |prefix: &[u8]| {
+        start.len() >= prefix.len() && start[..prefix.len()].eq_ignore_ascii_case(prefix)
+    };
+    starts_with(b"<!doctype") || starts_with(b"<html")
+}
+
+/// Parses a document or fragment into an Array of expressions.
+fn read_html(html: &str, is_fragment: bool) -> Vec<Expr> {
+    if is_fragment {
+        let fragment = Html::parse_fragment(html);
+        // #insight html5ever wraps the fragment nodes in an <html> element.
+        let root = fragment.root_element();
+        let Some(root) = fragment.tree.get(root.id()) else {
+            return Vec::new();
+        };
+        children_to_exprs(root, false)
+    } else {
+        let document = Html::parse_document(html);
+        children_to_exprs(document.tree.root(), false)
+    }
+}
+
+/// Parses an HTML document or fragment into an Array of expressions, in the
+/// form rendered by `to-html`. Documents are detected by a leading DOCTYPE or
+/// <html> tag, use `{:fragment true}` or `{:fragment false}` to override.
+/// ```tan
+/// (html/read "<ul class=\"nav\"><li>Home</li></ul>") ; => ['(ul {:class "nav"} (li "Home"))]
+/// (html/read (fs/read-file-to-string "legacy/index.html"))
+/// ```
+pub fn html_read(args: &[Expr]) -> Result<Expr, Error> {
+    let html = unpack_stringable_arg(args, 0, "html")?;
+
+    let is_fragment = if let Ok(options) = unpack_map_arg(args, 1, "options") {
+        match options.get("fragment").map(|f| f.unpack()) {
+            Some(Expr::Bool(is_fragment)) => *is_fragment,
+            _ => !is_document(html),
+        }
+    } else {
+        !is_document(html)
+    };
+
+    Ok(Expr::array(read_html(html, is_fragment)))
+}
+
+pub fn setup_lib_html_read(context: &mut Context) {
+    let module = require_module("html", context);
+
+    module.insert_invocable("read", Expr::foreign_func(&html_read));
+}
+
+#[cfg(test)]
+mod tests {
+    use tan::{context::Context, expr::Expr};
+
+    use crate::html::{
+        html_from_expr,
+        read::{is_document, read_html},
+    };
+
+    #[test]
+    fn is_document_usage() {
+        assert!(is_document("<!DOCTYPE html><html></html>"));
+        assert!(is_document("\n  <HTML lang=\"en\">"));
+        assert!(!is_document("<ul><li>Home</li></ul>"));
+        assert!(!is_document("Hi"));
+    }
+
+    #[test]
+    fn read_html_usage() {
+        let exprs = read_html("<ul class=\"nav\">\n  <li>A &amp; B</li>\n</ul>", true);
+        assert_eq!(exprs.len(), 1);
+
+        let Expr::List(terms) = &exprs[0] else {
+            panic!("expected a List");
+        };
+        assert!(matches!(&terms[0], Expr::Symbol(s) if s == "ul"));
+        assert!(matches!(&terms[1], Expr::Map(_)));
+        // #insight The indentation whitespace is dropped.
+        assert_eq!(terms.len(), 3);
+
+        let Expr::List(li) = &terms[2] else {
+            panic!("expected a List");
+        };
+        assert!(matches!(&li[1], Expr::String(s) if s == "A & B"));
+
+        // Malformed markup is repaired.
+        let exprs = read_html("<p>One<p>Two <b>bold</b> <i>italic</i>", true);
+        assert_eq!(exprs.len(), 2);
+        let Expr::List(p) = &exprs[1] else {
+            panic!("expected a List");
+        };
+        assert_eq!(p.len(), 5);
+        assert!(matches!(&p[3], Expr::String(s) if s == " "));
+
+        let exprs = read_html("<!DOCTYPE html><title>Tan</title>", false);
+        assert_eq!(exprs.len(), 2);
+    }
+
+    #[test]
+    fn read_html_reserved_tags_roundtrip() {
+        let html = concat!(
+            r#"<raw>a &amp; b</raw><slot name="footer">Footer</slot>"#,
+            r#"<format>c</format><a.b>d</a.b><x#y>e</x#y><p>ok</p>"#
+        );
+        let exprs = read_html(html, true);
+        assert_eq!(exprs.len(), 6);
+
+        for expr in &exprs[..5] {
+            let Expr::List(terms) = expr else {
+                panic!("expected a List");
+            };
+            assert!(matches!(&terms[0], Expr::Symbol(s) if s == "raw"));
+        }
+
+        let mut context = Context::new();
+        let output = html_from_expr(&exprs, &mut context).unwrap();
+        assert_eq!(output.as_string(), Some(html));
+    }
+}
@@ -0,0 +1,907 @@
+use std::collections::HashMap;
+
+use tan::{
+    context::Context,
+    error::Error,
+    expr::{format_value, Expr},
+    parser::util::STRING_INTERPOLATION_FUNC,
+    util::{
+        args::{unpack_arg, unpack_stringable_arg},
+        module_util::require_module,
+        try_lock_read,
+    },
+};
+
+use super::parse_tag;
+
+// #insight
+// The selectors are matched directly against the expression tree, so they
+// work both for parsed documents (`html/read`) and for generated expressions,
+// including the `tag.class#id` shorthand.
+
+// #todo Support more pseudo-classes, e.g. :not(), :nth-of-type(), :empty.
+// #todo Support the An+B syntax for :nth-child().
+// #todo Consider caching parsed selectors.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Combinator {
+    /// `a b`
+    Descendant,
+    /// `a > b`
+    Child,
+    /// `a + b`
+    NextSibling,
+    /// `a ~ b`
+    SubsequentSibling,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum AttributeOp {
+    /// `[name]`
+    Exists,
+    /// `[name=value]`
+    Equals,
+    /// `[name~=value]`
+    Includes,
+    /// `[name|=value]`
+    DashMatch,
+    /// `[name^=value]`
+    Prefix,
+    /// `[name$=value]`
+    Suffix,
+    /// `[name*=value]`
+    Substring,
+}
+
+// #insight More pseudo-classes are coming, e.g. :empty.
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Clone, PartialEq)]
+enum PseudoClass {
+    FirstChild,
+    LastChild,
+    OnlyChild,
+    NthChild(usize),
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct CompoundSelector {
+    tag: Option<String>,
+    id: Option<String>,
+    classes: Vec<String>,
+    attributes: Vec<(String, AttributeOp, String)>,
+    pseudo_classes: Vec<PseudoClass>,
+}
+
+/// A sequence of compound selectors, `combinators[i]` joins `compounds[i]`
+/// and `compounds[i + 1]`.
+#[derive(Debug, PartialEq)]
+struct ComplexSelector {
+    compounds: Vec<CompoundSelector>,
+    combinators: Vec<Combinator>,
+}
+
+struct SelectorParser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl SelectorParser {
+    fn new(input: &str) -> Self {
+        Self {
+            chars: input.chars().collect(),
+            position: 0,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.position += 1;
+        c
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(format!("expected `{expected}`, found `{c}`")),
+            None => Err(format!("expected `{expected}`")),
+        }
+    }
+
+    /// Skips whitespace, returns true if any whitespace was skipped.
+    fn skip_whitespace(&mut self) -> bool {
+        let start = self.position;
+        while self.peek().is_some_and(|c| c.is_whitespace()) {
+            self.position += 1;
+        }
+        self.position > start
+    }
+
+    fn parse_ident(&mut self) -> Result<String, String> {
+        let mut ident = String::new();
+        while let Some(c) = self.peek() {
+            if c.is_alphanumeric() || c == '-' || c == '_' || !c.is_ascii() {
+                ident.push(c);
+                self.position += 1;
+            } else {
+                break;
+            }
+        }
+
+        if ident.is_empty() {
+            match self.peek() {
+                Some(c) => Err(format!("unexpected `{c}`")),
+                None => Err("unexpected end".to_string()),
+            }
+        } else {
+            Ok(ident)
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<String, String> {
+        let Some(quote) = self.peek().filter(|c| *c == '"' || *c == '\'') else {
+            return self.parse_ident();
+        };
+        self.position += 1;
+
+        let mut value = String::new();
+        loop {
+            match self.next() {
+                Some(c) if c == quote => return Ok(value),
+                Some(c) => value.push(c),
+                None => return Err("unterminated string".to_string()),
+            }
+        }
+    }
+
+    fn parse_attribute(&mut self) -> Result<(String, AttributeOp, String), String> {
+        self.expect('[')?;
+        self.skip_whitespace();
+        let name = self.parse_ident()?.to_lowercase();
+        self.skip_whitespace();
+
+        let op = match self.next() {
+            Some(']') => return Ok((name, AttributeOp::Exists, String::new())),
+            Some('=') => AttributeOp::Equals,
+            Some(c) => {
+                let op = match c {
+                    '~' => AttributeOp::Includes,
+                    '|' => AttributeOp::DashMatch,
+                    '^' => AttributeOp::Prefix,
+                    '$' => AttributeOp::Suffix,
+                    '*' => AttributeOp::Substring,
+                    _ => return Err(format!("unexpected `{c}` in attribute selector")),
+                };
+                self.expect('=')?;
+                op
+            }
+            None => return Err("unterminated attribute selector".to_string()),
+        };
+
+        self.skip_whitespace();
+        let value = self.parse_value()?;
+        self.skip_whitespace();
+        self.expect(']')?;
+
+        Ok((name, op, value))
+    }
+
+    fn parse_pseudo_class(&mut self) -> Result<PseudoClass, String> {
+        self.expect(':')?;
+        let name = self.parse_ident()?.to_lowercase();
+
+        match name.as_str() {
+            "first-child" => Ok(PseudoClass::FirstChild),
+            "last-child" => Ok(PseudoClass::LastChild),
+            "only-child" => Ok(PseudoClass::OnlyChild),
+            "nth-child" => {
+                self.expect('(')?;
+                self.skip_whitespace();
+                let n = self.parse_ident()?;
+                self.skip_whitespace();
+                self.expect(')')?;
+                match n.parse::<usize>() {
+                    Ok(n) if n > 0 => Ok(PseudoClass::NthChild(n)),
+                    _ => Err(format!("unsupported :nth-child argument `{n}`")),
+                }
+            }
+            _ => Err(format!("unsupported pseudo-class `:{name}`")),
+        }
+    }
+
+    fn parse_compound(&mut self) -> Result<CompoundSelector, String> {
+        let mut compound = CompoundSelector::default();
+
+        let mut is_empty = true;
+
+        if self.peek() == Some('*') {
+            self.position += 1;
+            is_empty = false;
+        } else if self
+            .peek()
+            .is_some_and(|c| c.is_alphabetic() || c == '-' || c == '_')
+        {
+            compound.tag = Some(self.parse_ident()?.to_lowercase());
+            is_empty = false;
+        }
+
+        loop {
+            match self.peek() {
+                Some('#') => {
+                    self.position += 1;
+                    compound.id = Some(self.parse_ident()?);
+                }
+                Some('.') => {
+                    self.position += 1;
+                    compound.classes.push(self.parse_ident()?);
+                }
+                Some('[') => compound.attributes.push(self.parse_attribute()?),
+                Some(':') => compound.pseudo_classes.push(self.parse_pseudo_class()?),
+                _ => break,
+            }
+            is_empty = false;
+        }
+
+        if is_empty {
+            match self.peek() {
+                Some(c) => Err(format!("unexpected `{c}`")),
+                None => Err("expected a selector".to_string()),
+            }
+        } else {
+            Ok(compound)
+        }
+    }
+
+    fn parse_complex(&mut self) -> Result<ComplexSelector, String> {
+        let mut compounds = vec![self.parse_compound()?];
+        let mut combinators = Vec::new();
+
+        loop {
+            let has_whitespace = self.skip_whitespace();
+
+            let combinator = match self.peek() {
+                None | Some(',') => break,
+                Some('>') => Combinator::Child,
+                Some('+') => Combinator::NextSibling,
+                Some('~') => Combinator::SubsequentSibling,
+                Some(_) if has_whitespace => Combinator::Descendant,
+                Some(c) => return Err(format!("unexpected `{c}`")),
+            };
+
+            if combinator != Combinator::Descendant {
+                self.position += 1;
+                self.skip_whitespace();
+            }
+
+            combinators.push(combinator);
+            compounds.push(self.parse_compound()?);
+        }
+
+        Ok(ComplexSelector {
+            compounds,
+            combinators,
+        })
+    }
+
+    fn parse(mut self) -> Result<Vec<ComplexSelector>, String> {
+        let mut selectors = Vec::new();
+
+        loop {
+            self.skip_whitespace();
+            selectors.push(self.parse_complex()?);
+            match self.next() {
+                Some(',') => continue,
+                None => break,
+                Some(c) => return Err(format!("unexpected `{c}`")),
+            }
+        }
+
+        Ok(selectors)
+    }
+}
+
+fn parse_selector(selector: &str) -> Result<Vec<ComplexSelector>, String> {
+    SelectorParser::new(selector).parse()
+}
+
+/// The matching-relevant data of an element.
+#[derive(Debug, Default)]
+struct ElementInfo {
+    tag: String,
+    id: Option<String>,
+    classes: Vec<String>,
+    attributes: HashMap<String, String>,
+}
+
+/// The position of an element in the tree: its element siblings (including
+/// itself) and the position of its parent.
+struct Position<'a> {
+    siblings: &'a [ElementInfo],
+    index: usize,
+    parent: Option<&'a Position<'a>>,
+}
+
+impl Position<'_> {
+    fn element(&self) -> &ElementInfo {
+        &self.siblings[self.index]
+    }
+}
+
+fn matches_compound(compound: &CompoundSelector, position: &Position) -> bool {
+    let element = position.element();
+
+    if let Some(tag) = &compound.tag {
+        if !element.tag.eq_ignore_ascii_case(tag) {
+            return false;
+        }
+    }
+
+    if compound.id.is_some() && compound.id != element.id {
+        return false;
+    }
+
+    if !compound.classes.iter().all(|c| element.classes.contains(c)) {
+        return false;
+    }
+
+    for (name, op, value) in &compound.attributes {
+        let Some(attribute) = element.attributes.get(name) else {
+            return false;
+        };
+        let is_match = match op {
+            AttributeOp::Exists => true,
+            AttributeOp::Equals => attribute == value,
+            AttributeOp::Includes => attribute.split_whitespace().any(|v| v == value),
+            AttributeOp::DashMatch => {
+                attribute == value || attribute.starts_with(&format!("{value}-"))
+            }
+            AttributeOp::Prefix => !value.is_empty() && attribute.starts_with(value),
+            AttributeOp::Suffix => !value.is_empty() && attribute.ends_with(value),
+            AttributeOp::Substring => !value.is_empty() && attribute.contains(value),
+        };
+        if !is_match {
+            return false;
+        }
+    }
+
+    let count = position.siblings.len();
+    compound.pseudo_classes.iter().all(|pseudo| match pseudo {
+        PseudoClass::FirstChild => position.index == 0,
+        PseudoClass::LastChild => position.index == count - 1,
+        PseudoClass::OnlyChild => count == 1,
+        PseudoClass::NthChild(n) => position.index + 1 == *n,
+    })
+}
+
+/// Matches the compound selectors up to (including) `k`, right-to-left.
+fn matches_complex_at(selector: &ComplexSelector, k: usize, position: &Position) -> bool {
+    if !matches_compound(&selector.compounds[k], position) {
+        return false;
+    }
+
+    if k == 0 {
+        return true;
+    }
+
+    match selector.combinators[k - 1] {
+        Combinator::Child => position
+            .parent
+            .is_some_and(|parent| matches_complex_at(selector, k - 1, parent)),
+        Combinator::Descendant => {
+            let mut ancestor = position.parent;
+            while let Some(position) = ancestor {
+                if matches_complex_at(selector, k - 1, position) {
+                    return true;
+                }
+                ancestor = position.parent;
+            }
+            false
+        }
+        Combinator::NextSibling => {
+            position.index > 0
+                && matches_complex_at(
+                    selector,
+                    k - 1,
+                    &Position {
+                        siblings: position.siblings,
+                        index: position.index - 1,
+                        parent: position.parent,
+                    },
+                )
+        }
+        Combinator::SubsequentSibling => (0..position.index).any(|index| {
+            matches_complex_at(
+                selector,
+                k - 1,
+                &Position {
+                    siblings: position.siblings,
+                    index,
+                    parent: position.parent,
+                },
+            )
+        }),
+    }
+}
+
+fn matches_selector(selectors: &[ComplexSelector], position: &Position) -> bool {
+    selectors
+        .iter()
+        .any(|selector| matches_complex_at(selector, selector.compounds.len() - 1, position))
+}
+
+/// Returns the terms of an element expression, None for text and special
+/// forms, e.g. `(raw ...)` or `(!DOCTYPE html)`.
+fn element_terms(expr: &Expr) -> Option<(&str, &[Expr])> {
+    let Expr::List(terms) = expr.unpack() else {
+        return None;
+    };
+
+    let sym = terms.first()?.as_symbol()?;
+
+    if sym == "raw" || sym == "!DOCTYPE" || sym == STRING_INTERPOLATION_FUNC {
+        return None;
+    }
+
+    Some((sym, &terms[1..]))
+}
+
+/// Returns the children of an element, skipping the attributes.
+fn element_children(terms: &[Expr]) -> &[Expr] {
+    match terms.first().map(|term| term.unpack()) {
+        Some(Expr::Map(_)) => &terms[1..],
+        _ => terms,
+    }
+}
+
+fn element_info(sym: &str, terms: &[Expr]) -> ElementInfo {
+    let (tag, id, classes) = parse_tag(sym);
+
+    let mut info = ElementInfo {
+        tag: tag.to_string(),
+        id: id.map(|id| id.to_string()),
+        classes: classes.iter().map(|c| c.to_string()).collect(),
+        attributes: HashMap::new(),
+    };
+
+    if let Some(attributes) = terms.first().and_then(|term| term.as_map()) {
+        for (name, value) in attributes.iter() {
+            let value = match value.unpack() {
+                Expr::Bool(true) => String::new(),
+                Expr::Bool(false) | Expr::None => continue,
+                _ => format_value(value),
+            };
+            match name.as_str() {
+                "id" => info.id = Some(value.clone()),
+                "class" => info
+                    .classes
+                    .extend(value.split_whitespace().map(|c| c.to_string())),
+                _ => (),
+            }
+            info.attributes.insert(name.to_lowercase(), value);
+        }
+    }
+
+    if let Some(id) = &info.id {
+        info.attributes.insert("id".to_string(), id.clone());
+    }
+    if !info.classes.is_empty() {
+        info.attributes
+            .insert("class".to_string(), info.classes.join(" "));
+    }
+
+    info
+}
+
+/// Calls `f` for every child, spreading Arrays.
+fn for_each_child(
+    children: &[Expr],
+    f: &mut dyn FnMut(&Expr) -> Result<(), Error>,
+) -> Result<(), Error> {
+    for child in children {
+        if let Expr::Array(array) = child.unpack() {
+            let array = try_lock_read(array, None)?;
+            for_each_child(&array, f)?;
+        } else {
+            f(child)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn select_in(
+    children: &[Expr],
+    parent: Option<&Position>,
+    selectors: &[ComplexSelector],
+    is_first_only: bool,
+    matches: &mut Vec<Expr>,
+) -> Result<(), Error> {
+    let mut siblings = Vec::new();
+    for_each_child(children, &mut |child| {
+        if let Some((sym, terms)) = element_terms(child) {
+            siblings.push(element_info(sym, terms));
+        }
+        Ok(())
+    })?;
+
+    let mut index = 0;
+    for_each_child(children, &mut |child| {
+        if is_first_only && !matches.is_empty() {
+            return Ok(());
+        }
+
+        let Some((_, terms)) = element_terms(child) else {
+            return Ok(());
+        };
+
+        let position = Position {
+            siblings: &siblings,
+            index,
+            parent,
+        };
+        index += 1;
+
+        if matches_selector(selectors, &position) {
+            matches.push(child.clone());
+        }
+
+        select_in(
+            element_children(terms),
+            Some(&position),
+            selectors,
+            is_first_only,
+            matches,
+        )
+    })
+}
+
+fn unpack_selector_arg(args: &[Expr], index: usize) -> Result<Vec<ComplexSelector>, Error> {
+    let selector = unpack_stringable_arg(args, index, "selector")?;
+
+    parse_selector(selector).map_err(|reason| {
+        Error::invalid_arguments(
+            &format!("invalid selector `{selector}`: {reason}"),
+            args[index].range(),
+        )
+    })
+}
+
+/// Returns all the elements that match a CSS selector, in document order.
+/// ```tan
+/// (html/select doc "article > h2.title")
+/// (html/select doc "nav a[href^=\"https\"], footer a")
+/// ```
+pub fn html_select(args: &[Expr]) -> Result<Expr, Error> {
+    let doc = unpack_arg(args, 0, "doc")?;
+    let selectors = unpack_selector_arg(args, 1)?;
+
+    let mut matches = Vec::new();
+    select_in(
+        std::slice::from_ref(doc),
+        None,
+        &selectors,
+        false,
+        &mut matches,
+    )?;
+
+    Ok(Expr::array(matches))
+}
+
+/// Returns the first element that matches a CSS selector, None if there is
+/// no match.
+/// ```tan
+/// (html/select-one doc "head > title")
+/// ```
+pub fn html_select_one(args: &[Expr]) -> Result<Expr, Error> {
+    let doc = unpack_arg(args, 0, "doc")?;
+    let selectors = unpack_selector_arg(args, 1)?;
+
+    let mut matches = Vec::new();
+    select_in(
+        std::slice::from_ref(doc),
+        None,
+        &selectors,
+        true,
+        &mut matches,
+    )?;
+
+    Ok(matches.into_iter().next().unwrap_or(Expr::None))
+}
+
+fn collect_text(expr: &Expr, text: &mut String) -> Result<(), Error> {
+    match expr.unpack() {
+        Expr::List(terms) => {
+            let Some(sym) = terms.first().and_then(|op| op.as_symbol()) else {
+                return Ok(());
+            };
+            if sym == STRING_INTERPOLATION_FUNC {
+                for term in &terms[1..] {
+                    text.push_str(&format_value(term));
+                }
+                return Ok(());
+            }
+            let Some((sym, terms)) = element_terms(expr) else {
+                return Ok(());
+            };
+            let (tag, _, _) = parse_tag(sym);
+            if tag == "script" || tag == "style" {
+                return Ok(());
+            }
+            for_each_child(element_children(terms), &mut |child| {
+                collect_text(child, text)
+            })?;
+        }
+        Expr::Array(array) => {
+            let array = try_lock_read(array, None)?;
+            for expr in array.iter() {
+                collect_text(expr, text)?;
+            }
+        }
+        Expr::None | Expr::Never | Expr::Map(_) => (),
+        _ => text.push_str(&format_value(expr)),
+    }
+
+    Ok(())
+}
+
+/// Returns the text content of an element (or Array of elements), the
+/// contents of <script> and <style> are skipped.
+/// ```tan
+/// (html/get-text (html/select-one doc "h1")) ; => "Hello world"
+/// ```
+pub fn html_get_text(args: &[Expr]) -> Result<Expr, Error> {
+    let expr = unpack_arg(args, 0, "element")?;
+
+    let mut text = String::new();
+    collect_text(expr, &mut text)?;
+
+    Ok(Expr::string(text))
+}
+
+/// Returns the value of an attribute of an element, None if missing. The
+/// `id` and `class` attributes include the tag shorthand.
+/// ```tan
+/// (html/get-attribute (html/select-one doc "a.logo") "href")
+/// ```
+pub fn html_get_attribute(args: &[Expr]) -> Result<Expr, Error> {
+    let expr = unpack_arg(args, 0, "element")?;
+    let name = unpack_stringable_arg(args, 1, "name")?;
+
+    let Some((sym, terms)) = element_terms(expr) else {
+        return Err(Error::invalid_arguments(
+            "`element` argument should be an element expression",
+            expr.range(),
+        ));
+    };
+
+    if name == "id" || name == "class" {
+        let info = element_info(sym, terms);
+        return Ok(info
+            .attributes
+            .get(name)
+            .map(Expr::string)
+            .unwrap_or(Expr::None));
+    }
+
+    let value = terms
+        .first()
+        .and_then(|term| term.as_map())
+        .and_then(|attributes| attributes.get(name).cloned());
+
+    Ok(value.unwrap_or(Expr::None))
+}
+
+pub fn setup_lib_html_select(context: &mut Context) {
+    let module = require_module("html", context);
+
+    module.insert_invocable("select", Expr::foreign_func(&html_select));
+    module.insert_invocable("select-one", Expr::foreign_func(&html_select_one));
+    module.insert_invocable("get-text", Expr::foreign_func(&html_get_text));
+    module.insert_invocable("get-attribute", Expr::foreign_func(&html_get_attribute));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use tan::expr::Expr;
+
+    use crate::html::{
+        read::html_read,
+        select::{
+            html_get_attribute, html_get_text, html_select, html_select_one, matches_selector,
+            parse_selector, AttributeOp, Combinator, ElementInfo, Position, PseudoClass,
+        },
+    };
+
+    const DOC: &str = r#"<!DOCTYPE html>
+<html>
+<head><title>Tan</title></head>
+<body>
+  <ul class="nav main" id="x">
+    <li><a href="/">Home</a></li>
+    <li><a href="https://tan.dev" class="external">Tan <b>lang</b></a></li>
+  </ul>
+  <article>
+    <h2 class="title">First</h2>
+    <p>One <span class="note">inner</span></p>
+  </article>
+  <p class="note">outer</p>
+  <script>var x = "not text";</script>
+</body>
+</html>"#;
+
+    fn read_doc() -> Expr {
+        html_read(&[Expr::string(DOC)]).unwrap()
+    }
+
+    fn select(doc: &Expr, selector: &str) -> Vec<Expr> {
+        let matches = html_select(&[doc.clone(), Expr::string(selector)]).unwrap();
+        let matches = matches.as_array().unwrap();
+        matches.clone()
+    }
+
+    fn get_text(expr: &Expr) -> String {
+        let text = html_get_text(std::slice::from_ref(expr)).unwrap();
+        text.as_string().unwrap().to_string()
+    }
+
+    fn element(tag: &str, classes: &[&str], attributes: &[(&str, &str)]) -> ElementInfo {
+        ElementInfo {
+            tag: tag.to_string(),
+            id: None,
+            classes: classes.iter().map(|c| c.to_string()).collect(),
+            attributes: attributes
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<HashMap<_, _>>(),
+        }
+    }
+
+    #[test]
+    fn parse_selector_usage() {
+        let selectors = parse_selector("article > h2.title, nav a[href^='https']").unwrap();
+        assert_eq!(selectors.len(), 2);
+
+        let selector = &selectors[0];
+        assert_eq!(selector.combinators, vec![Combinator::Child]);
+        assert_eq!(selector.compounds[1].tag.as_deref(), Some("h2"));
+        assert_eq!(selector.compounds[1].classes, vec!["title"]);
+
+        let selector = &selectors[1];
+        assert_eq!(selector.combinators, vec![Combinator::Descendant]);
+        assert_eq!(
+            selector.compounds[1].attributes,
+            vec![("href".to_string(), AttributeOp::Prefix, "https".to_string())]
+        );
+
+        let selectors = parse_selector("li:nth-child(2) ~ *#x").unwrap();
+        assert_eq!(
+            selectors[0].compounds[0].pseudo_classes,
+            vec![PseudoClass::NthChild(2)]
+        );
+        assert_eq!(selectors[0].compounds[1].id.as_deref(), Some("x"));
+
+        assert!(parse_selector("").is_err());
+        assert!(parse_selector("a >").is_err());
+        assert!(parse_selector("a:hover").is_err());
+        assert!(parse_selector("[href").is_err());
+    }
+
+    #[test]
+    fn matches_selector_usage() {
+        // article > (h2.title, p, p.note[data-x=1])
+        let roots = [element("article", &[], &[])];
+        let article = Position {
+            siblings: &roots,
+            index: 0,
+            parent: None,
+        };
+        let children = [
+            element("h2", &["title"], &[]),
+            element("p", &[], &[]),
+            element("p", &["note"], &[("data-x", "1")]),
+        ];
+        let position = |index| Position {
+            siblings: &children,
+            index,
+            parent: Some(&article),
+        };
+
+        let matches = |selector: &str, index| {
+            matches_selector(&parse_selector(selector).unwrap(), &position(index))
+        };
+
+        assert!(matches("article > h2.title", 0));
+        assert!(matches("article h2", 0));
+        assert!(!matches("h2.other", 0));
+        assert!(matches("h2 + p", 1));
+        assert!(!matches("h2 + p", 2));
+        assert!(matches("h2 ~ p", 2));
+        assert!(matches("p:last-child", 2));
+        assert!(matches("p:nth-child(2)", 1));
+        assert!(matches("[data-x=\"1\"]", 2));
+        assert!(!matches("[data-x]", 1));
+        assert!(matches("div, p.note", 2));
+        assert!(!matches("section p", 2));
+    }
+
+    #[test]
+    fn html_select_usage() {
+        let doc = read_doc();
+
+        let links = select(&doc, "ul.nav#x > li a");
+        assert_eq!(links.len(), 2);
+        assert_eq!(get_text(&links[0]), "Home");
+        assert_eq!(get_text(&links[1]), "Tan lang");
+
+        assert_eq!(select(&doc, "a[href^=https].external").len(), 1);
+        assert_eq!(select(&doc, "li:first-child a").len(), 1);
+        assert_eq!(select(&doc, "h2, .note").len(), 3);
+        assert!(select(&doc, "ul.other").is_empty());
+
+        assert!(html_select(&[doc, Expr::string("a >")]).is_err());
+    }
+
+    #[test]
+    fn html_select_one_returns_the_first_match_in_document_order() {
+        let doc = read_doc();
+
+        // The nested span precedes the later top-level paragraph.
+        let note = html_select_one(&[doc.clone(), Expr::string("p.note, span.note")]).unwrap();
+        assert_eq!(get_text(&note), "inner");
+
+        let title = html_select_one(&[doc.clone(), Expr::string("head > title")]).unwrap();
+        assert_eq!(get_text(&title), "Tan");
+
+        let missing = html_select_one(&[doc, Expr::string("table")]).unwrap();
+        assert!(matches!(missing, Expr::None));
+    }
+
+    #[test]
+    fn html_get_text_and_attribute_usage() {
+        let doc = read_doc();
+
+        let body = html_select_one(&[doc.clone(), Expr::string("body")]).unwrap();
+        let text = get_text(&body);
+        assert!(text.contains("One inner"));
+        // The contents of <script> are skipped.
+        assert!(!text.contains("not text"));
+
+        let link = html_select_one(&[doc.clone(), Expr::string("a.external")]).unwrap();
+        let href = html_get_attribute(&[link.clone(), Expr::string("href")]).unwrap();
+        assert_eq!(href.as_string(), Some("https://tan.dev"));
+        let title = html_get_attribute(&[link, Expr::string("title")]).unwrap();
+        assert!(matches!(title, Expr::None));
+
+        assert!(html_get_attribute(&[Expr::string("Hi"), Expr::string("id")]).is_err());
+    }
+
+    #[test]
+    fn html_select_shorthand_and_arrays() {
+        // (ul.nav#x [(li.active "A") (li "B")] (li "C"))
+        let items = Expr::array(vec![
+            Expr::List(vec![Expr::symbol("li.active"), Expr::string("A")]),
+            Expr::List(vec![Expr::symbol("li"), Expr::string("B")]),
+        ]);
+        let ul = Expr::List(vec![
+            Expr::symbol("ul.nav#x"),
+            Expr::map(HashMap::from([("class".to_string(), Expr::string("main"))])),
+            items,
+            Expr::List(vec![Expr::symbol("li"), Expr::string("C")]),
+        ]);
+
+        assert_eq!(select(&ul, "ul.nav.main#x").len(), 1);
+        assert_eq!(select(&ul, "#x > li").len(), 3);
+        // The spread Array items are siblings of the other children.
+        assert_eq!(select(&ul, "li.active + li").len(), 1);
+        let last = select(&ul, "li:last-child");
+        assert_eq!(get_text(&last[0]), "C");
+
+        let class = html_get_attribute(&[ul, Expr::string("class")]).unwrap();
+        assert_eq!(class.as_string(), Some("nav main"));
+    }
+}
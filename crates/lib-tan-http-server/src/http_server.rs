@@ -22,6 +22,8 @@ static DEFAULT_ADDRESS: &str = "127.0.0.1";
 static DEFAULT_PORT: i64 = 8000;
 // #todo consider using "./static" as the default.
 static DEFAULT_STATIC_FILES_DIR: &str = "./public";
+// (content-encoding, file extension), in order of preference.
+static PRECOMPRESSED_ENCODINGS: [(&str, &str); 3] = [("br", "br"), ("zstd", "zst"), ("gzip", "gz")];
 
 // #see https://docs.rs/axum/latest/axum/response/index.html
 
@@ -151,11 +153,47 @@ fn axum_response_from_tan_response(tan_resp: Expr) -> HandlerResponse {
         return internal_server_error_response("missing body");
     };
 
-    let Some(body) = body.as_stringable_consuming() else {
+    let Some(body) = body_bytes(body) else {
         return internal_server_error_response("invalid body");
     };
 
-    (status_code, header_map, body.into_bytes())
+    (status_code, header_map, body)
+}
+
+// #insight
+// Buffer bodies are sent as is, e.g. HTML rendered with `html/to-buffer` or
+// binary data.
+/// Returns the bytes of a String or Buffer response body.
+fn body_bytes(body: Expr) -> Option<Vec<u8>> {
+    if let Some((length, bytes)) = body.as_buffer() {
+        return Some(bytes[..length].to_vec());
+    }
+
+    body.as_stringable_consuming().map(String::into_bytes)
+}
+
+// #ref https://www.rfc-editor.org/rfc/rfc9110#name-accept-encoding
+/// Checks if an `Accept-Encoding` header value accepts an encoding, entries
+/// with `q=0` reject the encoding.
+fn accepts_encoding(accept_encoding: &str, encoding: &str) -> bool {
+    let mut wildcard = None;
+
+    for entry in accept_encoding.split(',') {
+        let mut params = entry.split(';');
+        let name = params.next().unwrap_or_default().trim();
+        let quality = params
+            .filter_map(|param| param.trim().strip_prefix("q="))
+            .find_map(|q| q.trim().parse::<f32>().ok())
+            .unwrap_or(1.0);
+
+        if name.eq_ignore_ascii_case(encoding) {
+            return quality > 0.0;
+        } else if name == "*" {
+            wildcard = Some(quality > 0.0);
+        }
+    }
+
+    wildcard.unwrap_or(false)
 }
 
 async fn run_server(options: HashMap<String, Expr>, handler: Expr, context: &mut Context) {
@@ -194,6 +232,29 @@ async fn run_server(options: HashMap<String, Expr>, handler: Expr, context: &mut
 
                 // #todo _really_ nasty code, use tower's ServeFile instead.
                 let path = format!("{static_files_dir}{path}");
+
+                // #insight Serve precompressed variants, e.g. generated with `codec/compress`.
+                let accept_encoding = axum_req
+                    .headers()
+                    .get(header::ACCEPT_ENCODING)
+                    .and_then(|value| value.to_str().ok())
+                    .unwrap_or_default();
+                for (encoding, extension) in PRECOMPRESSED_ENCODINGS {
+                    if !accepts_encoding(accept_encoding, encoding) {
+                        continue;
+                    }
+                    let encoded_path = format!("{path}.{extension}");
+                    if let Ok(file_contents) = tokio::fs::read(&encoded_path).await {
+                        let mime_type = from_path(&path).first_or_octet_stream();
+                        let mut header_map = HeaderMap::new();
+                        header_map
+                            .insert(header::CONTENT_TYPE, mime_type.to_string().parse().unwrap());
+                        header_map.insert(header::CONTENT_ENCODING, encoding.parse().unwrap());
+                        header_map.insert(header::VARY, "accept-encoding".parse().unwrap());
+                        return (StatusCode::OK, header_map, file_contents);
+                    }
+                }
+
                 if let Ok(file_contents) = tokio::fs::read(&path).await {
                     let mime_type = from_path(&path).first_or_octet_stream(); // Guess MIME type
                     let mut header_map = HeaderMap::new();
@@ -344,3 +405,57 @@ pub fn import_lib_http_server(context: &mut Context) {
         Expr::foreign_func(&read_form_urlencoded),
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{Arc, RwLock},
+    };
+
+    use axum::http::StatusCode;
+    use tan::expr::Expr;
+
+    use crate::http_server::{accepts_encoding, axum_response_from_tan_response};
+
+    #[test]
+    fn accepts_encoding_usage() {
+        assert!(accepts_encoding("gzip, deflate, br", "br"));
+        assert!(accepts_encoding("gzip, BR;q=0.5", "br"));
+        assert!(!accepts_encoding("gzip, br;q=0", "br"));
+        assert!(!accepts_encoding("gzip, br; q=0.0", "br"));
+        assert!(!accepts_encoding("gzip", "br"));
+        assert!(!accepts_encoding("", "gzip"));
+        assert!(accepts_encoding("*", "zstd"));
+        assert!(!accepts_encoding("*, zstd;q=0", "zstd"));
+        assert!(!accepts_encoding("*;q=0", "zstd"));
+        // #insight Substrings do not match.
+        assert!(!accepts_encoding("x-gzip-br", "br"));
+    }
+
+    #[test]
+    fn axum_response_from_tan_response_usage() {
+        let buffer = Expr::Buffer(2, Arc::new(RwLock::new(b"hi!".to_vec())));
+        let tan_resp = Expr::array(vec![Expr::Int(200), Expr::map(HashMap::new()), buffer]);
+        let (status_code, _, body) = axum_response_from_tan_response(tan_resp);
+        assert_eq!(status_code, StatusCode::OK);
+        assert_eq!(body, b"hi");
+
+        let tan_resp = Expr::array(vec![
+            Expr::Int(404),
+            Expr::map(HashMap::new()),
+            Expr::string("not found"),
+        ]);
+        let (status_code, _, body) = axum_response_from_tan_response(tan_resp);
+        assert_eq!(status_code, StatusCode::NOT_FOUND);
+        assert_eq!(body, b"not found");
+
+        let tan_resp = Expr::array(vec![
+            Expr::Int(200),
+            Expr::map(HashMap::new()),
+            Expr::Int(1),
+        ]);
+        let (status_code, _, _) = axum_response_from_tan_response(tan_resp);
+        assert_eq!(status_code, StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
@@ -9,8 +9,6 @@
 
 // #todo random_int, random_float
 // #todo should take a range trait.
-// #todo support random-number-generator 'object', with more specialization
-// #todo fast, crypto-secure, very-random, per-thread, etc versions.
 // #todo but also a couple of helper functions.
 // #todo better module name: stochastic, rnd, rng? `rng` is interesting, nah 'rng' is not great.
 
@@ -26,38 +24,275 @@
 // (let rng (RNG))
 // (let n (gen-float rng 5.0))
 
-// #todo add support for seeding.
-
 // #todo Extract as standalone library?
 
-use rand::Rng;
+// #insight
+// All functions accept an optional RNG object as the first argument, when it
+// is omitted the per-thread global generator is used:
+//
+// (let rng (rng/RNG {:seed 42}))
+// (rng/int rng 1 7) ; reproducible
+// (rng/int 1 7) ; global
+
+use std::sync::{Arc, RwLock};
+
+use self::{distributions::setup_lib_rng_distributions, sampling::setup_lib_rng_sampling};
+
+use rand::{rngs::OsRng, Rng, RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use rand_pcg::Pcg64;
 
 use tan::{
     context::Context,
     error::Error,
-    expr::Expr,
-    util::{args::unpack_float_arg, module_util::require_module},
+    expr::{annotate_type, Expr},
+    util::{
+        args::{unpack_arg, unpack_float_arg, unpack_int_arg, unpack_map_arg},
+        expect_lock_write,
+        module_util::require_module,
+    },
 };
 
-/// (random 100) returns a random integer in the range 0..100
-pub fn random_int(args: &[Expr]) -> Result<Expr, Error> {
-    if let Some(end) = args.first() {
-        let Some(end) = end.as_int() else {
+pub mod distributions;
+pub mod sampling;
+
+// #todo Consider xoshiro for an even faster generator.
+
+/// A random number generator object.
+pub struct Generator {
+    rng: Box<dyn RngCore + Send + Sync>,
+}
+
+/// Calls `f` with the RNG object given as first argument, or the global
+/// generator, and the remaining arguments.
+pub fn with_rng<T>(
+    args: &[Expr],
+    f: impl FnOnce(&mut dyn RngCore, &[Expr]) -> Result<T, Error>,
+) -> Result<T, Error> {
+    if let Some(Expr::ForeignMut(generator)) = args.first().map(|arg| arg.unpack()) {
+        let mut generator = expect_lock_write(generator);
+        if let Some(generator) = generator.downcast_mut::<Generator>() {
+            return f(generator.rng.as_mut(), &args[1..]);
+        }
+    }
+
+    f(&mut rand::thread_rng(), args)
+}
+
+// #insight
+// Algorithms:
+// - pcg: fast, reproducible, the default.
+// - chacha: ChaCha20, crypto-secure, reproducible when seeded.
+// - os: the operating system generator, crypto-secure, cannot be seeded.
+/// Creates a random number generator object.
+/// ```tan
+/// (let rng (rng/RNG {:seed 42 :algorithm :pcg}))
+/// (let secure-rng (rng/RNG {:algorithm :chacha}))
+/// ```
+pub fn rng_new(args: &[Expr]) -> Result<Expr, Error> {
+    let (seed, algorithm) = if let Ok(options) = unpack_map_arg(args, 0, "options") {
+        let seed = match options.get("seed") {
+            Some(seed) => {
+                let Some(seed) = seed.as_int() else {
+                    return Err(Error::invalid_arguments(
+                        "`seed` option should be an Int",
+                        seed.range(),
+                    ));
+                };
+                // #insight Negative seeds are reinterpreted, not rejected.
+                Some(seed as u64)
+            }
+            None => None,
+        };
+        let algorithm = options
+            .get("algorithm")
+            .and_then(|a| a.as_stringable())
+            .unwrap_or("pcg")
+            .to_string();
+        (seed, algorithm)
+    } else {
+        (None, "pcg".to_string())
+    };
+
+    let rng: Box<dyn RngCore + Send + Sync> = match (algorithm.as_str(), seed) {
+        ("pcg", Some(seed)) => Box::new(Pcg64::seed_from_u64(seed)),
+        ("pcg", None) => Box::new(Pcg64::from_entropy()),
+        ("chacha", Some(seed)) => Box::new(ChaCha20Rng::seed_from_u64(seed)),
+        ("chacha", None) => Box::new(ChaCha20Rng::from_entropy()),
+        ("os", None) => Box::new(OsRng),
+        ("os", Some(_)) => {
             return Err(Error::invalid_arguments(
-                "expected Int argument",
-                end.range(),
+                "the `os` algorithm cannot be seeded",
+                args[0].range(),
             ));
-        };
+        }
+        _ => {
+            return Err(Error::invalid_arguments(
+                &format!("unsupported algorithm `{algorithm}`, expected :pcg, :chacha or :os"),
+                args[0].range(),
+            ));
+        }
+    };
 
-        let mut rng = rand::thread_rng();
+    let expr = Expr::ForeignMut(Arc::new(RwLock::new(Generator { rng })));
 
-        Ok(Expr::Int(rng.gen_range(0..end)))
-    } else {
-        Err(Error::invalid_arguments(
-            "expected at least one argument",
-            None,
-        ))
+    Ok(annotate_type(expr, "RNG"))
+}
+
+// #insight `gen_range` panics on empty ranges.
+fn check_int_range(start: i64, end: i64, arg: &Expr) -> Result<(), Error> {
+    if start >= end {
+        return Err(Error::invalid_arguments(
+            &format!("empty range {start}..{end}"),
+            arg.range(),
+        ));
     }
+    Ok(())
+}
+
+// #insight `gen_range` also panics on infinite or NaN bounds.
+fn check_float_range(start: f64, end: f64, arg: &Expr) -> Result<(), Error> {
+    if start >= end || !start.is_finite() || !end.is_finite() {
+        return Err(Error::invalid_arguments(
+            &format!("empty range {start}..{end}"),
+            arg.range(),
+        ));
+    }
+    Ok(())
+}
+
+/// Returns a random Int in the range start..end, start defaults to 0.
+/// ```tan
+/// (rng/int rng 6) ; 0..6
+/// (rng/int rng 1 7) ; 1..7
+/// ```
+pub fn rng_int(args: &[Expr]) -> Result<Expr, Error> {
+    with_rng(args, |rng, args| {
+        let (start, end) = if args.len() > 1 {
+            (
+                unpack_int_arg(args, 0, "start")?,
+                unpack_int_arg(args, 1, "end")?,
+            )
+        } else {
+            (0, unpack_int_arg(args, 0, "end")?)
+        };
+
+        check_int_range(start, end, &args[0])?;
+
+        Ok(Expr::Int(rng.gen_range(start..end)))
+    })
+}
+
+/// Returns a random Float in the range start..end, defaults to 0.0..1.0.
+/// ```tan
+/// (rng/float rng)
+/// (rng/float rng 100.0)
+/// (rng/float rng -1.0 1.0)
+/// ```
+pub fn rng_float(args: &[Expr]) -> Result<Expr, Error> {
+    with_rng(args, |rng, args| {
+        let (start, end) = match args.len() {
+            0 => return Ok(Expr::Float(rng.gen())),
+            1 => (0.0, unpack_float_arg(args, 0, "end")?),
+            _ => (
+                unpack_float_arg(args, 0, "start")?,
+                unpack_float_arg(args, 1, "end")?,
+            ),
+        };
+
+        check_float_range(start, end, &args[0])?;
+
+        Ok(Expr::Float(rng.gen_range(start..end)))
+    })
+}
+
+/// Returns true with the given probability, defaults to 0.5.
+/// ```tan
+/// (rng/bool rng 0.1) ; true 10% of the time
+/// ```
+pub fn rng_bool(args: &[Expr]) -> Result<Expr, Error> {
+    with_rng(args, |rng, args| {
+        let probability = if args.is_empty() {
+            0.5
+        } else {
+            unpack_float_arg(args, 0, "probability")?
+        };
+
+        if !(0.0..=1.0).contains(&probability) {
+            return Err(Error::invalid_arguments(
+                &format!("probability=`{probability}` should be in the range 0.0..=1.0"),
+                args[0].range(),
+            ));
+        }
+
+        Ok(Expr::Bool(rng.gen_bool(probability)))
+    })
+}
+
+/// Returns a Buffer of random bytes.
+/// ```tan
+/// (let token (rng/bytes (rng/RNG {:algorithm :os}) 32))
+/// ```
+pub fn rng_bytes(args: &[Expr]) -> Result<Expr, Error> {
+    with_rng(args, |rng, args| {
+        let length = unpack_int_arg(args, 0, "length")?;
+
+        if length < 0 {
+            return Err(Error::invalid_arguments(
+                &format!("length=`{length}` cannot be negative"),
+                args[0].range(),
+            ));
+        }
+
+        let mut bytes = vec![0; length as usize];
+        rng.fill_bytes(&mut bytes);
+
+        Ok(Expr::Buffer(bytes.len(), Arc::new(RwLock::new(bytes))))
+    })
+}
+
+/// Fills a Buffer with random bytes.
+/// ```tan
+/// (rng/fill! rng buffer)
+/// ```
+pub fn rng_fill(args: &[Expr]) -> Result<Expr, Error> {
+    with_rng(args, |rng, args| {
+        let buffer = unpack_arg(args, 0, "buffer")?;
+
+        let Some((length, mut bytes)) = buffer.as_buffer_mut() else {
+            return Err(Error::invalid_arguments(
+                "`buffer` argument should be a Buffer",
+                buffer.range(),
+            ));
+        };
+
+        rng.fill_bytes(&mut bytes[..length]);
+
+        Ok(Expr::None)
+    })
+}
+
+/// (random 100) returns a random integer in the range 0..100
+pub fn random_int(args: &[Expr]) -> Result<Expr, Error> {
+    with_rng(args, |rng, args| {
+        if let Some(end) = args.first() {
+            let Some(end) = end.as_int() else {
+                return Err(Error::invalid_arguments(
+                    "expected Int argument",
+                    end.range(),
+                ));
+            };
+
+            check_int_range(0, end, &args[0])?;
+
+            Ok(Expr::Int(rng.gen_range(0..end)))
+        } else {
+            Err(Error::invalid_arguments(
+                "expected at least one argument",
+                None,
+            ))
+        }
+    })
 }
 
 // #todo Should make random generic, and also work with type-inference.
@@ -69,24 +304,24 @@ pub fn random_int(args: &[Expr]) -> Result<Expr, Error> {
 pub fn random_float(args: &[Expr]) -> Result<Expr, Error> {
     // #todo Also support a range argument!
 
-    let mut rng = rand::thread_rng();
-
-    match args.len() {
+    with_rng(args, |rng, args| match args.len() {
         0 => Ok(Expr::Float(rng.gen())),
         1 => {
             let end = unpack_float_arg(args, 0, "end")?;
+            check_float_range(0.0, end, &args[0])?;
             Ok(Expr::Float(rng.gen_range(0.0..end)))
         }
         2 => {
             let start = unpack_float_arg(args, 0, "start")?;
             let end = unpack_float_arg(args, 1, "end")?;
+            check_float_range(start, end, &args[0])?;
             Ok(Expr::Float(rng.gen_range(start..end)))
         }
         _ => Err(Error::invalid_arguments(
             "expected at least one argument to random-float",
             None,
         )),
-    }
+    })
 }
 
 pub fn import_lib_rng(context: &mut Context) {
@@ -98,6 +333,128 @@ pub fn import_lib_rng(context: &mut Context) {
     module.insert_invocable("random", Expr::foreign_func(&random_int));
     // #todo better name?
     module.insert_invocable("random-float", Expr::foreign_func(&random_float));
+
+    module.insert_invocable("RNG", Expr::foreign_func(&rng_new));
+    module.insert_invocable("int", Expr::foreign_func(&rng_int));
+    module.insert_invocable("float", Expr::foreign_func(&rng_float));
+    module.insert_invocable("bool", Expr::foreign_func(&rng_bool));
+    module.insert_invocable("bytes", Expr::foreign_func(&rng_bytes));
+    module.insert_invocable("fill!", Expr::foreign_func(&rng_fill));
+
+    setup_lib_rng_sampling(context);
+    setup_lib_rng_distributions(context);
 }
 
-// #todo add unit tests.
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use tan::expr::Expr;
+
+    use crate::rng::{random_float, random_int, rng_bytes, rng_float, rng_int, rng_new};
+
+    pub fn seeded_rng(algorithm: &str, seed: i64) -> Expr {
+        let options = HashMap::from([
+            ("seed".to_string(), Expr::Int(seed)),
+            (
+                "algorithm".to_string(),
+                Expr::KeySymbol(algorithm.to_string()),
+            ),
+        ]);
+        rng_new(&[Expr::map(options)]).unwrap()
+    }
+
+    fn sequence(rng: &Expr) -> (Vec<i64>, Vec<f64>, Vec<u8>) {
+        let ints = (0..8)
+            .map(|_| {
+                rng_int(&[rng.clone(), Expr::Int(1000)])
+                    .unwrap()
+                    .as_int()
+                    .unwrap()
+            })
+            .collect();
+        let floats = (0..8)
+            .map(|_| {
+                rng_float(std::slice::from_ref(rng))
+                    .unwrap()
+                    .as_float()
+                    .unwrap()
+            })
+            .collect();
+        let bytes = rng_bytes(&[rng.clone(), Expr::Int(16)]).unwrap();
+        let Expr::Buffer(_, bytes) = bytes.unpack() else {
+            panic!("expected a Buffer");
+        };
+        let bytes = bytes.read().unwrap().clone();
+        (ints, floats, bytes)
+    }
+
+    #[test]
+    fn seeded_rng_is_deterministic() {
+        for algorithm in ["pcg", "chacha"] {
+            let a = sequence(&seeded_rng(algorithm, 42));
+            let b = sequence(&seeded_rng(algorithm, 42));
+            assert_eq!(a, b);
+
+            let c = sequence(&seeded_rng(algorithm, 43));
+            assert_ne!(a, c);
+        }
+
+        // Different algorithms produce different sequences for the same seed.
+        assert_ne!(
+            sequence(&seeded_rng("pcg", 42)),
+            sequence(&seeded_rng("chacha", 42))
+        );
+    }
+
+    #[test]
+    fn rng_ranges() {
+        let rng = seeded_rng("pcg", 7);
+
+        for _ in 0..100 {
+            let n = rng_int(&[rng.clone(), Expr::Int(1), Expr::Int(7)]).unwrap();
+            assert!((1..7).contains(&n.as_int().unwrap()));
+
+            let n = rng_int(&[rng.clone(), Expr::Int(-3), Expr::Int(-2)]).unwrap();
+            assert_eq!(n.as_int(), Some(-3));
+
+            let x = rng_float(&[rng.clone(), Expr::Float(-1.0), Expr::Float(1.0)]).unwrap();
+            assert!((-1.0..1.0).contains(&x.as_float().unwrap()));
+
+            let x = rng_float(std::slice::from_ref(&rng)).unwrap();
+            assert!((0.0..1.0).contains(&x.as_float().unwrap()));
+        }
+    }
+
+    #[test]
+    fn rng_errors() {
+        let rng = seeded_rng("pcg", 7);
+
+        assert!(rng_int(&[rng.clone(), Expr::Int(5), Expr::Int(5)]).is_err());
+        assert!(rng_int(&[rng.clone(), Expr::Int(0)]).is_err());
+        assert!(rng_float(&[rng.clone(), Expr::Float(2.0), Expr::Float(1.0)]).is_err());
+        assert!(rng_float(&[rng.clone(), Expr::Float(f64::INFINITY)]).is_err());
+        assert!(rng_bytes(&[rng, Expr::Int(-1)]).is_err());
+
+        assert!(random_int(&[Expr::Int(0)]).is_err());
+        assert!(random_int(&[Expr::Int(-5)]).is_err());
+        assert!(random_float(&[Expr::Float(0.0)]).is_err());
+        assert!(random_float(&[Expr::Float(f64::NAN)]).is_err());
+        assert!(random_float(&[Expr::Float(2.0), Expr::Float(1.0)]).is_err());
+        assert!(random_float(&[Expr::Float(1.0), Expr::Float(1.0)]).is_err());
+
+        let options = |entries: &[(&str, Expr)]| {
+            Expr::map(
+                entries
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.clone()))
+                    .collect::<HashMap<_, _>>(),
+            )
+        };
+        let os = Expr::KeySymbol("os".to_string());
+        assert!(rng_new(&[options(&[("algorithm", os.clone())])]).is_ok());
+        assert!(rng_new(&[options(&[("algorithm", os), ("seed", Expr::Int(1))])]).is_err());
+        assert!(rng_new(&[options(&[("algorithm", Expr::string("mt"))])]).is_err());
+        assert!(rng_new(&[options(&[("seed", Expr::string("42"))])]).is_err());
+    }
+}
@@ -0,0 +1,170 @@
+use rand_distr::{Binomial, Distribution, Exp, Normal, Poisson};
+
+use tan::{
+    context::Context,
+    error::Error,
+    expr::Expr,
+    util::{
+        args::{unpack_float_arg, unpack_int_arg},
+        module_util::require_module,
+    },
+};
+
+use super::with_rng;
+
+// #todo Support generating an Array of samples, e.g. `(rng/normal rng 0.0 1.0 {:count 100})`.
+// #todo Add uniform, log-normal, gamma, beta.
+
+fn distribution_error(name: &str, error: impl std::fmt::Display, args: &[Expr]) -> Error {
+    Error::invalid_arguments(
+        &format!("invalid {name} distribution parameters: {error}"),
+        args.first().and_then(|arg| arg.range()),
+    )
+}
+
+/// Returns a sample of the normal (Gaussian) distribution, defaults to
+/// mean=0.0 and std-dev=1.0.
+/// ```tan
+/// (let latency (rng/normal rng 120.0 15.0))
+/// ```
+pub fn rng_normal(args: &[Expr]) -> Result<Expr, Error> {
+    with_rng(args, |rng, args| {
+        let (mean, std_dev) = if args.is_empty() {
+            (0.0, 1.0)
+        } else {
+            (
+                unpack_float_arg(args, 0, "mean")?,
+                unpack_float_arg(args, 1, "std-dev")?,
+            )
+        };
+
+        // #insight rand_distr accepts a negative std-dev, reject it explicitly.
+        if std_dev < 0.0 {
+            return Err(distribution_error(
+                "normal",
+                format!("std-dev=`{std_dev}` cannot be negative"),
+                args,
+            ));
+        }
+
+        let distribution =
+            Normal::new(mean, std_dev).map_err(|e| distribution_error("normal", e, args))?;
+
+        Ok(Expr::Float(distribution.sample(rng)))
+    })
+}
+
+/// Returns a sample of the exponential distribution with the given rate
+/// (lambda), e.g. the time between events.
+/// ```tan
+/// (let wait-time (rng/exponential rng 0.5))
+/// ```
+pub fn rng_exponential(args: &[Expr]) -> Result<Expr, Error> {
+    with_rng(args, |rng, args| {
+        let rate = unpack_float_arg(args, 0, "rate")?;
+
+        let distribution =
+            Exp::new(rate).map_err(|e| distribution_error("exponential", e, args))?;
+
+        Ok(Expr::Float(distribution.sample(rng)))
+    })
+}
+
+/// Returns a sample of the Poisson distribution with the given mean (lambda),
+/// e.g. the number of events in an interval.
+/// ```tan
+/// (let requests (rng/poisson rng 30.0))
+/// ```
+pub fn rng_poisson(args: &[Expr]) -> Result<Expr, Error> {
+    with_rng(args, |rng, args| {
+        let lambda = unpack_float_arg(args, 0, "lambda")?;
+
+        let distribution =
+            Poisson::new(lambda).map_err(|e| distribution_error("poisson", e, args))?;
+
+        let sample: f64 = distribution.sample(rng);
+
+        Ok(Expr::Int(sample as i64))
+    })
+}
+
+/// Returns a sample of the binomial distribution, the number of successes in
+/// `n` trials with probability `p`.
+/// ```tan
+/// (let conversions (rng/binomial rng 1000 0.03))
+/// ```
+pub fn rng_binomial(args: &[Expr]) -> Result<Expr, Error> {
+    with_rng(args, |rng, args| {
+        let n = unpack_int_arg(args, 0, "n")?;
+        let p = unpack_float_arg(args, 1, "p")?;
+
+        if n < 0 {
+            return Err(Error::invalid_arguments(
+                &format!("n=`{n}` cannot be negative"),
+                args[0].range(),
+            ));
+        }
+
+        let distribution =
+            Binomial::new(n as u64, p).map_err(|e| distribution_error("binomial", e, args))?;
+
+        Ok(Expr::Int(distribution.sample(rng) as i64))
+    })
+}
+
+pub fn setup_lib_rng_distributions(context: &mut Context) {
+    let module = require_module("rng", context);
+
+    module.insert_invocable("normal", Expr::foreign_func(&rng_normal));
+    module.insert_invocable("exponential", Expr::foreign_func(&rng_exponential));
+    module.insert_invocable("poisson", Expr::foreign_func(&rng_poisson));
+    module.insert_invocable("binomial", Expr::foreign_func(&rng_binomial));
+}
+
+#[cfg(test)]
+mod tests {
+    use tan::expr::Expr;
+
+    use crate::rng::{
+        distributions::{rng_binomial, rng_exponential, rng_normal, rng_poisson},
+        tests::seeded_rng,
+    };
+
+    #[test]
+    fn seeded_distributions_are_deterministic() {
+        let run = || {
+            let rng = seeded_rng("chacha", 42);
+            let normal = rng_normal(&[rng.clone(), Expr::Float(120.0), Expr::Float(15.0)])
+                .unwrap()
+                .as_float();
+            let exponential = rng_exponential(&[rng.clone(), Expr::Float(0.5)])
+                .unwrap()
+                .as_float();
+            let poisson = rng_poisson(&[rng.clone(), Expr::Float(30.0)])
+                .unwrap()
+                .as_int();
+            let binomial = rng_binomial(&[rng, Expr::Int(1000), Expr::Float(0.03)])
+                .unwrap()
+                .as_int();
+            (normal, exponential, poisson, binomial)
+        };
+
+        assert_eq!(run(), run());
+
+        let (_, exponential, poisson, binomial) = run();
+        assert!(exponential.unwrap() >= 0.0);
+        assert!(poisson.unwrap() >= 0);
+        assert!((0..=1000).contains(&binomial.unwrap()));
+    }
+
+    #[test]
+    fn distribution_errors() {
+        let rng = seeded_rng("pcg", 1);
+
+        assert!(rng_normal(&[rng.clone(), Expr::Float(0.0), Expr::Float(-1.0)]).is_err());
+        assert!(rng_exponential(&[rng.clone(), Expr::Float(-0.5)]).is_err());
+        assert!(rng_poisson(&[rng.clone(), Expr::Float(0.0)]).is_err());
+        assert!(rng_binomial(&[rng.clone(), Expr::Int(-1), Expr::Float(0.5)]).is_err());
+        assert!(rng_binomial(&[rng, Expr::Int(10), Expr::Float(1.5)]).is_err());
+    }
+}
@@ -0,0 +1,251 @@
+use rand::{
+    distributions::WeightedIndex,
+    prelude::Distribution,
+    seq::{index, SliceRandom},
+};
+
+use tan::{
+    context::Context,
+    error::Error,
+    expr::Expr,
+    util::{
+        args::{unpack_arg, unpack_array_arg, unpack_int_arg},
+        module_util::require_module,
+    },
+};
+
+use super::with_rng;
+
+/// Returns a shuffled copy of an Array.
+/// ```tan
+/// (let deck (rng/shuffle rng cards))
+/// ```
+pub fn rng_shuffle(args: &[Expr]) -> Result<Expr, Error> {
+    with_rng(args, |rng, args| {
+        let array = unpack_array_arg(args, 0, "array")?;
+
+        let mut items = array.clone();
+        items.shuffle(rng);
+
+        Ok(Expr::array(items))
+    })
+}
+
+/// Shuffles an Array in place.
+/// ```tan
+/// (rng/shuffle! rng cards)
+/// ```
+pub fn rng_shuffle_mut(args: &[Expr]) -> Result<Expr, Error> {
+    with_rng(args, |rng, args| {
+        let array = unpack_arg(args, 0, "array")?;
+
+        let Some(mut items) = array.as_array_mut() else {
+            return Err(Error::invalid_arguments(
+                "`array` argument should be an Array",
+                array.range(),
+            ));
+        };
+
+        items.shuffle(rng);
+
+        // #todo What should we return?
+        Ok(Expr::None)
+    })
+}
+
+/// Returns a random element of an Array, None if the Array is empty.
+/// ```tan
+/// (let color (rng/choose ["red" "green" "blue"]))
+/// ```
+pub fn rng_choose(args: &[Expr]) -> Result<Expr, Error> {
+    with_rng(args, |rng, args| {
+        let array = unpack_array_arg(args, 0, "array")?;
+
+        Ok(array.choose(rng).cloned().unwrap_or(Expr::None))
+    })
+}
+
+/// Returns a random element of an Array, with the probability of each element
+/// proportional to its weight.
+/// ```tan
+/// (let bucket (rng/choose-weighted ["control" "variant-a" "variant-b"] [80 10 10]))
+/// ```
+pub fn rng_choose_weighted(args: &[Expr]) -> Result<Expr, Error> {
+    with_rng(args, |rng, args| {
+        let array = unpack_array_arg(args, 0, "array")?;
+        let weights_expr = unpack_arg(args, 1, "weights")?;
+        let weights = unpack_array_arg(args, 1, "weights")?;
+
+        if array.len() != weights.len() {
+            return Err(Error::invalid_arguments(
+                &format!("expected {} weights, found {}", array.len(), weights.len()),
+                weights_expr.range(),
+            ));
+        }
+
+        let mut float_weights = Vec::with_capacity(weights.len());
+        for weight in weights.iter() {
+            let weight = match weight.unpack() {
+                Expr::Int(n) => *n as f64,
+                Expr::Float(n) => *n,
+                _ => {
+                    return Err(Error::invalid_arguments(
+                        "weights should be numbers",
+                        weight.range(),
+                    ));
+                }
+            };
+            float_weights.push(weight);
+        }
+
+        let distribution = match WeightedIndex::new(&float_weights) {
+            Ok(distribution) => distribution,
+            Err(error) => {
+                return Err(Error::invalid_arguments(
+                    &format!("invalid weights: {error}"),
+                    weights_expr.range(),
+                ));
+            }
+        };
+
+        Ok(array[distribution.sample(rng)].clone())
+    })
+}
+
+/// Returns `count` distinct random elements of an Array (sampling without
+/// replacement), in random order.
+/// ```tan
+/// (let winners (rng/sample rng participants 3))
+/// ```
+pub fn rng_sample(args: &[Expr]) -> Result<Expr, Error> {
+    with_rng(args, |rng, args| {
+        let array = unpack_array_arg(args, 0, "array")?;
+        let count = unpack_int_arg(args, 1, "count")?;
+
+        if count < 0 || count as usize > array.len() {
+            return Err(Error::invalid_arguments(
+                &format!("count=`{count}` should be in the range 0..={}", array.len()),
+                args[1].range(),
+            ));
+        }
+
+        let items = index::sample(rng, array.len(), count as usize)
+            .into_iter()
+            .map(|i| array[i].clone())
+            .collect::<Vec<_>>();
+
+        Ok(Expr::array(items))
+    })
+}
+
+pub fn setup_lib_rng_sampling(context: &mut Context) {
+    let module = require_module("rng", context);
+
+    module.insert_invocable("shuffle", Expr::foreign_func(&rng_shuffle));
+    module.insert_invocable("shuffle!", Expr::foreign_func(&rng_shuffle_mut));
+    module.insert_invocable("choose", Expr::foreign_func(&rng_choose));
+    module.insert_invocable("choose-weighted", Expr::foreign_func(&rng_choose_weighted));
+    module.insert_invocable("sample", Expr::foreign_func(&rng_sample));
+}
+
+#[cfg(test)]
+mod tests {
+    use tan::expr::Expr;
+
+    use crate::rng::{
+        sampling::{rng_choose, rng_choose_weighted, rng_sample, rng_shuffle, rng_shuffle_mut},
+        tests::seeded_rng,
+    };
+
+    fn ints(n: i64) -> Expr {
+        Expr::array((0..n).map(Expr::Int).collect::<Vec<_>>())
+    }
+
+    fn to_vec(expr: &Expr) -> Vec<i64> {
+        expr.as_array()
+            .unwrap()
+            .iter()
+            .map(|x| x.as_int().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn seeded_sampling_is_deterministic() {
+        let run = || {
+            let rng = seeded_rng("pcg", 42);
+            let shuffled = to_vec(&rng_shuffle(&[rng.clone(), ints(20)]).unwrap());
+            let sample = to_vec(&rng_sample(&[rng.clone(), ints(20), Expr::Int(5)]).unwrap());
+            let weights = Expr::array(vec![Expr::Int(1), Expr::Float(2.5), Expr::Int(0)]);
+            let chosen: Vec<_> = (0..10)
+                .map(|_| {
+                    rng_choose_weighted(&[rng.clone(), ints(3), weights.clone()])
+                        .unwrap()
+                        .as_int()
+                        .unwrap()
+                })
+                .collect();
+            (shuffled, sample, chosen)
+        };
+
+        let (shuffled, sample, chosen) = run();
+        assert_eq!((shuffled.clone(), sample.clone(), chosen.clone()), run());
+
+        let mut sorted = shuffled;
+        sorted.sort();
+        assert_eq!(sorted, (0..20).collect::<Vec<_>>());
+
+        let mut distinct = sample;
+        distinct.sort();
+        distinct.dedup();
+        assert_eq!(distinct.len(), 5);
+
+        // The element with weight 0 is never chosen.
+        assert!(chosen.iter().all(|i| *i != 2));
+    }
+
+    #[test]
+    fn rng_shuffle_mut_usage() {
+        let array = ints(10);
+        rng_shuffle_mut(&[seeded_rng("pcg", 1), array.clone()]).unwrap();
+
+        let mut items = to_vec(&array);
+        items.sort();
+        assert_eq!(items, (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn sampling_errors() {
+        let rng = seeded_rng("pcg", 1);
+        let empty = Expr::array(Vec::new());
+
+        assert!(matches!(
+            rng_choose(&[rng.clone(), empty.clone()]).unwrap(),
+            Expr::None
+        ));
+        assert!(rng_choose_weighted(&[rng.clone(), empty.clone(), empty.clone()]).is_err());
+
+        let weights = |weights: Vec<Expr>| Expr::array(weights);
+        assert!(rng_choose_weighted(&[rng.clone(), ints(2), weights(vec![Expr::Int(1)])]).is_err());
+        assert!(rng_choose_weighted(&[
+            rng.clone(),
+            ints(2),
+            weights(vec![Expr::Int(1), Expr::Int(-1)])
+        ])
+        .is_err());
+        assert!(rng_choose_weighted(&[
+            rng.clone(),
+            ints(2),
+            weights(vec![Expr::Int(0), Expr::Int(0)])
+        ])
+        .is_err());
+        assert!(rng_choose_weighted(&[
+            rng.clone(),
+            ints(2),
+            weights(vec![Expr::Int(1), Expr::string("1")])
+        ])
+        .is_err());
+
+        assert!(rng_sample(&[rng.clone(), ints(3), Expr::Int(4)]).is_err());
+        assert!(rng_sample(&[rng, ints(3), Expr::Int(-1)]).is_err());
+    }
+}
@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+
+use tan::{
+    error::Error,
+    expr::Expr,
+    util::{
+        args::{unpack_arg, unpack_map_arg},
+        expect_lock_read,
+    },
+};
+
+// #insight Complements `tan::util::args`.
+
+/// Returns the optional `options` Map argument, an empty Map if missing.
+pub fn options_from_args(args: &[Expr], index: usize) -> HashMap<String, Expr> {
+    if let Ok(options) = unpack_map_arg(args, index, "options") {
+        options.clone()
+    } else {
+        HashMap::new()
+    }
+}
+
+// #insight Strings are converted to UTF-8 bytes.
+/// Returns the bytes of a Buffer or String.
+pub fn bytes_from_expr(expr: &Expr, name: &str) -> Result<Vec<u8>, Error> {
+    match expr.unpack() {
+        Expr::Buffer(length, bytes) => {
+            let bytes = expect_lock_read(bytes);
+            Ok(bytes[..*length].to_vec())
+        }
+        _ => {
+            let Some(string) = expr.as_stringable() else {
+                return Err(Error::invalid_arguments(
+                    &format!("`{name}` argument should be a Buffer or a String"),
+                    expr.range(),
+                ));
+            };
+            Ok(string.as_bytes().to_vec())
+        }
+    }
+}
+
+pub fn unpack_bytes_arg(args: &[Expr], index: usize, name: &str) -> Result<Vec<u8>, Error> {
+    let expr = unpack_arg(args, index, name)?;
+    bytes_from_expr(expr, name)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{Arc, RwLock},
+    };
+
+    use tan::expr::Expr;
+
+    use crate::args::{options_from_args, unpack_bytes_arg};
+
+    #[test]
+    fn unpack_bytes_arg_usage() {
+        let buffer = Expr::Buffer(2, Arc::new(RwLock::new(vec![1, 2, 3])));
+        let args = [Expr::string("tan"), buffer, Expr::Int(1)];
+
+        assert_eq!(unpack_bytes_arg(&args, 0, "data").unwrap(), b"tan");
+        // Only the bytes within the Buffer length are returned.
+        assert_eq!(unpack_bytes_arg(&args, 1, "data").unwrap(), [1, 2]);
+        assert!(unpack_bytes_arg(&args, 2, "data").is_err());
+        assert!(unpack_bytes_arg(&args, 3, "data").is_err());
+    }
+
+    #[test]
+    fn options_from_args_usage() {
+        let options = Expr::map(HashMap::from([("level".to_string(), Expr::Int(9))]));
+        let args = [Expr::string("tan"), options];
+
+        assert_eq!(options_from_args(&args, 1).len(), 1);
+        assert!(options_from_args(&args, 0).is_empty());
+        assert!(options_from_args(&args, 2).is_empty());
+    }
+}
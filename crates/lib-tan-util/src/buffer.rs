@@ -0,0 +1,29 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use tan::{error::Error, expr::Expr};
+
+pub fn buffer_expr(bytes: Vec<u8>) -> Expr {
+    Expr::Buffer(bytes.len(), Arc::new(RwLock::new(bytes)))
+}
+
+/// Returns the decoded bytes as a Buffer, or as a String with the
+/// `{:encoding :utf8}` option. Errors refer to the `source` expression.
+pub fn decoded_bytes_expr(
+    bytes: Vec<u8>,
+    options: &HashMap<String, Expr>,
+    source: &Expr,
+) -> Result<Expr, Error> {
+    match options.get("encoding").and_then(|e| e.as_stringable()) {
+        Some("utf8") => match String::from_utf8(bytes) {
+            Ok(s) => Ok(Expr::string(s)),
+            Err(_) => Err(Error::invalid_arguments(
+                "the decoded data is not valid UTF-8",
+                source.range(),
+            )),
+        },
+        _ => Ok(buffer_expr(bytes)),
+    }
+}
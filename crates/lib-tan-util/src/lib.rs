@@ -0,0 +1,9 @@
+// #insight
+// Rust helpers shared by the foreign libraries, this is not a Tan library
+// and has no `install_foreign_dyn_lib` entry point.
+
+// #todo Consider moving to `tan::util`.
+
+pub mod args;
+pub mod buffer;
+pub mod path;
@@ -0,0 +1,63 @@
+use std::path::Path;
+
+use globset::{GlobBuilder, GlobMatcher};
+use tan::{
+    error::Error,
+    expr::{annotate_type, Expr},
+};
+
+/// Wraps a Rust path into a Tan `Path` value.
+pub fn path_expr(path: impl AsRef<Path>) -> Expr {
+    annotate_type(Expr::string(path.as_ref().to_string_lossy()), "Path")
+}
+
+// #insight
+// `*` and `?` do not match the path separator, use `**` to match across
+// directories. Character classes (`[a-z]`) and brace alternation (`{md,txt}`)
+// are supported.
+/// Compiles a glob pattern into a matcher.
+pub fn glob_matcher_from_pattern(
+    pattern: &str,
+    case_insensitive: bool,
+) -> Result<GlobMatcher, Error> {
+    let glob = GlobBuilder::new(pattern)
+        .literal_separator(true)
+        .case_insensitive(case_insensitive)
+        .build();
+
+    match glob {
+        Ok(glob) => Ok(glob.compile_matcher()),
+        Err(glob_error) => Err(Error::invalid_arguments(
+            &format!("invalid glob pattern `{pattern}`: {}", glob_error.kind()),
+            None,
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::path::glob_matcher_from_pattern;
+
+    #[test]
+    fn glob_matcher_from_pattern_usage() {
+        let matcher = glob_matcher_from_pattern("content/**/*.md", false).unwrap();
+        assert!(matcher.is_match("content/post.md"));
+        assert!(matcher.is_match("content/blog/2024/post.md"));
+        assert!(!matcher.is_match("content/post.MD"));
+        assert!(!matcher.is_match("public/post.md"));
+
+        let matcher = glob_matcher_from_pattern("*.md", false).unwrap();
+        assert!(!matcher.is_match("content/post.md"));
+
+        let matcher = glob_matcher_from_pattern("img/[a-c]?.{png,jpg}", false).unwrap();
+        assert!(matcher.is_match("img/a1.png"));
+        assert!(matcher.is_match("img/c2.jpg"));
+        assert!(!matcher.is_match("img/d1.png"));
+        assert!(!matcher.is_match("img/a1.gif"));
+
+        let matcher = glob_matcher_from_pattern("content/**/*.md", true).unwrap();
+        assert!(matcher.is_match("Content/Post.MD"));
+
+        assert!(glob_matcher_from_pattern("content/[a-", false).is_err());
+    }
+}
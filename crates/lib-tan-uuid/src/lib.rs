@@ -1,11 +1,17 @@
 // #todo Consider moving to /string.
 
+use nanoid::import_lib_nanoid;
 use tan::context::Context;
+use ulid::import_lib_ulid;
 use uuid::import_lib_uuid;
 
+pub mod nanoid;
+pub mod ulid;
 pub mod uuid;
 
 #[no_mangle]
 pub fn install_foreign_dyn_lib(context: &mut Context) {
     import_lib_uuid(context);
+    import_lib_ulid(context);
+    import_lib_nanoid(context);
 }
@@ -0,0 +1,165 @@
+use tan::{
+    context::Context,
+    error::Error,
+    expr::Expr,
+    util::{
+        args::{unpack_map_arg, unpack_stringable_arg},
+        module_util::require_module,
+    },
+};
+
+// #insight
+// NanoIDs are short, URL-safe random IDs, the default 21 characters give a
+// collision probability similar to v4 UUIDs.
+
+const DEFAULT_SIZE: usize = 21;
+
+struct NanoidOptions {
+    size: Option<usize>,
+    alphabet: Vec<char>,
+}
+
+fn nanoid_options(args: &[Expr], index: usize) -> Result<NanoidOptions, Error> {
+    let mut nanoid_options = NanoidOptions {
+        size: None,
+        alphabet: nanoid::alphabet::SAFE.to_vec(),
+    };
+
+    let Ok(options) = unpack_map_arg(args, index, "options") else {
+        return Ok(nanoid_options);
+    };
+
+    if let Some(size) = options.get("size") {
+        match size.as_int() {
+            Some(n) if n > 0 => nanoid_options.size = Some(n as usize),
+            _ => {
+                return Err(Error::invalid_arguments(
+                    "`size` option should be a positive Int",
+                    size.range(),
+                ));
+            }
+        }
+    }
+
+    if let Some(alphabet) = options.get("alphabet") {
+        let Some(chars) = alphabet.as_stringable() else {
+            return Err(Error::invalid_arguments(
+                "`alphabet` option should be a String",
+                alphabet.range(),
+            ));
+        };
+
+        let chars: Vec<char> = chars.chars().collect();
+
+        let mut unique_chars = chars.clone();
+        unique_chars.sort_unstable();
+        unique_chars.dedup();
+
+        // #insight
+        // The nanoid crate panics on alphabets longer than 255, and counts the
+        // size in bytes, a non-ASCII alphabet gives IDs of the wrong size.
+        if unique_chars.len() != chars.len()
+            || !(2..=255).contains(&chars.len())
+            || !chars.iter().all(|c| c.is_ascii())
+        {
+            return Err(Error::invalid_arguments(
+                "`alphabet` option should have 2 to 255 unique ASCII characters",
+                alphabet.range(),
+            ));
+        }
+
+        nanoid_options.alphabet = chars;
+    }
+
+    Ok(nanoid_options)
+}
+
+/// Returns a new NanoID, 21 URL-safe characters by default.
+/// ```tan
+/// (let id (uuid/nanoid))
+/// (let code (uuid/nanoid {:size 8 :alphabet "0123456789ABCDEF"}))
+/// ```
+pub fn nanoid_new(args: &[Expr]) -> Result<Expr, Error> {
+    let options = nanoid_options(args, 0)?;
+
+    let id = nanoid::format(
+        nanoid::rngs::default,
+        &options.alphabet,
+        options.size.unwrap_or(DEFAULT_SIZE),
+    );
+
+    Ok(Expr::string(id))
+}
+
+/// Returns true if the string only contains characters of the alphabet, and
+/// has the given size (any size if not given).
+/// ```tan
+/// (uuid/nanoid-valid? code {:size 8 :alphabet "0123456789ABCDEF"})
+/// ```
+pub fn nanoid_is_valid(args: &[Expr]) -> Result<Expr, Error> {
+    let s = unpack_stringable_arg(args, 0, "string")?;
+    let options = nanoid_options(args, 1)?;
+
+    let is_valid_size = match options.size {
+        Some(size) => s.chars().count() == size,
+        None => !s.is_empty(),
+    };
+
+    let is_valid = is_valid_size && s.chars().all(|c| options.alphabet.contains(&c));
+
+    Ok(Expr::Bool(is_valid))
+}
+
+pub fn import_lib_nanoid(context: &mut Context) {
+    // #insight Installed together with the uuid module.
+    let module = require_module("uuid", context);
+
+    module.insert_invocable("nanoid", Expr::foreign_func(&nanoid_new));
+    module.insert_invocable("nanoid-valid?", Expr::foreign_func(&nanoid_is_valid));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use tan::expr::Expr;
+
+    use crate::nanoid::{nanoid_is_valid, nanoid_new};
+
+    fn options(size: i64, alphabet: &str) -> Expr {
+        Expr::map(HashMap::from([
+            ("size".to_string(), Expr::Int(size)),
+            ("alphabet".to_string(), Expr::string(alphabet)),
+        ]))
+    }
+
+    #[test]
+    fn nanoid_new_usage() {
+        let id = nanoid_new(&[]).unwrap();
+        assert_eq!(id.as_string().unwrap().len(), 21);
+
+        let options = options(7, "0123456789ABCDEF");
+        let id = nanoid_new(std::slice::from_ref(&options)).unwrap();
+        let id = id.as_string().unwrap();
+        assert_eq!(id.len(), 7);
+        assert!(id.chars().all(|c| c.is_ascii_hexdigit()));
+
+        let is_valid = nanoid_is_valid(&[Expr::string(id), options.clone()]).unwrap();
+        assert_eq!(is_valid.as_bool(), Some(true));
+        let is_valid = nanoid_is_valid(&[Expr::string("0123456"), options.clone()]).unwrap();
+        assert_eq!(is_valid.as_bool(), Some(true));
+        let is_valid = nanoid_is_valid(&[Expr::string("012345g"), options]).unwrap();
+        assert_eq!(is_valid.as_bool(), Some(false));
+    }
+
+    #[test]
+    fn nanoid_invalid_alphabet() {
+        // Non-ASCII alphabets are rejected, instead of looping forever.
+        assert!(nanoid_new(&[options(3, "αβ")]).is_err());
+        assert!(nanoid_new(&[options(4, "ab€")]).is_err());
+
+        assert!(nanoid_new(&[options(4, "a")]).is_err());
+        assert!(nanoid_new(&[options(4, "abca")]).is_err());
+        assert!(nanoid_new(&[options(0, "ab")]).is_err());
+    }
+}
@@ -0,0 +1,161 @@
+use std::sync::{Mutex, OnceLock};
+
+use tan::{
+    context::Context,
+    error::Error,
+    expr::{annotate_type, Expr},
+    util::{
+        args::{unpack_arg, unpack_map_arg, unpack_stringable_arg},
+        module_util::require_module,
+    },
+};
+use ulid::{Generator, Ulid};
+use uuid::Uuid;
+
+use crate::uuid::uuid_expr;
+
+// #insight
+// A Ulid is a String in the canonical (uppercase Crockford base32) form,
+// annotated with the `Ulid` type. ULIDs sort lexicographically by time.
+
+// #insight
+// In monotonic mode, ULIDs generated within the same millisecond increment
+// the random part, so they still sort in generation order.
+static MONOTONIC_GENERATOR: OnceLock<Mutex<Generator>> = OnceLock::new();
+
+pub fn ulid_expr(id: Ulid) -> Expr {
+    annotate_type(Expr::string(id.to_string()), "Ulid")
+}
+
+fn unpack_ulid_arg(args: &[Expr], index: usize, name: &str) -> Result<Ulid, Error> {
+    let expr = unpack_arg(args, index, name)?;
+
+    let Some(s) = expr.as_stringable() else {
+        return Err(Error::invalid_arguments(
+            &format!("`{name}` argument should be a Ulid or String"),
+            expr.range(),
+        ));
+    };
+
+    Ulid::from_string(s).map_err(|error| {
+        Error::invalid_arguments(&format!("invalid ULID `{s}`: {error}"), expr.range())
+    })
+}
+
+/// Returns a new ULID.
+/// ```tan
+/// (let id (uuid/ulid))
+/// (let event-id (uuid/ulid {:monotonic true}))
+/// ```
+pub fn ulid_new(args: &[Expr]) -> Result<Expr, Error> {
+    let monotonic = if let Ok(options) = unpack_map_arg(args, 0, "options") {
+        options
+            .get("monotonic")
+            .and_then(|x| x.as_bool())
+            .unwrap_or_default()
+    } else {
+        false
+    };
+
+    if !monotonic {
+        return Ok(ulid_expr(Ulid::new()));
+    }
+
+    let generator = MONOTONIC_GENERATOR.get_or_init(|| Mutex::new(Generator::new()));
+    let mut generator = generator.lock().expect("not poisoned");
+
+    match generator.generate() {
+        Ok(id) => Ok(ulid_expr(id)),
+        Err(error) => Err(Error::general(&format!("cannot generate ULID: {error}"))),
+    }
+}
+
+/// Parses a ULID string (case-insensitive), returns the canonical Ulid.
+pub fn ulid_parse(args: &[Expr]) -> Result<Expr, Error> {
+    let id = unpack_ulid_arg(args, 0, "string")?;
+
+    Ok(ulid_expr(id))
+}
+
+/// Returns true if the string is a valid ULID.
+pub fn ulid_is_valid(args: &[Expr]) -> Result<Expr, Error> {
+    let s = unpack_stringable_arg(args, 0, "string")?;
+
+    Ok(Expr::Bool(Ulid::from_string(s).is_ok()))
+}
+
+/// Returns the timestamp of a Ulid in milliseconds since the unix epoch.
+pub fn ulid_get_timestamp(args: &[Expr]) -> Result<Expr, Error> {
+    let id = unpack_ulid_arg(args, 0, "ulid")?;
+
+    Ok(Expr::Int(id.timestamp_ms() as i64))
+}
+
+/// Converts a Ulid to a Uuid with the same 128 bits.
+/// ```tan
+/// (uuid/ulid->uuid "01ARZ3NDEKTSV4RRFFQ69G5FAV") ; => "01563e3a-b5d3-d676-4c61-efb99302bd5b"
+/// ```
+pub fn ulid_to_uuid(args: &[Expr]) -> Result<Expr, Error> {
+    let id = unpack_ulid_arg(args, 0, "ulid")?;
+
+    Ok(uuid_expr(Uuid::from_u128(id.0)))
+}
+
+pub fn import_lib_ulid(context: &mut Context) {
+    // #insight Installed together with the uuid module.
+    let module = require_module("uuid", context);
+
+    module.insert_invocable("ulid", Expr::foreign_func(&ulid_new));
+    module.insert_invocable("parse-ulid", Expr::foreign_func(&ulid_parse));
+    module.insert_invocable("ulid-valid?", Expr::foreign_func(&ulid_is_valid));
+    module.insert_invocable(
+        "get-ulid-timestamp",
+        Expr::foreign_func(&ulid_get_timestamp),
+    );
+    module.insert_invocable("ulid->uuid", Expr::foreign_func(&ulid_to_uuid));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use tan::expr::Expr;
+
+    use crate::ulid::{ulid_get_timestamp, ulid_is_valid, ulid_new, ulid_parse, ulid_to_uuid};
+
+    #[test]
+    fn ulid_to_uuid_usage() {
+        let id = ulid_to_uuid(&[Expr::string("01ARZ3NDEKTSV4RRFFQ69G5FAV")]).unwrap();
+        assert_eq!(id.as_string(), Some("01563e3a-b5d3-d676-4c61-efb99302bd5b"));
+    }
+
+    #[test]
+    fn ulid_parse_usage() {
+        let id = ulid_parse(&[Expr::string("01arz3ndektsv4rrffq69g5fav")]).unwrap();
+        assert_eq!(id.as_string(), Some("01ARZ3NDEKTSV4RRFFQ69G5FAV"));
+
+        let timestamp = ulid_get_timestamp(&[id]).unwrap();
+        assert_eq!(timestamp.as_int(), Some(1469922850259));
+
+        let is_valid = ulid_is_valid(&[Expr::string("01ARZ3NDEKTSV4RRFFQ69G5FA")]).unwrap();
+        assert_eq!(is_valid.as_bool(), Some(false));
+        assert!(ulid_parse(&[Expr::string("not-a-ulid")]).is_err());
+    }
+
+    #[test]
+    fn monotonic_ulids_are_ordered() {
+        let options = Expr::map(HashMap::from([("monotonic".to_string(), Expr::Bool(true))]));
+
+        let ids: Vec<String> = (0..100)
+            .map(|_| {
+                let id = ulid_new(std::slice::from_ref(&options)).unwrap();
+                id.as_string().unwrap().to_string()
+            })
+            .collect();
+
+        let mut sorted = ids.clone();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(ids, sorted);
+    }
+}
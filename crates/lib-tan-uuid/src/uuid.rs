@@ -1,16 +1,181 @@
-use tan::{context::Context, error::Error, expr::Expr, util::module_util::require_module};
+use std::sync::{Arc, RwLock};
+
+use tan::{
+    context::Context,
+    error::Error,
+    expr::{annotate_type, Expr},
+    util::{
+        args::{unpack_arg, unpack_stringable_arg},
+        expect_lock_read,
+        module_util::require_module,
+    },
+};
 use uuid::Uuid;
 
 // #todo Extract as a separate lib-tan-* crate.
-// #todo UUID object with related methods.
 // #todo UUID is a 128bit number, a buffer of 16 bytes.
 
+// #insight
+// A Uuid is a String in the canonical (lowercase, hyphenated) form, annotated
+// with the `Uuid` type. The canonical form makes `=` compare UUIDs correctly,
+// e.g. a parsed uppercase UUID is equal to the original.
+
+pub fn uuid_expr(id: Uuid) -> Expr {
+    annotate_type(Expr::string(id.hyphenated()), "Uuid")
+}
+
+fn parse_uuid(expr: &Expr, name: &str) -> Result<Uuid, Error> {
+    let Some(s) = expr.as_stringable() else {
+        return Err(Error::invalid_arguments(
+            &format!("`{name}` argument should be a Uuid or String"),
+            expr.range(),
+        ));
+    };
+
+    Uuid::try_parse(s).map_err(|error| {
+        Error::invalid_arguments(&format!("invalid UUID `{s}`: {error}"), expr.range())
+    })
+}
+
+fn unpack_uuid_arg(args: &[Expr], index: usize, name: &str) -> Result<Uuid, Error> {
+    let expr = unpack_arg(args, index, name)?;
+    parse_uuid(expr, name)
+}
+
+/// Returns the namespace for the well-known names, or parses a Uuid.
+fn unpack_namespace_arg(args: &[Expr], index: usize) -> Result<Uuid, Error> {
+    let expr = unpack_arg(args, index, "namespace")?;
+
+    match expr.as_stringable() {
+        Some("dns") => Ok(Uuid::NAMESPACE_DNS),
+        Some("url") => Ok(Uuid::NAMESPACE_URL),
+        Some("oid") => Ok(Uuid::NAMESPACE_OID),
+        Some("x500") => Ok(Uuid::NAMESPACE_X500),
+        _ => parse_uuid(expr, "namespace"),
+    }
+}
+
 pub fn uuid_new_v4(_args: &[Expr]) -> Result<Expr, Error> {
     let id = Uuid::new_v4();
 
-    // #todo for the moment we use the UUID as a string alias, should be a buffer of 16 bytes?
+    Ok(uuid_expr(id))
+}
+
+/// Returns a time-ordered v7 UUID, ideal for database keys.
+/// ```tan
+/// (let id (uuid/v7))
+/// ```
+pub fn uuid_new_v7(_args: &[Expr]) -> Result<Expr, Error> {
+    let id = Uuid::now_v7();
 
-    Ok(Expr::string(id))
+    Ok(uuid_expr(id))
+}
+
+/// Returns a name-based v3 (MD5) UUID, prefer v5.
+pub fn uuid_new_v3(args: &[Expr]) -> Result<Expr, Error> {
+    let namespace = unpack_namespace_arg(args, 0)?;
+    let name = unpack_stringable_arg(args, 1, "name")?;
+
+    Ok(uuid_expr(Uuid::new_v3(&namespace, name.as_bytes())))
+}
+
+/// Returns a name-based v5 (SHA-1) UUID, the same name always gives the same UUID.
+/// ```tan
+/// (let id (uuid/v5 :url "https://tan-language.org"))
+/// ```
+pub fn uuid_new_v5(args: &[Expr]) -> Result<Expr, Error> {
+    let namespace = unpack_namespace_arg(args, 0)?;
+    let name = unpack_stringable_arg(args, 1, "name")?;
+
+    Ok(uuid_expr(Uuid::new_v5(&namespace, name.as_bytes())))
+}
+
+/// Returns the nil UUID, all bits zero.
+pub fn uuid_nil(_args: &[Expr]) -> Result<Expr, Error> {
+    Ok(uuid_expr(Uuid::nil()))
+}
+
+/// Parses a UUID string in any supported form (hyphenated, simple, braced,
+/// urn), returns the canonical Uuid.
+/// ```tan
+/// (uuid/parse "{67E55044-10B1-426F-9247-BB680E5FE0C8}") ; => "67e55044-10b1-426f-9247-bb680e5fe0c8"
+/// ```
+pub fn uuid_parse(args: &[Expr]) -> Result<Expr, Error> {
+    let id = unpack_uuid_arg(args, 0, "string")?;
+
+    Ok(uuid_expr(id))
+}
+
+/// Returns true if the string is a valid UUID.
+pub fn uuid_is_valid(args: &[Expr]) -> Result<Expr, Error> {
+    let s = unpack_stringable_arg(args, 0, "string")?;
+
+    Ok(Expr::Bool(Uuid::try_parse(s).is_ok()))
+}
+
+/// Converts a Uuid to a 16-byte Buffer.
+pub fn uuid_to_buffer(args: &[Expr]) -> Result<Expr, Error> {
+    let id = unpack_uuid_arg(args, 0, "uuid")?;
+
+    let bytes = id.as_bytes().to_vec();
+
+    Ok(Expr::Buffer(bytes.len(), Arc::new(RwLock::new(bytes))))
+}
+
+/// Converts a 16-byte Buffer to a Uuid.
+pub fn uuid_from_buffer(args: &[Expr]) -> Result<Expr, Error> {
+    let buffer = unpack_arg(args, 0, "buffer")?;
+
+    let Expr::Buffer(length, bytes) = buffer.unpack() else {
+        return Err(Error::invalid_arguments(
+            "`buffer` argument should be a Buffer",
+            buffer.range(),
+        ));
+    };
+
+    let bytes = expect_lock_read(bytes);
+
+    let Ok(id) = Uuid::from_slice(&bytes[..*length]) else {
+        return Err(Error::invalid_arguments(
+            &format!("expected a 16-byte Buffer, found {length} bytes"),
+            buffer.range(),
+        ));
+    };
+
+    Ok(uuid_expr(id))
+}
+
+/// Returns the version number of a Uuid, None for the nil/max or unknown versions.
+/// ```tan
+/// (uuid/get-version (uuid/v7)) ; => 7
+/// ```
+pub fn uuid_get_version(args: &[Expr]) -> Result<Expr, Error> {
+    let id = unpack_uuid_arg(args, 0, "uuid")?;
+
+    match id.get_version() {
+        Some(version) if !id.is_nil() && !id.is_max() => Ok(Expr::Int(version as i64)),
+        _ => Ok(Expr::None),
+    }
+}
+
+// #insight
+// Milliseconds preserve the precision of v7 UUIDs, use `(/ ms 1000)` for the
+// unix timestamp accepted by `Date-Time`.
+/// Returns the embedded timestamp of a time-based Uuid (v1, v6, v7) in
+/// milliseconds since the unix epoch, None for other versions.
+/// ```tan
+/// (uuid/get-timestamp (uuid/v7)) ; => 1718000000000
+/// ```
+pub fn uuid_get_timestamp(args: &[Expr]) -> Result<Expr, Error> {
+    let id = unpack_uuid_arg(args, 0, "uuid")?;
+
+    let Some(timestamp) = id.get_timestamp() else {
+        return Ok(Expr::None);
+    };
+
+    let (seconds, nanos) = timestamp.to_unix();
+
+    Ok(Expr::Int(seconds as i64 * 1000 + nanos as i64 / 1_000_000))
 }
 
 pub fn import_lib_uuid(context: &mut Context) {
@@ -18,6 +183,123 @@ pub fn import_lib_uuid(context: &mut Context) {
 
     // #todo better name? construct-v4-uuid, or just v4-uuid.
     module.insert_invocable("make-v4-uuid", Expr::foreign_func(&uuid_new_v4));
+
+    module.insert_invocable("v3", Expr::foreign_func(&uuid_new_v3));
+    module.insert_invocable("v4", Expr::foreign_func(&uuid_new_v4));
+    module.insert_invocable("v5", Expr::foreign_func(&uuid_new_v5));
+    module.insert_invocable("v7", Expr::foreign_func(&uuid_new_v7));
+    module.insert_invocable("nil", Expr::foreign_func(&uuid_nil));
+
+    module.insert_invocable("parse", Expr::foreign_func(&uuid_parse));
+    module.insert_invocable("Uuid", Expr::foreign_func(&uuid_parse));
+    module.insert_invocable("valid?", Expr::foreign_func(&uuid_is_valid));
+
+    module.insert_invocable("to-buffer", Expr::foreign_func(&uuid_to_buffer));
+    module.insert_invocable("from-buffer", Expr::foreign_func(&uuid_from_buffer));
+
+    module.insert_invocable("get-version", Expr::foreign_func(&uuid_get_version));
+    module.insert_invocable("get-timestamp", Expr::foreign_func(&uuid_get_timestamp));
 }
 
 // #todo Add unit-tests (Tan).
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, RwLock},
+        time::{SystemTime, UNIX_EPOCH},
+    };
+
+    use tan::expr::Expr;
+
+    use crate::uuid::{
+        uuid_from_buffer, uuid_get_timestamp, uuid_get_version, uuid_is_valid, uuid_new_v3,
+        uuid_new_v5, uuid_new_v7, uuid_nil, uuid_parse, uuid_to_buffer,
+    };
+
+    fn key(s: &str) -> Expr {
+        Expr::KeySymbol(s.to_string())
+    }
+
+    #[test]
+    fn name_based_known_vectors() {
+        // #ref https://www.rfc-editor.org/rfc/rfc9562#name-example-of-a-uuidv3-value
+        let id = uuid_new_v3(&[key("dns"), Expr::string("www.example.com")]).unwrap();
+        assert_eq!(id.as_string(), Some("5df41881-3aed-3515-88a7-2f4a814cf09e"));
+
+        // #ref https://www.rfc-editor.org/rfc/rfc9562#name-example-of-a-uuidv5-value
+        let id = uuid_new_v5(&[key("dns"), Expr::string("www.example.com")]).unwrap();
+        assert_eq!(id.as_string(), Some("2ed6657d-e927-568b-95e1-2665a8aea6a2"));
+        assert_eq!(uuid_get_version(&[id]).unwrap().as_int(), Some(5));
+
+        // An explicit namespace is equivalent to the well-known name.
+        let namespace = Expr::string("6ba7b810-9dad-11d1-80b4-00c04fd430c8");
+        let id = uuid_new_v5(&[namespace, Expr::string("www.example.com")]).unwrap();
+        assert_eq!(id.as_string(), Some("2ed6657d-e927-568b-95e1-2665a8aea6a2"));
+
+        assert!(uuid_new_v5(&[key("isbn"), Expr::string("x")]).is_err());
+    }
+
+    #[test]
+    fn v7_timestamp_round_trip() {
+        // #ref https://www.rfc-editor.org/rfc/rfc9562#name-example-of-a-uuidv7-value
+        let id = Expr::string("017F22E2-79B0-7CC3-98C4-DC0C0C07398F");
+        let timestamp = uuid_get_timestamp(&[id]).unwrap();
+        assert_eq!(timestamp.as_int(), Some(1645557742000));
+
+        let before = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        let id = uuid_new_v7(&[]).unwrap();
+        let after = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+
+        assert_eq!(
+            uuid_get_version(std::slice::from_ref(&id))
+                .unwrap()
+                .as_int(),
+            Some(7)
+        );
+        let timestamp = uuid_get_timestamp(&[id]).unwrap().as_int().unwrap();
+        assert!(before.as_millis() as i64 <= timestamp);
+        assert!(timestamp <= after.as_millis() as i64);
+
+        let id = uuid_new_v5(&[key("dns"), Expr::string("tan")]).unwrap();
+        assert!(matches!(uuid_get_timestamp(&[id]).unwrap(), Expr::None));
+    }
+
+    #[test]
+    fn parse_and_buffer_round_trip() {
+        let id = uuid_parse(&[Expr::string("{67E55044-10B1-426F-9247-BB680E5FE0C8}")]).unwrap();
+        assert_eq!(id.as_string(), Some("67e55044-10b1-426f-9247-bb680e5fe0c8"));
+
+        let buffer = uuid_to_buffer(std::slice::from_ref(&id)).unwrap();
+        let Expr::Buffer(length, bytes) = buffer.unpack() else {
+            panic!("expected a Buffer");
+        };
+        assert_eq!(*length, 16);
+        assert_eq!(bytes.read().unwrap()[0], 0x67);
+
+        let round_trip = uuid_from_buffer(std::slice::from_ref(&buffer)).unwrap();
+        assert_eq!(round_trip.as_string(), id.as_string());
+
+        let short_buffer = Expr::Buffer(3, Arc::new(RwLock::new(vec![1, 2, 3])));
+        assert!(uuid_from_buffer(&[short_buffer]).is_err());
+
+        for valid in [
+            "67e55044-10b1-426f-9247-bb680e5fe0c8",
+            "67e5504410b1426f9247bb680e5fe0c8",
+            "urn:uuid:67e55044-10b1-426f-9247-bb680e5fe0c8",
+        ] {
+            let is_valid = uuid_is_valid(&[Expr::string(valid)]).unwrap();
+            assert_eq!(is_valid.as_bool(), Some(true));
+        }
+        let is_valid = uuid_is_valid(&[Expr::string("67e55044-10b1")]).unwrap();
+        assert_eq!(is_valid.as_bool(), Some(false));
+        assert!(uuid_parse(&[Expr::string("67e55044-10b1")]).is_err());
+
+        let nil = uuid_nil(&[]).unwrap();
+        assert_eq!(
+            nil.as_string(),
+            Some("00000000-0000-0000-0000-000000000000")
+        );
+        assert!(matches!(uuid_get_version(&[nil]).unwrap(), Expr::None));
+    }
+}
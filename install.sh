@@ -1,8 +1,17 @@
+pushd crates/lib-tan-archive; ./install.sh; popd
 pushd crates/lib-tan-chrono; ./install.sh; popd
+pushd crates/lib-tan-cli; ./install.sh; popd
 pushd crates/lib-tan-cmark; ./install.sh; popd
+pushd crates/lib-tan-codec-base32; ./install.sh; popd
+pushd crates/lib-tan-codec-base64; ./install.sh; popd
+pushd crates/lib-tan-codec-compress; ./install.sh; popd
+pushd crates/lib-tan-codec-hex; ./install.sh; popd
 pushd crates/lib-tan-codec-json; ./install.sh; popd
 pushd crates/lib-tan-codec-uri; ./install.sh; popd
 pushd crates/lib-tan-cron; ./install.sh; popd
+pushd crates/lib-tan-crypto-cipher; ./install.sh; popd
+pushd crates/lib-tan-crypto-hash; ./install.sh; popd
+pushd crates/lib-tan-crypto-password; ./install.sh; popd
 pushd crates/lib-tan-css-expr; ./install.sh; popd
 pushd crates/lib-tan-fs; ./install.sh; popd
 pushd crates/lib-tan-http-client; ./install.sh; popd